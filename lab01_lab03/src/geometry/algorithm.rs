@@ -0,0 +1,557 @@
+//! Common interface of the intersection algorithms and a registry of them.
+//!
+//! An algorithm is created for the input lines and options and computes the set of intersections
+//! together with statistics of the run. New algorithms implement [IntersectionAlgorithm] and are
+//! added to [Registry::default], which makes them available in all commands of the tool.
+use crate::geometry::analysis::{AnalysisOptions, InputReport};
+use crate::geometry::brute_force::handler::BruteForceHandler;
+use crate::geometry::counting::IntersectionCounts;
+use crate::geometry::external::handler::GeoHandler;
+use crate::geometry::grid::handler::GridHandler;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::rtree::handler::RTreeHandler;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+use crate::geometry::sweep_line::strips::StripHandler;
+use cpu_time::ProcessTime;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Options of all algorithms, algorithm specific options are ignored by the other algorithms.
+#[derive(Clone, Copy, Debug)]
+pub struct AlgorithmOptions {
+    /// Bichromatic mode: the first lines are red, the others blue and only intersections of red with
+    /// blue lines are reported
+    pub red_count: Option<usize>,
+    pub sweep_line: SweepLineOptions,
+    /// Number of threads of the algorithms supporting parallel execution, 1 runs sequentially
+    pub threads: usize,
+}
+
+impl Default for AlgorithmOptions {
+    fn default() -> Self {
+        AlgorithmOptions {
+            red_count: None,
+            sweep_line: SweepLineOptions::panic_disabled(),
+            threads: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Statistics {
+    pub lines: usize,
+    pub intersections: usize,
+    /// CPU time of the computation, without creating the algorithm
+    pub time: Duration,
+}
+
+pub struct IntersectionResult<T: Scalar> {
+    pub intersections: HashSet<Intersection<T>>,
+    pub statistics: Statistics,
+}
+
+impl<T: Scalar> IntersectionResult<T> {
+    /// Runs the computation and measures its CPU time.
+    pub fn measure(lines: usize, compute: impl FnOnce() -> HashSet<Intersection<T>>) -> Self {
+        let start = ProcessTime::try_now().expect("Getting process time failed");
+        let intersections = compute();
+        IntersectionResult {
+            statistics: Statistics {
+                lines,
+                intersections: intersections.len(),
+                time: start.elapsed(),
+            },
+            intersections,
+        }
+    }
+}
+
+pub struct CountResult {
+    pub counts: IntersectionCounts,
+    pub statistics: Statistics,
+}
+
+impl CountResult {
+    /// Runs the counting and measures its CPU time.
+    pub fn measure(lines: usize, count: impl FnOnce() -> IntersectionCounts) -> Self {
+        let start = ProcessTime::try_now().expect("Getting process time failed");
+        let counts = count();
+        CountResult {
+            statistics: Statistics {
+                lines,
+                intersections: counts.total(),
+                time: start.elapsed(),
+            },
+            counts,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AlgorithmError {
+    /// The algorithm doesn't support the options or the input
+    Unsupported(String),
+}
+
+impl Display for AlgorithmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgorithmError::Unsupported(reason) => write!(f, "Unsupported: {}", reason),
+        }
+    }
+}
+
+pub trait IntersectionAlgorithm<T: Scalar> {
+    /// Prepares the algorithm for the lines.
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError>
+    where
+        Self: Sized;
+
+    /// Computes the intersections of the lines.
+    fn intersect(&mut self) -> IntersectionResult<T>;
+
+    /// Counts the intersections of the lines by variant. Algorithms which can count without storing
+    /// the intersections override this, the others compute them.
+    fn count(&mut self) -> CountResult {
+        let result = self.intersect();
+        CountResult {
+            counts: IntersectionCounts::of(&result.intersections),
+            statistics: result.statistics,
+        }
+    }
+}
+
+/// Whether the lines at the indices i and j are intersected: all pairs, in the bichromatic mode only
+/// pairs of a red and a blue line.
+pub(crate) fn is_tested_pair(red_count: Option<usize>, i: usize, j: usize) -> bool {
+    match red_count {
+        Some(red_count) => (i < red_count) != (j < red_count),
+        None => true,
+    }
+}
+
+/// Splits the lines into the red and blue lines of the bichromatic mode.
+fn split_layers<T: Scalar>(
+    mut lines: Vec<Line2D<T>>,
+    red_count: usize,
+) -> (Vec<Line2D<T>>, Vec<Line2D<T>>) {
+    let blue = lines.split_off(red_count.min(lines.len()));
+    (lines, blue)
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for BruteForceHandler<T> {
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        Ok(match options.red_count {
+            Some(red_count) => {
+                let (red, blue) = split_layers(lines, red_count);
+                BruteForceHandler::new_bichromatic(red, blue)
+            }
+            None => BruteForceHandler::new(lines),
+        }
+        .with_threads(options.threads))
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+
+    fn count(&mut self) -> CountResult {
+        CountResult::measure(self.line_count(), || BruteForceHandler::count(self))
+    }
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for Handler<T> {
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        Ok(match options.red_count {
+            Some(red_count) => {
+                let (red, blue) = split_layers(lines, red_count);
+                Handler::new_bichromatic(red, blue, options.sweep_line)
+            }
+            None => Handler::new(lines, options.sweep_line),
+        })
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+    fn count(&mut self) -> CountResult {
+        CountResult::measure(self.line_count(), || Handler::count(self))
+    }
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for StripHandler<T> {
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        Ok(match options.red_count {
+            Some(red_count) => {
+                let (red, blue) = split_layers(lines, red_count);
+                StripHandler::new_bichromatic(red, blue, options.sweep_line)
+            }
+            None => StripHandler::new(lines, options.sweep_line),
+        }
+        .with_threads(options.threads))
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+
+    fn count(&mut self) -> CountResult {
+        CountResult::measure(self.line_count(), || StripHandler::count(self))
+    }
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for GeoHandler<T> {
+    /// Only supports f64, the intersection points of the geo library generally aren't representable
+    /// in other scalars.
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        if !T::IS_FLOAT {
+            return Err(AlgorithmError::Unsupported(format!(
+                "the geo library only computes with f64, not {}",
+                T::NAME
+            )));
+        }
+        Ok(GeoHandler::from_lines(lines, options.red_count))
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for GridHandler<T> {
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        Ok(match options.red_count {
+            Some(red_count) => {
+                let (red, blue) = split_layers(lines, red_count);
+                GridHandler::new_bichromatic(red, blue)
+            }
+            None => GridHandler::new(lines),
+        }
+        .with_threads(options.threads))
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+
+    fn count(&mut self) -> CountResult {
+        CountResult::measure(self.line_count(), || GridHandler::count(self))
+    }
+}
+
+impl<T: Scalar> IntersectionAlgorithm<T> for RTreeHandler<T> {
+    fn create(lines: Vec<Line2D<T>>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        Ok(match options.red_count {
+            Some(red_count) => {
+                let (red, blue) = split_layers(lines, red_count);
+                RTreeHandler::new_bichromatic(red, blue)
+            }
+            None => RTreeHandler::new(lines),
+        }
+        .with_threads(options.threads))
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<T> {
+        IntersectionResult::measure(self.line_count(), || self.run())
+    }
+
+    fn count(&mut self) -> CountResult {
+        CountResult::measure(self.line_count(), || RTreeHandler::count(self))
+    }
+}
+
+type Factory<T> = fn(
+    Vec<Line2D<T>>,
+    &AlgorithmOptions,
+) -> Result<Box<dyn IntersectionAlgorithm<T>>, AlgorithmError>;
+
+fn create_boxed<T: Scalar, A: IntersectionAlgorithm<T> + 'static>(
+    lines: Vec<Line2D<T>>,
+    options: &AlgorithmOptions,
+) -> Result<Box<dyn IntersectionAlgorithm<T>>, AlgorithmError> {
+    Ok(Box::new(A::create(lines, options)?))
+}
+
+pub struct RegisteredAlgorithm<T: Scalar> {
+    /// Name on the command line, without the leading `--`
+    pub flag: &'static str,
+    pub name: &'static str,
+    /// Adjusts the options to this variant of the algorithm
+    configure: fn(&mut AlgorithmOptions),
+    factory: Factory<T>,
+}
+
+impl<T: Scalar> RegisteredAlgorithm<T> {
+    /// The options adjusted to this variant of the algorithm, as it is created with them.
+    pub fn options(&self, options: &AlgorithmOptions) -> AlgorithmOptions {
+        let mut options = *options;
+        (self.configure)(&mut options);
+        options
+    }
+
+    pub fn create(
+        &self,
+        lines: Vec<Line2D<T>>,
+        options: &AlgorithmOptions,
+    ) -> Result<Box<dyn IntersectionAlgorithm<T>>, AlgorithmError> {
+        (self.factory)(lines, &self.options(options))
+    }
+}
+
+pub struct Registry<T: Scalar> {
+    algorithms: Vec<RegisteredAlgorithm<T>>,
+}
+
+impl<T: Scalar> Registry<T> {
+    pub fn new() -> Self {
+        Registry {
+            algorithms: Vec::new(),
+        }
+    }
+
+    /// Adds the algorithm, replacing an algorithm with the same flag.
+    pub fn register<A: IntersectionAlgorithm<T> + 'static>(
+        &mut self,
+        flag: &'static str,
+        name: &'static str,
+        configure: fn(&mut AlgorithmOptions),
+    ) {
+        self.algorithms.retain(|algorithm| algorithm.flag != flag);
+        self.algorithms.push(RegisteredAlgorithm {
+            flag,
+            name,
+            configure,
+            factory: create_boxed::<T, A>,
+        });
+    }
+
+    pub fn get(&self, flag: &str) -> Option<&RegisteredAlgorithm<T>> {
+        self.algorithms
+            .iter()
+            .find(|algorithm| algorithm.flag == flag)
+    }
+
+    pub fn algorithms(&self) -> &[RegisteredAlgorithm<T>] {
+        &self.algorithms
+    }
+}
+
+impl<T: Scalar> Default for Registry<T> {
+    /// Registers the algorithms of the crate, the first one is the default.
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        registry.register::<BruteForceHandler<T>>("brute-force", "Brute Force", |_| {});
+        registry.register::<Handler<T>>("sweep-line", "Sweep Line", |options| {
+            options.sweep_line = SweepLineOptions::panic_disabled()
+        });
+        registry.register::<StripHandler<T>>(
+            "sweep-line-degenerate",
+            "Sweep Line (Degenerate Handling)",
+            |options| options.sweep_line = SweepLineOptions::degenerate_handling(),
+        );
+        registry.register::<GeoHandler<T>>("external", "External (Geo Library)", |_| {});
+        registry.register::<GridHandler<T>>("grid", "Uniform Grid", |_| {});
+        registry.register::<RTreeHandler<T>>("rtree", "R-Tree (STR)", |_| {});
+        registry
+    }
+}
+
+/// Prints the quality report of the lines and their intersections with the default thresholds, see
+/// [InputReport].
+pub fn analyze<T: Scalar>(lines: &[Line2D<T>], intersections: &HashSet<Intersection<T>>) {
+    InputReport::new(lines, intersections, &AnalysisOptions::default())
+        .write_text(&mut std::io::stdout())
+        .expect("Writing the report failed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::generator;
+    use crate::geometry::line_segments::LineSegments2D;
+    use crate::geometry::point::Point2D;
+    use std::path::Path;
+
+    #[test]
+    fn registered_algorithms_should_find_the_intersections_of_the_brute_force() {
+        let registry = Registry::default();
+        for lines in [
+            LineSegments2D::from_dat(Path::new("data/s_1000_1.dat"))
+                .unwrap()
+                .lines,
+            LineSegments2D::from_dat(Path::new("data/s_1000_10.dat"))
+                .unwrap()
+                .lines,
+            generator::degenerate_lines(2000, 3),
+        ] {
+            let red_count = lines.len() / 2;
+            let (red, blue) = lines.split_at(red_count);
+            let bichromatic = AlgorithmOptions {
+                red_count: Some(red_count),
+                ..AlgorithmOptions::default()
+            };
+            for (options, expected) in [
+                (
+                    AlgorithmOptions::default(),
+                    BruteForceHandler::new(lines.clone()).run(),
+                ),
+                (
+                    bichromatic,
+                    BruteForceHandler::new_bichromatic(red.to_vec(), blue.to_vec()).run(),
+                ),
+            ] {
+                // the sweep line without degenerate handling rejects degenerate input
+                for algorithm in registry
+                    .algorithms()
+                    .iter()
+                    .filter(|algorithm| algorithm.flag != "sweep-line")
+                {
+                    let result = algorithm
+                        .create(lines.clone(), &options)
+                        .unwrap()
+                        .intersect();
+
+                    assert_eq!(result.statistics.lines, lines.len());
+                    assert_eq!(result.statistics.intersections, result.intersections.len());
+                    if algorithm.flag == "external" {
+                        // geo doesn't distinguish between the kinds of overlaps
+                        assert!(result.intersections.len() <= expected.len());
+                    } else {
+                        assert_eq!(result.intersections, expected, "{}", algorithm.flag);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn external_algorithm_should_only_support_floats() {
+        let lines: Vec<Line2D<i64>> = vec![
+            Line2D {
+                start: Point2D { x: 0, y: 0 },
+                end: Point2D { x: 1, y: 1 },
+            },
+            Line2D {
+                start: Point2D { x: 0, y: 1 },
+                end: Point2D { x: 1, y: 0 },
+            },
+        ];
+        let registry = Registry::default();
+
+        let result = registry
+            .get("external")
+            .unwrap()
+            .create(lines, &AlgorithmOptions::default());
+
+        assert!(matches!(result, Err(AlgorithmError::Unsupported(_))));
+    }
+
+    #[test]
+    fn parallel_algorithms_should_equal_the_sequential_ones() {
+        let lines = LineSegments2D::<ordered_float::OrderedFloat<f64>>::from_dat(Path::new(
+            "data/s_1000_10.dat",
+        ))
+        .unwrap()
+        .lines;
+        let registry = Registry::default();
+        let sequential = AlgorithmOptions::default();
+        let parallel = AlgorithmOptions {
+            threads: 4,
+            ..AlgorithmOptions::default()
+        };
+        let bichromatic = AlgorithmOptions {
+            red_count: Some(400),
+            threads: 3,
+            ..AlgorithmOptions::default()
+        };
+
+        for flag in ["brute-force", "sweep-line-degenerate", "grid", "rtree"] {
+            let algorithm = registry.get(flag).unwrap();
+            let run = |options: &AlgorithmOptions| {
+                algorithm
+                    .create(lines.clone(), options)
+                    .unwrap()
+                    .intersect()
+                    .intersections
+            };
+
+            assert_eq!(run(&parallel), run(&sequential), "{}", flag);
+            assert_eq!(
+                run(&bichromatic),
+                run(&AlgorithmOptions {
+                    threads: 1,
+                    ..bichromatic
+                }),
+                "{}",
+                flag
+            );
+        }
+    }
+
+    #[test]
+    fn counts_should_equal_the_intersections() {
+        // the generator's grid is 2^-10, so scaling by 2^10 keeps the intersections
+        let mut lines: Vec<Line2D<i64>> = generator::degenerate_lines(800, 3)
+            .iter()
+            .map(|line| {
+                let scaled = |point: &Point2D| Point2D {
+                    x: (point.x.0 * 1024.0) as i64,
+                    y: (point.y.0 * 1024.0) as i64,
+                };
+                Line2D {
+                    start: scaled(&line.start),
+                    end: scaled(&line.end),
+                }
+            })
+            .collect();
+        // copies within and across the layers of the bichromatic mode
+        lines.extend_from_within(..50);
+        lines.extend_from_within(350..450);
+        let registry = Registry::default();
+
+        for options in [
+            AlgorithmOptions::default(),
+            AlgorithmOptions {
+                red_count: Some(400),
+                threads: 2,
+                ..AlgorithmOptions::default()
+            },
+        ] {
+            for flag in ["brute-force", "sweep-line-degenerate", "grid", "rtree"] {
+                let algorithm = registry.get(flag).unwrap();
+                let intersections = algorithm
+                    .create(lines.clone(), &options)
+                    .unwrap()
+                    .intersect()
+                    .intersections;
+
+                let result = algorithm.create(lines.clone(), &options).unwrap().count();
+
+                assert_eq!(
+                    result.counts,
+                    IntersectionCounts::of(&intersections),
+                    "{}",
+                    flag
+                );
+                assert_eq!(result.statistics.intersections, intersections.len());
+            }
+        }
+    }
+
+    #[test]
+    fn registry_should_replace_algorithms_with_the_same_flag() {
+        let mut registry: Registry<i64> = Registry::default();
+        let count = registry.algorithms().len();
+
+        registry.register::<Handler<i64>>("brute-force", "Sweep Line", |options| {
+            options.sweep_line = SweepLineOptions::degenerate_handling()
+        });
+
+        assert_eq!(registry.algorithms().len(), count);
+        assert_eq!(registry.get("brute-force").unwrap().name, "Sweep Line");
+        assert!(registry.get("unknown").is_none());
+    }
+}
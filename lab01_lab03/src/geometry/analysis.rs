@@ -0,0 +1,656 @@
+//! Quality report of an input: degenerate and suspicious lines, the coordinates, the intersections
+//! found by an algorithm and which sweep line options handle the input correctly.
+//!
+//! Near pairs are lines which don't intersect exactly but come closer than
+//! [AnalysisOptions::near_distance]; with floats, rounding of the input may have separated or joined
+//! them. They are found with an R-tree over the boxes of the lines, grown by the distance, and
+//! measured in floats. Clusters of shared x-coordinates are x-coordinates of several endpoints or
+//! crossing points, which the sweep line without degenerate handling rejects.
+use crate::geometry::algorithm::{AlgorithmOptions, Registry};
+use crate::geometry::bounding_box::BoundingBox;
+use crate::geometry::counting::IntersectionCounts;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::rtree::handler::RTreeHandler;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::SweepLineOptions;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+use ordered_float::OrderedFloat;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+
+/// Thresholds of the report, in the units of the coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct AnalysisOptions {
+    /// Lines shorter than this, but not of zero length, are reported as short
+    pub min_length: f64,
+    /// Lines closer than this which don't intersect are reported as near pairs
+    pub near_distance: f64,
+    /// Near pairs whose directions differ by less than this angle in radians are near-parallel
+    pub parallel_angle: f64,
+    /// Number of the largest clusters of shared x-coordinates listed
+    pub max_clusters: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            min_length: 1e-6,
+            near_distance: 1e-6,
+            parallel_angle: 1e-3,
+            max_clusters: 5,
+        }
+    }
+}
+
+/// Range and precision of the coordinates, converted to floats.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoordinateStatistics {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub distinct_x: usize,
+    pub distinct_y: usize,
+    /// Smallest distance between distinct x-coordinates, None with less than two
+    pub min_x_gap: Option<f64>,
+    pub min_y_gap: Option<f64>,
+    /// Whether all coordinates are integers, so `--i64` can read the input
+    pub integer: bool,
+    /// Largest number of decimal places of a coordinate, None if the decimal expansion of a
+    /// coordinate doesn't terminate, e.g. `1/3`
+    pub decimal_places: Option<usize>,
+    /// Whether the decimal values of all coordinates are exactly representable as floats, e.g. not
+    /// `3.18`, which is read as the nearest float
+    pub exact_f64: bool,
+}
+
+/// x-coordinate shared by several endpoints or crossing points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XCluster {
+    /// Exact x-coordinate
+    pub x: String,
+    pub endpoints: usize,
+    pub crossings: usize,
+}
+
+impl XCluster {
+    pub fn size(&self) -> usize {
+        self.endpoints + self.crossings
+    }
+}
+
+/// Sweep line variant which computes the intersections of the input correctly.
+#[derive(Clone, Debug)]
+pub struct Recommendation {
+    /// Algorithm flag on the command line
+    pub flag: &'static str,
+    pub options: SweepLineOptions,
+    /// Whether floats may misclassify near pairs, so an exact coordinate type should be used
+    pub exact_coordinates: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InputReport {
+    pub lines: usize,
+    pub intersections: IntersectionCounts,
+    pub zero_length: usize,
+    pub vertical: usize,
+    /// Lines equal to an earlier line, also with swapped endpoints
+    pub duplicates: usize,
+    /// Lines shorter than [AnalysisOptions::min_length]
+    pub short: usize,
+    /// Pairs of lines which don't intersect, but are closer than [AnalysisOptions::near_distance]
+    pub near_pairs: usize,
+    /// Near pairs which are also near-parallel
+    pub near_parallel: usize,
+    /// None without lines
+    pub coordinates: Option<CoordinateStatistics>,
+    /// Number of x-coordinates shared by several endpoints or crossing points
+    pub shared_x: usize,
+    /// Largest clusters of shared x-coordinates, largest first
+    pub clusters: Vec<XCluster>,
+    pub recommendation: Recommendation,
+}
+
+impl InputReport {
+    /// Analyzes the lines and the intersections an algorithm found for them.
+    pub fn new<T: Scalar>(
+        lines: &[Line2D<T>],
+        intersections: &HashSet<Intersection<T>>,
+        options: &AnalysisOptions,
+    ) -> Self {
+        let mut zero_length = 0;
+        let mut vertical = 0;
+        let mut short = 0;
+        let mut seen = HashSet::new();
+        let mut duplicates = 0;
+        for line in lines {
+            if line.is_zero_length() {
+                zero_length += 1;
+            } else if line.get_length() < options.min_length {
+                short += 1;
+            }
+            if line.is_vertical() {
+                vertical += 1;
+            }
+            let (start, end) = line.sorted_endpoints();
+            if !seen.insert((start, end)) {
+                duplicates += 1;
+            }
+        }
+
+        let (shared_x, clusters) = x_clusters(lines, intersections, options.max_clusters);
+        let (near_pairs, near_parallel) = near_pairs(lines, options);
+        let mut report = InputReport {
+            lines: lines.len(),
+            intersections: IntersectionCounts::of(intersections),
+            zero_length,
+            vertical,
+            duplicates,
+            short,
+            near_pairs,
+            near_parallel,
+            coordinates: coordinate_statistics(lines),
+            shared_x,
+            clusters,
+            // replaced by the recommendation for the counts below
+            recommendation: Recommendation {
+                flag: "sweep-line-degenerate",
+                options: SweepLineOptions::degenerate_handling(),
+                exact_coordinates: false,
+                reasons: Vec::new(),
+            },
+        };
+        report.recommendation = report.recommend::<T>();
+        report
+    }
+
+    /// The sweep line without degenerate handling is only correct without any degeneracy. The
+    /// options are the ones the registry creates the recommended algorithm with.
+    fn recommend<T: Scalar>(&self) -> Recommendation {
+        let counts = &self.intersections;
+        let overlaps = counts.partial_overlap + counts.contained_overlap + counts.identical_overlap;
+        let mut reasons: Vec<String> = [
+            (self.zero_length, "zero length lines"),
+            (self.vertical, "vertical lines"),
+            (self.shared_x, "shared x-coordinates"),
+            (counts.touching, "touching lines"),
+            (overlaps, "overlapping lines"),
+        ]
+        .into_iter()
+        .filter(|&(count, _)| count > 0)
+        .map(|(count, kind)| format!("{} {}", count, kind))
+        .collect();
+        let degenerate = !reasons.is_empty();
+
+        let exact_coordinates = T::IS_FLOAT && self.near_pairs > 0;
+        if exact_coordinates {
+            reasons.push(format!(
+                "{} near pairs of lines, which floats may misclassify: use --i64 or --rational",
+                self.near_pairs
+            ));
+        }
+        if self.short > 0 {
+            reasons.push(format!(
+                "{} lines shorter than the threshold, which may be rounding artifacts",
+                self.short
+            ));
+        }
+
+        let flag = match degenerate {
+            true => "sweep-line-degenerate",
+            false => "sweep-line",
+        };
+        let options = Registry::<T>::default()
+            .get(flag)
+            .expect("The sweep lines are registered")
+            .options(&AlgorithmOptions::default());
+        Recommendation {
+            flag,
+            options: options.sweep_line,
+            exact_coordinates,
+            reasons,
+        }
+    }
+
+    pub fn write_text<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Analysis:")?;
+        writeln!(writer, "Analyzed {} lines", self.lines)?;
+        writeln!(writer, "Found {} intersections", self.intersections.total())?;
+        for (variant, count) in self.intersections.by_variant() {
+            writeln!(writer, "  {}: {}", variant, count)?;
+        }
+        writeln!(writer, "Number of zero length lines: {}", self.zero_length)?;
+        writeln!(writer, "Number of short lines: {}", self.short)?;
+        writeln!(writer, "Number of vertical lines: {}", self.vertical)?;
+        writeln!(writer, "Number of duplicated lines: {}", self.duplicates)?;
+        writeln!(
+            writer,
+            "Number of near pairs: {} ({} near-parallel)",
+            self.near_pairs, self.near_parallel
+        )?;
+        if let Some(coordinates) = &self.coordinates {
+            writeln!(
+                writer,
+                "Coordinate range: x {} to {}, y {} to {}",
+                coordinates.x_min, coordinates.x_max, coordinates.y_min, coordinates.y_max
+            )?;
+            let gap = |gap: Option<f64>| gap.map_or("-".to_string(), |gap| gap.to_string());
+            writeln!(
+                writer,
+                "Distinct coordinates: {} x (smallest gap {}), {} y (smallest gap {})",
+                coordinates.distinct_x,
+                gap(coordinates.min_x_gap),
+                coordinates.distinct_y,
+                gap(coordinates.min_y_gap)
+            )?;
+            writeln!(
+                writer,
+                "Precision: {} decimal places, integer: {}, exact as f64: {}",
+                coordinates
+                    .decimal_places
+                    .map_or("non-terminating".to_string(), |places| places.to_string()),
+                coordinates.integer,
+                coordinates.exact_f64
+            )?;
+        }
+        writeln!(
+            writer,
+            "Number of shared x-coordinates among endpoints and crossings: {}",
+            self.shared_x
+        )?;
+        for cluster in &self.clusters {
+            writeln!(
+                writer,
+                "  x = {}: {} endpoints, {} crossings",
+                cluster.x, cluster.endpoints, cluster.crossings
+            )?;
+        }
+        writeln!(writer, "Recommendation: --{}", self.recommendation.flag)?;
+        for reason in &self.recommendation.reasons {
+            writeln!(writer, "  {}", reason)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Value {
+        let counts: serde_json::Map<String, Value> = self
+            .intersections
+            .by_variant()
+            .iter()
+            .map(|(variant, count)| (variant.to_string(), json!(count)))
+            .collect();
+        let options = &self.recommendation.options;
+        json!({
+            "lines": self.lines,
+            "intersections": self.intersections.total(),
+            "variants": counts,
+            "zero_length": self.zero_length,
+            "short": self.short,
+            "vertical": self.vertical,
+            "duplicates": self.duplicates,
+            "near_pairs": self.near_pairs,
+            "near_parallel": self.near_parallel,
+            "coordinates": self.coordinates.as_ref().map(|c| json!({
+                "x_min": c.x_min,
+                "x_max": c.x_max,
+                "y_min": c.y_min,
+                "y_max": c.y_max,
+                "distinct_x": c.distinct_x,
+                "distinct_y": c.distinct_y,
+                "min_x_gap": c.min_x_gap,
+                "min_y_gap": c.min_y_gap,
+                "integer": c.integer,
+                "decimal_places": c.decimal_places,
+                "exact_f64": c.exact_f64,
+            })),
+            "shared_x": self.shared_x,
+            "clusters": self.clusters.iter().map(|cluster| json!({
+                "x": cluster.x,
+                "endpoints": cluster.endpoints,
+                "crossings": cluster.crossings,
+            })).collect::<Vec<Value>>(),
+            "recommendation": {
+                "algorithm": self.recommendation.flag,
+                "exact_coordinates": self.recommendation.exact_coordinates,
+                "options": {
+                    "panic_on_identical_x": options.panic_on_identical_x,
+                    "panic_on_vertical": options.panic_on_vertical,
+                    "panic_on_zero_length": options.panic_on_zero_length,
+                    "panic_on_overlap": options.panic_on_overlap,
+                    "panic_on_touch": options.panic_on_touch,
+                    "handle_degenerate": options.handle_degenerate,
+                },
+                "reasons": self.recommendation.reasons,
+            },
+        })
+    }
+
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, &self.to_json())?;
+        writeln!(writer)
+    }
+}
+
+fn coordinate_statistics<T: Scalar>(lines: &[Line2D<T>]) -> Option<CoordinateStatistics> {
+    if lines.is_empty() {
+        return None;
+    }
+    let xs: Vec<&T> = lines
+        .iter()
+        .flat_map(|line| [&line.start.x, &line.end.x])
+        .collect();
+    let ys: Vec<&T> = lines
+        .iter()
+        .flat_map(|line| [&line.start.y, &line.end.y])
+        .collect();
+    let (distinct_x, min_x_gap) = distinct_values(&xs);
+    let (distinct_y, min_y_gap) = distinct_values(&ys);
+
+    // the coordinates as written, floats by their shortest decimal text, which reads back the same
+    let values: Vec<Option<BigRational>> = xs
+        .iter()
+        .chain(&ys)
+        .map(|c| BigRational::parse(&c.to_string()))
+        .collect();
+    let integer = values
+        .iter()
+        .all(|value| value.as_ref().is_some_and(BigRational::is_integer));
+    let decimal_places = values
+        .iter()
+        .map(|value| value.as_ref().and_then(decimal_places))
+        .try_fold(0, |places, value| value.map(|value| places.max(value)));
+    let exact_f64 = values.iter().all(|value| {
+        value
+            .as_ref()
+            .is_some_and(|value| BigRational::from_f64(value.to_f64()).as_ref() == Some(value))
+    });
+    let min = |values: &[&T]| values.iter().min().map_or(0.0, |v| v.to_f64());
+    let max = |values: &[&T]| values.iter().max().map_or(0.0, |v| v.to_f64());
+
+    Some(CoordinateStatistics {
+        x_min: min(&xs),
+        x_max: max(&xs),
+        y_min: min(&ys),
+        y_max: max(&ys),
+        distinct_x,
+        distinct_y,
+        min_x_gap,
+        min_y_gap,
+        integer,
+        decimal_places,
+        exact_f64,
+    })
+}
+
+/// Number of decimal places of the value, None if its decimal expansion doesn't terminate, which
+/// is the case if its denominator has other prime factors than 2 and 5.
+fn decimal_places(value: &BigRational) -> Option<usize> {
+    let mut denominator = value.denom().clone();
+    let mut factors = [0, 0];
+    for (factor, count) in [2u32, 5].into_iter().zip(&mut factors) {
+        let factor = BigInt::from(factor);
+        while (&denominator % &factor).is_zero() {
+            denominator /= &factor;
+            *count += 1;
+        }
+    }
+    denominator.is_one().then(|| factors[0].max(factors[1]))
+}
+
+/// Number of distinct values and the smallest distance between them.
+fn distinct_values<T: Scalar>(values: &[&T]) -> (usize, Option<f64>) {
+    let mut values = values.to_vec();
+    values.sort();
+    values.dedup();
+    let gap = values
+        .windows(2)
+        .map(|pair| pair[1].to_f64() - pair[0].to_f64())
+        .min_by(f64::total_cmp);
+    (values.len(), gap)
+}
+
+/// Number of shared x-coordinates and the largest clusters.
+fn x_clusters<T: Scalar>(
+    lines: &[Line2D<T>],
+    intersections: &HashSet<Intersection<T>>,
+    max_clusters: usize,
+) -> (usize, Vec<XCluster>) {
+    // endpoints and crossings at each x
+    let mut events: BTreeMap<T::Field, (usize, usize)> = BTreeMap::new();
+    for line in lines {
+        events.entry(line.start.x.to_field()).or_default().0 += 1;
+        if line.end.x != line.start.x {
+            events.entry(line.end.x.to_field()).or_default().0 += 1;
+        }
+    }
+    for intersection in intersections {
+        // touching points are endpoints
+        if let Intersection::Crossing { point, .. } = intersection {
+            events.entry(point.x.clone()).or_default().1 += 1;
+        }
+    }
+
+    let mut clusters: Vec<XCluster> = events
+        .into_iter()
+        .filter(|(_, (endpoints, crossings))| endpoints + crossings > 1)
+        .map(|(x, (endpoints, crossings))| XCluster {
+            x: x.to_string(),
+            endpoints,
+            crossings,
+        })
+        .collect();
+    let shared = clusters.len();
+    // stable, so clusters of equal size stay ordered by x
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.size()));
+    clusters.truncate(max_clusters);
+    (shared, clusters)
+}
+
+/// Number of near pairs and of the near-parallel ones among them.
+fn near_pairs<T: Scalar>(lines: &[Line2D<T>], options: &AnalysisOptions) -> (usize, usize) {
+    let floats: Vec<Line2D<OrderedFloat<f64>>> = lines
+        .iter()
+        .map(|line| {
+            Line2D::new(
+                line.start.x.to_f64(),
+                line.start.y.to_f64(),
+                line.end.x.to_f64(),
+                line.end.y.to_f64(),
+            )
+        })
+        .collect();
+    let tree = RTreeHandler::new(floats.clone());
+    let distance = options.near_distance;
+    let max_sine = options.parallel_angle.sin();
+
+    let (mut near, mut parallel) = (0, 0);
+    for (i, line) in floats.iter().enumerate() {
+        let bounds = BoundingBox::of(line);
+        let grown = BoundingBox {
+            x_min: bounds.x_min - distance,
+            y_min: bounds.y_min - distance,
+            x_max: bounds.x_max + distance,
+            y_max: bounds.y_max + distance,
+        };
+        for j in tree.query(&grown) {
+            if j <= i
+                || segment_distance(line, &floats[j]) > distance
+                || lines[i].intersects(lines[j].clone())
+            {
+                continue;
+            }
+            near += 1;
+            if direction_sine(line, &floats[j]).is_some_and(|sine| sine <= max_sine) {
+                parallel += 1;
+            }
+        }
+    }
+    (near, parallel)
+}
+
+type Vector = (f64, f64);
+
+fn vector(line: &Line2D<OrderedFloat<f64>>) -> Vector {
+    (line.end.x.0 - line.start.x.0, line.end.y.0 - line.start.y.0)
+}
+
+/// Sine of the angle between the lines, None for zero length lines.
+fn direction_sine(a: &Line2D<OrderedFloat<f64>>, b: &Line2D<OrderedFloat<f64>>) -> Option<f64> {
+    let (u, v) = (vector(a), vector(b));
+    let lengths = u.0.hypot(u.1) * v.0.hypot(v.1);
+    (lengths > 0.0).then(|| (u.0 * v.1 - u.1 * v.0).abs() / lengths)
+}
+
+/// Distance of lines which don't cross: the smallest distance of an endpoint to the other line.
+fn segment_distance(a: &Line2D<OrderedFloat<f64>>, b: &Line2D<OrderedFloat<f64>>) -> f64 {
+    [
+        point_distance((a.start.x.0, a.start.y.0), b),
+        point_distance((a.end.x.0, a.end.y.0), b),
+        point_distance((b.start.x.0, b.start.y.0), a),
+        point_distance((b.end.x.0, b.end.y.0), a),
+    ]
+    .into_iter()
+    .fold(f64::INFINITY, f64::min)
+}
+
+fn point_distance(point: Vector, line: &Line2D<OrderedFloat<f64>>) -> f64 {
+    let (dx, dy) = vector(line);
+    let (px, py) = (point.0 - line.start.x.0, point.1 - line.start.y.0);
+    let squared_length = dx * dx + dy * dy;
+    let t = match squared_length > 0.0 {
+        true => ((px * dx + py * dy) / squared_length).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    (px - t * dx).hypot(py - t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::point::Point2D;
+
+    fn report(lines: Vec<Line2D>) -> InputReport {
+        let intersections = BruteForceHandler::new(lines.clone()).run();
+        InputReport::new(&lines, &intersections, &AnalysisOptions::default())
+    }
+
+    #[test]
+    fn degenerate_input_should_be_reported() {
+        let report = report(vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(2.0, 2.0, 0.0, 0.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+            Line2D::new(5.0, 0.0, 5.0, 1.0),
+            Line2D::new(3.0, 3.0, 3.0, 3.0),
+            Line2D::new(6.0, 0.0, 6.0000000001, 0.0),
+            // 1e-7 below the first line, nearly parallel
+            Line2D::new(0.5, 0.4999999, 1.5, 1.4999999),
+        ]);
+
+        assert_eq!(report.lines, 7);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.zero_length, 1);
+        assert_eq!(report.short, 1);
+        // the zero length line is also vertical
+        assert_eq!(report.vertical, 2);
+        // the copies of the first line are both near the last line
+        assert_eq!((report.near_pairs, report.near_parallel), (2, 2));
+        // the reversed copy is an identical overlap
+        assert_eq!(report.intersections.identical_overlap, 1);
+        // x = 0 and x = 2 are shared by the copies and the crossing line, x = 1 by the crossings of
+        // the copies
+        assert_eq!(report.shared_x, 3);
+        assert_eq!(report.clusters[0].x, "0");
+        assert_eq!(report.clusters[0].size(), 3);
+        assert_eq!(report.recommendation.flag, "sweep-line-degenerate");
+        assert_eq!(
+            report.recommendation.options,
+            SweepLineOptions::degenerate_handling()
+        );
+        assert!(report.recommendation.exact_coordinates);
+    }
+
+    #[test]
+    fn general_position_should_be_safe_for_the_sweep_line() {
+        let report = report(vec![
+            Line2D::new(0.0, 0.0, 4.0, 4.0),
+            Line2D::new(1.0, 3.0, 3.0, 0.5),
+            Line2D::new(5.0, 0.0, 6.0, 0.25),
+        ]);
+
+        assert_eq!(report.intersections.crossing, 1);
+        assert_eq!(report.shared_x, 0);
+        assert_eq!(report.near_pairs, 0);
+        assert_eq!(report.recommendation.flag, "sweep-line");
+        // the options of the registered sweep line, which doesn't panic on shared x-coordinates
+        assert_eq!(
+            report.recommendation.options,
+            SweepLineOptions::panic_disabled()
+        );
+        let registered = Registry::<OrderedFloat<f64>>::default()
+            .get("sweep-line")
+            .unwrap()
+            .options(&AlgorithmOptions::default());
+        assert_eq!(report.recommendation.options, registered.sweep_line);
+        let coordinates = report.coordinates.unwrap();
+        assert_eq!(coordinates.decimal_places, Some(2));
+        assert_eq!(coordinates.min_x_gap, Some(1.0));
+        assert!(!coordinates.integer && coordinates.exact_f64);
+    }
+
+    #[test]
+    fn precision_should_be_derived_from_the_decimal_values() {
+        let decimal = coordinate_statistics(&[Line2D::new(0.0, 0.5, 3.18, 1.0)]).unwrap();
+        assert_eq!(decimal.decimal_places, Some(2));
+        assert!(!decimal.exact_f64 && !decimal.integer);
+
+        let integer = coordinate_statistics(&[Line2D {
+            start: Point2D { x: 0i64, y: 1 },
+            end: Point2D { x: 1 << 60, y: -3 },
+        }])
+        .unwrap();
+        assert_eq!(integer.decimal_places, Some(0));
+        assert!(integer.integer && integer.exact_f64);
+
+        let third = BigRational::new(BigInt::from(1), BigInt::from(3));
+        let zero = BigRational::zero();
+        let rational = coordinate_statistics(&[Line2D {
+            start: Point2D {
+                x: zero.clone(),
+                y: zero.clone(),
+            },
+            end: Point2D { x: third, y: zero },
+        }])
+        .unwrap();
+        assert_eq!(rational.decimal_places, None);
+        assert!(!rational.exact_f64);
+    }
+
+    #[test]
+    fn report_should_be_written_as_text_and_json() {
+        let report = report(vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+        ]);
+        let mut text = Vec::new();
+        let mut json = Vec::new();
+
+        report.write_text(&mut text).unwrap();
+        report.write_json(&mut json).unwrap();
+
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Found 1 intersections"));
+        assert!(text.contains("Recommendation: --sweep-line-degenerate"));
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["variants"]["Crossing"], 1);
+        assert_eq!(json["shared_x"], 2);
+        assert_eq!(json["recommendation"]["options"]["handle_degenerate"], true);
+    }
+}
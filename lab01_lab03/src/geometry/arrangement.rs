@@ -0,0 +1,584 @@
+//! Planar arrangement of line segments as doubly connected edge list (DCEL).
+//!
+//! Every line is split at its intersection points and at the endpoints of its overlaps with other lines.
+//! The resulting edges are stored as pairs of twin half-edges, each of them bounding the face on its left.
+//! Bounded faces are traversed counterclockwise, the boundaries of holes and of the unbounded face clockwise.
+//!
+//! Vertices are expressed in the [Scalar::Field] of the scalar, so the arrangement is exact for exact scalars.
+//! With floating point coordinates, crossing points are rounded and overlapping lines may be split at slightly
+//! different points, [crate::geometry::snap_rounding] the lines first in this case.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::predicates::{self, Orientation};
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct Vertex<F> {
+    pub point: Point2D<F>,
+    /// outgoing half-edges in counterclockwise order
+    pub outgoing: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HalfEdge {
+    pub origin: usize,
+    pub twin: usize,
+    pub next: usize,
+    pub prev: usize,
+    /// face on the left side of the half-edge
+    pub face: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Face {
+    /// a half-edge of the outer boundary, None for the unbounded face
+    pub outer_component: Option<usize>,
+    /// a half-edge of each hole
+    pub inner_components: Vec<usize>,
+}
+
+pub struct Arrangement<T: Scalar = OrderedFloat<f64>> {
+    vertices: Vec<Vertex<T::Field>>,
+    half_edges: Vec<HalfEdge>,
+    faces: Vec<Face>,
+}
+
+/// Index of the unbounded face
+pub const UNBOUNDED_FACE: usize = 0;
+
+impl<T: Scalar> Arrangement<T> {
+    /// Builds the arrangement of the lines, the intersections are computed with the sweep line.
+    pub fn from_lines(lines: &[Line2D<T>]) -> Self {
+        let intersections =
+            Handler::new(lines.to_vec(), SweepLineOptions::degenerate_handling()).run();
+        Self::new(lines, &intersections)
+    }
+
+    /// Builds the arrangement of the lines from all of their intersections.
+    ///
+    /// Complexity: O((n + k) log(n + k) + h * e) with h the number of holes and e the number of edges
+    pub fn new(lines: &[Line2D<T>], intersections: &HashSet<Intersection<T>>) -> Self {
+        let mut arrangement = Arrangement {
+            vertices: Vec::new(),
+            half_edges: Vec::new(),
+            faces: vec![Face::default()],
+        };
+
+        let split_points = Self::split_points(lines, intersections);
+        let mut vertex_ids: BTreeMap<Point2D<T::Field>, usize> = BTreeMap::new();
+        let mut edges: HashSet<(usize, usize)> = HashSet::new();
+        for line in lines {
+            let mut points = split_points.get(line).cloned().unwrap_or_default();
+            points.push(line.start.to_field());
+            points.push(line.end.to_field());
+            // the points lie on the line, so ordering them by x and y orders them along the line
+            points.sort();
+            points.dedup();
+
+            let ids: Vec<usize> = points
+                .into_iter()
+                .map(|point| arrangement.vertex_id(&mut vertex_ids, point))
+                .collect();
+            for pair in ids.windows(2) {
+                if edges.insert((pair[0].min(pair[1]), pair[0].max(pair[1]))) {
+                    arrangement.add_edge(pair[0], pair[1]);
+                }
+            }
+        }
+
+        arrangement.link_half_edges();
+        arrangement.build_faces();
+        arrangement
+    }
+
+    pub fn vertices(&self) -> &[Vertex<T::Field>] {
+        &self.vertices
+    }
+
+    pub fn half_edges(&self) -> &[HalfEdge] {
+        &self.half_edges
+    }
+
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+
+    /// Number of edges, each of them consists of two half-edges.
+    pub fn edge_count(&self) -> usize {
+        self.half_edges.len() / 2
+    }
+
+    pub fn vertex_degree(&self, vertex: usize) -> usize {
+        self.vertices[vertex].outgoing.len()
+    }
+
+    /// Indices of the bounded faces.
+    pub fn bounded_faces(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.faces.len()).filter(|&face| self.faces[face].outer_component.is_some())
+    }
+
+    /// Half-edges of the cycle starting with the half-edge.
+    pub fn cycle(&self, half_edge: usize) -> Vec<usize> {
+        let mut cycle = vec![half_edge];
+        let mut current = self.half_edges[half_edge].next;
+        while current != half_edge {
+            cycle.push(current);
+            current = self.half_edges[current].next;
+        }
+        cycle
+    }
+
+    /// Vertices of the outer boundary of the face in counterclockwise order, empty for the unbounded face.
+    pub fn face_boundary(&self, face: usize) -> Vec<&Point2D<T::Field>> {
+        match self.faces[face].outer_component {
+            Some(half_edge) => self
+                .cycle(half_edge)
+                .into_iter()
+                .map(|h| &self.vertices[self.half_edges[h].origin].point)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Area of the face without its holes, None for the unbounded face.
+    pub fn face_area(&self, face: usize) -> Option<T::Field> {
+        let outer = self.faces[face].outer_component?;
+        // holes are oriented clockwise, so their signed area is negative
+        Some(
+            self.faces[face]
+                .inner_components
+                .iter()
+                .fold(self.signed_area(outer), |area, &hole| {
+                    area + self.signed_area(hole)
+                }),
+        )
+    }
+
+    /// Signed area of the cycle of the half-edge, positive if it is oriented counterclockwise.
+    pub fn signed_area(&self, half_edge: usize) -> T::Field {
+        let twice_area = self
+            .cycle(half_edge)
+            .into_iter()
+            .fold(field::<T>(0.0), |sum, h| {
+                let start = &self.vertices[self.half_edges[h].origin].point;
+                let end = &self.vertices[self.half_edges[self.half_edges[h].twin].origin].point;
+                sum + start.x.clone() * end.y.clone() - end.x.clone() * start.y.clone()
+            });
+        twice_area / field::<T>(2.0)
+    }
+
+    /// Collects the points of the intersections on each line.
+    fn split_points(
+        lines: &[Line2D<T>],
+        intersections: &HashSet<Intersection<T>>,
+    ) -> HashMap<Line2D<T>, Vec<Point2D<T::Field>>> {
+        let mut split_points: HashMap<Line2D<T>, Vec<Point2D<T::Field>>> = lines
+            .iter()
+            .map(|line| (line.clone(), Vec::new()))
+            .collect();
+        let mut add = |line: &Line2D<T>, point: Point2D<T::Field>| {
+            if let Some(points) = split_points.get_mut(line) {
+                points.push(point);
+            }
+        };
+        for intersection in intersections {
+            match intersection {
+                Intersection::Crossing {
+                    line1,
+                    line2,
+                    point,
+                }
+                | Intersection::Touching {
+                    line1,
+                    line2,
+                    point,
+                } => {
+                    add(line1, point.clone());
+                    add(line2, point.clone());
+                }
+                Intersection::PartialOverlap {
+                    line1,
+                    line2,
+                    overlap,
+                }
+                | Intersection::ContainedOverlap {
+                    line1,
+                    line2,
+                    overlap,
+                }
+                | Intersection::IdenticalOverlap {
+                    line1,
+                    line2,
+                    overlap,
+                } => {
+                    for point in [overlap.start.to_field(), overlap.end.to_field()] {
+                        add(line1, point.clone());
+                        add(line2, point);
+                    }
+                }
+            }
+        }
+        split_points
+    }
+
+    fn vertex_id(
+        &mut self,
+        vertex_ids: &mut BTreeMap<Point2D<T::Field>, usize>,
+        point: Point2D<T::Field>,
+    ) -> usize {
+        let next_id = self.vertices.len();
+        *vertex_ids.entry(point.clone()).or_insert_with(|| {
+            self.vertices.push(Vertex {
+                point,
+                outgoing: Vec::new(),
+            });
+            next_id
+        })
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        let id = self.half_edges.len();
+        for (origin, twin) in [(from, id + 1), (to, id)] {
+            self.vertices[origin].outgoing.push(self.half_edges.len());
+            self.half_edges.push(HalfEdge {
+                origin,
+                twin,
+                next: usize::MAX,
+                prev: usize::MAX,
+                face: UNBOUNDED_FACE,
+            });
+        }
+    }
+
+    /// Sorts the outgoing half-edges of every vertex counterclockwise and links each incoming half-edge
+    /// to the outgoing half-edge directly clockwise of its twin.
+    fn link_half_edges(&mut self) {
+        for vertex in 0..self.vertices.len() {
+            let mut outgoing = std::mem::take(&mut self.vertices[vertex].outgoing);
+            outgoing.sort_by(|&a, &b| self.compare_angles(a, b));
+            self.vertices[vertex].outgoing = outgoing;
+        }
+
+        for vertex in &self.vertices {
+            let count = vertex.outgoing.len();
+            for (i, &outgoing) in vertex.outgoing.iter().enumerate() {
+                let incoming = self.half_edges[outgoing].twin;
+                let next = vertex.outgoing[(i + count - 1) % count];
+                self.half_edges[incoming].next = next;
+                self.half_edges[next].prev = incoming;
+            }
+        }
+    }
+
+    /// Compares the directions of two half-edges with the same origin by their angle to the positive x-axis.
+    fn compare_angles(&self, a: usize, b: usize) -> Ordering {
+        let origin = &self.vertices[self.half_edges[a].origin].point;
+        let target_a = self.target(a);
+        let target_b = self.target(b);
+
+        // directions in [0, pi) come before directions in [pi, 2 pi)
+        let lower_half = |target: &Point2D<T::Field>| {
+            target.y < origin.y || (target.y == origin.y && target.x < origin.x)
+        };
+        lower_half(target_a)
+            .cmp(&lower_half(target_b))
+            .then_with(
+                || match predicates::orientation(origin, target_a, target_b) {
+                    Orientation::CounterClockwise => Ordering::Less,
+                    Orientation::Clockwise => Ordering::Greater,
+                    Orientation::Collinear => target_a.cmp(target_b),
+                },
+            )
+    }
+
+    fn target(&self, half_edge: usize) -> &Point2D<T::Field> {
+        &self.vertices[self.half_edges[self.half_edges[half_edge].twin].origin].point
+    }
+
+    /// Creates a face for every counterclockwise cycle and assigns the remaining cycles as holes to the
+    /// smallest face containing them.
+    fn build_faces(&mut self) {
+        let components = self.components();
+        let mut visited = vec![false; self.half_edges.len()];
+        let mut holes = Vec::new();
+        let mut bounded = Vec::new();
+
+        for start in 0..self.half_edges.len() {
+            if visited[start] {
+                continue;
+            }
+            let cycle = self.cycle(start);
+            for &h in &cycle {
+                visited[h] = true;
+            }
+            let area = self.signed_area(start);
+            if area > field::<T>(0.0) {
+                let face = self.faces.len();
+                self.faces.push(Face {
+                    outer_component: Some(start),
+                    inner_components: Vec::new(),
+                });
+                for &h in &cycle {
+                    self.half_edges[h].face = face;
+                }
+                bounded.push((face, area));
+            } else {
+                holes.push(start);
+            }
+        }
+
+        for hole in holes {
+            let component = components[self.half_edges[hole].origin];
+            let leftmost = self
+                .cycle(hole)
+                .into_iter()
+                .map(|h| &self.vertices[self.half_edges[h].origin].point)
+                .min()
+                .expect("Cycles aren't empty");
+
+            let face = bounded
+                .iter()
+                .filter(|(face, _)| {
+                    let outer = self.faces[*face].outer_component.unwrap();
+                    components[self.half_edges[outer].origin] != component
+                        && self.encloses(outer, leftmost)
+                })
+                .min_by(|(_, area1), (_, area2)| area1.cmp(area2))
+                .map_or(UNBOUNDED_FACE, |(face, _)| *face);
+
+            for h in self.cycle(hole) {
+                self.half_edges[h].face = face;
+            }
+            self.faces[face].inner_components.push(hole);
+        }
+    }
+
+    /// Connected component of each vertex.
+    fn components(&self) -> Vec<usize> {
+        let mut component = vec![usize::MAX; self.vertices.len()];
+        for start in 0..self.vertices.len() {
+            if component[start] != usize::MAX {
+                continue;
+            }
+            component[start] = start;
+            let mut stack = vec![start];
+            while let Some(vertex) = stack.pop() {
+                for &h in &self.vertices[vertex].outgoing {
+                    let target = self.half_edges[self.half_edges[h].twin].origin;
+                    if component[target] == usize::MAX {
+                        component[target] = start;
+                        stack.push(target);
+                    }
+                }
+            }
+        }
+        component
+    }
+
+    /// Checks whether the point, which must not lie on the cycle, is enclosed by the cycle.
+    /// Counts the crossings of the cycle with the ray from the point in positive x direction.
+    fn encloses(&self, half_edge: usize, point: &Point2D<T::Field>) -> bool {
+        let mut inside = false;
+        for h in self.cycle(half_edge) {
+            let start = &self.vertices[self.half_edges[h].origin].point;
+            let end = self.target(h);
+            if (start.y > point.y) != (end.y > point.y) {
+                let orientation = predicates::orientation(start, end, point);
+                let upwards = end.y > start.y;
+                if (upwards && orientation == Orientation::CounterClockwise)
+                    || (!upwards && orientation == Orientation::Clockwise)
+                {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+/// Converts a small constant, which every field represents exactly.
+fn field<T: Scalar>(value: f64) -> T::Field {
+    T::Field::from_f64(value).expect("Constant not representable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+
+    fn integer_line(x1: i64, y1: i64, x2: i64, y2: i64) -> Line2D<i64> {
+        Line2D {
+            start: Point2D { x: x1, y: y1 },
+            end: Point2D { x: x2, y: y2 },
+        }
+    }
+
+    fn rational(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    fn assert_euler_formula<T: Scalar>(arrangement: &Arrangement<T>) {
+        let vertices = arrangement.vertices();
+        let mut component: Vec<usize> = (0..vertices.len()).collect();
+        fn find(component: &mut Vec<usize>, v: usize) -> usize {
+            if component[v] != v {
+                let root = find(component, component[v]);
+                component[v] = root;
+            }
+            component[v]
+        }
+        for h in arrangement.half_edges() {
+            let a = find(&mut component, h.origin);
+            let b = find(&mut component, arrangement.half_edges()[h.twin].origin);
+            component[a] = b;
+        }
+        let components = (0..vertices.len())
+            .filter(|&v| find(&mut component, v) == v)
+            .count();
+
+        // V - E + F = 1 + C
+        assert_eq!(
+            vertices.len() + arrangement.faces().len(),
+            arrangement.edge_count() + 1 + components
+        );
+    }
+
+    #[test]
+    fn crossing_lines_should_be_split_at_the_intersection() {
+        let lines = [integer_line(0, 0, 2, 2), integer_line(0, 2, 2, 0)];
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        assert_eq!(arrangement.vertices().len(), 5);
+        assert_eq!(arrangement.edge_count(), 4);
+        assert_eq!(arrangement.faces().len(), 1);
+        let center = arrangement
+            .vertices()
+            .iter()
+            .position(|v| {
+                v.point
+                    == Point2D {
+                        x: rational(1, 1),
+                        y: rational(1, 1),
+                    }
+            })
+            .unwrap();
+        assert_eq!(arrangement.vertex_degree(center), 4);
+        assert_euler_formula(&arrangement);
+    }
+
+    #[test]
+    fn square_with_diagonal_should_have_two_triangles() {
+        let lines = [
+            integer_line(0, 0, 2, 0),
+            integer_line(2, 0, 2, 2),
+            integer_line(0, 2, 2, 2),
+            integer_line(0, 0, 0, 2),
+            integer_line(0, 0, 2, 2),
+        ];
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        let faces: Vec<usize> = arrangement.bounded_faces().collect();
+        assert_eq!(faces.len(), 2);
+        for face in faces {
+            assert_eq!(arrangement.face_area(face), Some(rational(2, 1)));
+            assert_eq!(arrangement.face_boundary(face).len(), 3);
+        }
+        assert_eq!(arrangement.face_area(UNBOUNDED_FACE), None);
+        assert_euler_formula(&arrangement);
+    }
+
+    #[test]
+    fn nested_squares_should_be_holes_of_the_enclosing_face() {
+        let square = |min: i64, max: i64| {
+            vec![
+                integer_line(min, min, max, min),
+                integer_line(max, min, max, max),
+                integer_line(min, max, max, max),
+                integer_line(min, min, min, max),
+            ]
+        };
+        let mut lines = square(0, 6);
+        lines.extend(square(2, 4));
+        // a dangling line inside the inner square
+        lines.push(integer_line(3, 3, 3, 4));
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        let mut areas: Vec<BigRational> = arrangement
+            .bounded_faces()
+            .map(|face| arrangement.face_area(face).unwrap())
+            .collect();
+        areas.sort();
+        assert_eq!(areas, vec![rational(4, 1), rational(32, 1)]);
+
+        let outer = arrangement
+            .bounded_faces()
+            .find(|&face| arrangement.face_area(face) == Some(rational(32, 1)))
+            .unwrap();
+        assert_eq!(arrangement.faces()[outer].inner_components.len(), 1);
+        assert_eq!(
+            arrangement.faces()[UNBOUNDED_FACE].inner_components.len(),
+            1
+        );
+        assert_euler_formula(&arrangement);
+    }
+
+    #[test]
+    fn overlapping_lines_should_share_edges() {
+        let lines = [
+            integer_line(0, 0, 4, 0),
+            integer_line(2, 0, 6, 0),
+            integer_line(0, 0, 4, 0),
+            integer_line(3, -1, 3, 1),
+        ];
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        // 0 - 2 - 3 - 4 - 6 on the x-axis and the vertical line split at 3
+        assert_eq!(arrangement.vertices().len(), 7);
+        assert_eq!(arrangement.edge_count(), 6);
+        assert_euler_formula(&arrangement);
+    }
+
+    #[test]
+    fn reversed_collinear_lines_should_merge() {
+        let lines = [integer_line(0, 0, 2, 0), integer_line(3, 0, 1, 0)];
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        // 0 - 1 - 2 - 3 on the x-axis, the overlap from 1 to 2 is a single edge
+        assert_eq!(arrangement.vertices().len(), 4);
+        assert_eq!(arrangement.edge_count(), 3);
+        for vertex in 0..arrangement.vertices().len() {
+            assert!(arrangement.vertex_degree(vertex) <= 2);
+        }
+        assert_euler_formula(&arrangement);
+    }
+
+    #[test]
+    fn arrangement_of_many_lines_should_satisfy_euler_formula() {
+        let mut lines = Vec::new();
+        for i in 0..8i64 {
+            for j in 0..8i64 {
+                let end = ((i * 5 + j * 3) % 8, (i * 3 + j * 7) % 8);
+                lines.push(integer_line(i, j, end.0, end.1));
+            }
+        }
+
+        let arrangement = Arrangement::from_lines(&lines);
+
+        assert!(arrangement.faces().len() > 50);
+        for face in arrangement.bounded_faces() {
+            assert!(arrangement.face_area(face).unwrap() > rational(0, 1));
+        }
+        assert_euler_formula(&arrangement);
+    }
+}
@@ -0,0 +1,368 @@
+//! Measurements of repeated benchmark runs: CPU and wall time, peak memory, their statistics and
+//! the output of the results as CSV or JSON.
+//!
+//! The peak memory is the high water mark of the resident memory during a run relative to the
+//! resident memory at its start. On Linux the high water mark is reset before every run through
+//! `/proc/self/clear_refs`, on other platforms only the difference of the resident memory after and
+//! before the run is measured.
+use cpu_time::ProcessTime;
+use memory_stats::memory_stats;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Number of warmup and measured runs of each benchmark.
+#[derive(Clone, Copy, Debug)]
+pub struct BenchmarkOptions {
+    /// Runs before the measured runs, their results are discarded
+    pub warmup: usize,
+    pub repeats: usize,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions {
+            warmup: 0,
+            repeats: 1,
+        }
+    }
+}
+
+/// CPU and wall time of a single run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub cpu: Duration,
+    pub wall: Duration,
+}
+
+impl Measurement {
+    /// Runs the function and measures its CPU and wall time.
+    pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Measurement) {
+        let cpu = ProcessTime::try_now().expect("Getting process time failed");
+        let wall = Instant::now();
+        let result = f();
+        let measurement = Measurement {
+            cpu: cpu.elapsed(),
+            wall: wall.elapsed(),
+        };
+        (result, measurement)
+    }
+}
+
+/// Statistics of the durations of repeated runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub median: Duration,
+    pub min: Duration,
+    pub mean: Duration,
+    /// Sample standard deviation, zero for a single run
+    pub stddev: Duration,
+}
+
+impl Summary {
+    /// Summarizes the samples, None if there are none.
+    pub fn new(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let n = sorted.len();
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        };
+        let mean = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            sorted
+                .iter()
+                .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1) as f64
+        } else {
+            0.0
+        };
+        Some(Summary {
+            median,
+            min: sorted[0],
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+/// Statistics of the CPU and wall time of repeated runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    pub cpu: Summary,
+    pub wall: Summary,
+}
+
+impl Timing {
+    pub fn new(measurements: &[Measurement]) -> Option<Self> {
+        let cpu: Vec<Duration> = measurements.iter().map(|m| m.cpu).collect();
+        let wall: Vec<Duration> = measurements.iter().map(|m| m.wall).collect();
+        Some(Timing {
+            cpu: Summary::new(&cpu)?,
+            wall: Summary::new(&wall)?,
+        })
+    }
+}
+
+/// Tracks the peak resident memory of a run.
+pub struct MemoryTracker {
+    baseline: u64,
+    high_water_mark: bool,
+}
+
+impl MemoryTracker {
+    /// Starts tracking at the current resident memory.
+    pub fn start() -> Self {
+        let high_water_mark = fs::write("/proc/self/clear_refs", "5").is_ok();
+        MemoryTracker {
+            baseline: resident_memory(),
+            high_water_mark,
+        }
+    }
+
+    /// Peak resident memory since the start in bytes, never negative.
+    pub fn peak(&self) -> u64 {
+        let peak = if self.high_water_mark {
+            high_water_mark().unwrap_or_else(resident_memory)
+        } else {
+            resident_memory()
+        };
+        peak.saturating_sub(self.baseline)
+    }
+}
+
+fn resident_memory() -> u64 {
+    memory_stats().map_or(0, |usage| usage.physical_mem as u64)
+}
+
+/// `VmHWM` of `/proc/self/status` in bytes.
+fn high_water_mark() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim();
+    kilobytes.parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+/// Result of the benchmark of an algorithm on a file.
+#[derive(Clone, Debug)]
+pub struct BenchmarkRecord {
+    pub file: String,
+    pub algorithm: String,
+    pub scalar: &'static str,
+    pub lines: usize,
+    pub warmup: usize,
+    pub repeats: usize,
+    /// None if the algorithm failed
+    pub intersections: Option<usize>,
+    /// Reading the file
+    pub parse: Option<Timing>,
+    /// Creating the algorithm and computing the intersections
+    pub compute: Option<Timing>,
+    /// Largest peak memory of the computations in bytes
+    pub peak_memory: Option<u64>,
+    pub error: Option<String>,
+}
+
+const CSV_HEADER: &str = "file,algorithm,scalar,lines,intersections,warmup,repeats,\
+parse_cpu_median_ms,parse_cpu_min_ms,parse_cpu_stddev_ms,parse_wall_median_ms,parse_wall_min_ms,parse_wall_stddev_ms,\
+compute_cpu_median_ms,compute_cpu_min_ms,compute_cpu_stddev_ms,compute_wall_median_ms,compute_wall_min_ms,compute_wall_stddev_ms,\
+peak_memory_kb,error";
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn summary_json(summary: &Summary) -> Value {
+    json!({
+        "median_ms": milliseconds(summary.median),
+        "min_ms": milliseconds(summary.min),
+        "mean_ms": milliseconds(summary.mean),
+        "stddev_ms": milliseconds(summary.stddev),
+    })
+}
+
+fn timing_json(timing: &Option<Timing>) -> Value {
+    match timing {
+        Some(timing) => {
+            json!({ "cpu": summary_json(&timing.cpu), "wall": summary_json(&timing.wall) })
+        }
+        None => Value::Null,
+    }
+}
+
+/// Quotes the field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl BenchmarkRecord {
+    fn csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let mut fields = vec![
+            csv_field(&self.file),
+            csv_field(&self.algorithm),
+            self.scalar.to_string(),
+            self.lines.to_string(),
+            optional(self.intersections.map(|i| i.to_string())),
+            self.warmup.to_string(),
+            self.repeats.to_string(),
+        ];
+        for timing in [&self.parse, &self.compute] {
+            for summary in [timing.map(|t| t.cpu), timing.map(|t| t.wall)] {
+                fields.push(optional(
+                    summary.map(|s| format!("{:.3}", milliseconds(s.median))),
+                ));
+                fields.push(optional(
+                    summary.map(|s| format!("{:.3}", milliseconds(s.min))),
+                ));
+                fields.push(optional(
+                    summary.map(|s| format!("{:.3}", milliseconds(s.stddev))),
+                ));
+            }
+        }
+        fields.push(optional(self.peak_memory.map(|m| (m / 1024).to_string())));
+        fields.push(csv_field(self.error.as_deref().unwrap_or_default()));
+        fields.join(",")
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "file": self.file,
+            "algorithm": self.algorithm,
+            "scalar": self.scalar,
+            "lines": self.lines,
+            "intersections": self.intersections,
+            "warmup": self.warmup,
+            "repeats": self.repeats,
+            "parse": timing_json(&self.parse),
+            "compute": timing_json(&self.compute),
+            "peak_memory_kb": self.peak_memory.map(|m| m / 1024),
+            "error": self.error,
+        })
+    }
+}
+
+/// Writes the records as CSV with a header row, durations in milliseconds.
+pub fn write_csv<W: Write>(writer: &mut W, records: &[BenchmarkRecord]) -> io::Result<()> {
+    writeln!(writer, "{}", CSV_HEADER)?;
+    for record in records {
+        writeln!(writer, "{}", record.csv_row())?;
+    }
+    Ok(())
+}
+
+/// Writes the records as JSON array, durations in milliseconds.
+pub fn write_json<W: Write>(writer: &mut W, records: &[BenchmarkRecord]) -> io::Result<()> {
+    let records: Vec<Value> = records.iter().map(BenchmarkRecord::to_json).collect();
+    serde_json::to_writer_pretty(&mut *writer, &records)?;
+    writeln!(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> BenchmarkRecord {
+        let timing = Timing::new(&[
+            Measurement {
+                cpu: Duration::from_millis(4),
+                wall: Duration::from_millis(5),
+            },
+            Measurement {
+                cpu: Duration::from_millis(2),
+                wall: Duration::from_millis(3),
+            },
+        ]);
+        BenchmarkRecord {
+            file: "data/s_1000_1.dat".to_string(),
+            algorithm: "Sweep Line".to_string(),
+            scalar: "f64",
+            lines: 1000,
+            warmup: 1,
+            repeats: 2,
+            intersections: Some(4),
+            parse: timing,
+            compute: timing,
+            peak_memory: Some(2048),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn summary_should_compute_statistics() {
+        let samples: Vec<Duration> = [5, 1, 3, 7]
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect();
+
+        let summary = Summary::new(&samples).unwrap();
+
+        assert_eq!(summary.median, Duration::from_millis(4));
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.mean, Duration::from_millis(4));
+        // sample variance (1 + 9 + 1 + 9) / 3 ms^2
+        assert!((milliseconds(summary.stddev) - (20.0f64 / 3.0).sqrt()).abs() < 1e-6);
+        assert_eq!(Summary::new(&samples[..1]).unwrap().stddev, Duration::ZERO);
+        assert_eq!(Summary::new(&[]), None);
+    }
+
+    #[test]
+    fn records_should_be_written_as_csv_and_json() {
+        let failed = BenchmarkRecord {
+            intersections: None,
+            parse: None,
+            compute: None,
+            peak_memory: None,
+            error: Some("Unsupported, \"red-blue\"".to_string()),
+            ..record()
+        };
+        let mut csv = Vec::new();
+        let mut json = Vec::new();
+
+        write_csv(&mut csv, &[record(), failed.clone()]).unwrap();
+        write_json(&mut json, &[record(), failed]).unwrap();
+
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(
+            rows[1],
+            "data/s_1000_1.dat,Sweep Line,f64,1000,4,1,2,3.000,2.000,1.414,4.000,3.000,1.414,3.000,2.000,1.414,4.000,3.000,1.414,2,"
+        );
+        assert_eq!(
+            rows[2],
+            "data/s_1000_1.dat,Sweep Line,f64,1000,,1,2,,,,,,,,,,,,,,\"Unsupported, \"\"red-blue\"\"\""
+        );
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[0]["compute"]["wall"]["median_ms"], 4.0);
+        assert_eq!(json[1]["intersections"], Value::Null);
+    }
+
+    #[test]
+    fn peak_memory_should_include_allocations() {
+        let tracker = MemoryTracker::start();
+        let data = vec![1u8; 64 << 20];
+
+        let peak = tracker.peak();
+
+        assert_eq!(data.iter().map(|&b| b as usize).sum::<usize>(), 64 << 20);
+        if memory_stats().is_some() {
+            assert!(peak >= 32 << 20, "peak {} bytes", peak);
+        }
+    }
+}
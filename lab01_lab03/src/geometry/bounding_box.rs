@@ -0,0 +1,88 @@
+//! Axis-aligned bounding boxes of line segments, the filter of the spatial index backends.
+use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
+
+/// Closed rectangle, exact in the coordinates of the lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundingBox<T: Scalar> {
+    pub x_min: T,
+    pub y_min: T,
+    pub x_max: T,
+    pub y_max: T,
+}
+
+impl<T: Scalar> BoundingBox<T> {
+    pub fn of(line: &Line2D<T>) -> Self {
+        let (x_min, x_max) = ordered(&line.start.x, &line.end.x);
+        let (y_min, y_max) = ordered(&line.start.y, &line.end.y);
+        BoundingBox {
+            x_min: x_min.clone(),
+            y_min: y_min.clone(),
+            x_max: x_max.clone(),
+            y_max: y_max.clone(),
+        }
+    }
+
+    /// Whether the boxes share at least a point, boxes touching at their border intersect.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.x_min <= other.x_max
+            && other.x_min <= self.x_max
+            && self.y_min <= other.y_max
+            && other.y_min <= self.y_max
+    }
+
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        BoundingBox {
+            x_min: self.x_min.clone().min(other.x_min.clone()),
+            y_min: self.y_min.clone().min(other.y_min.clone()),
+            x_max: self.x_max.clone().max(other.x_max.clone()),
+            y_max: self.y_max.clone().max(other.y_max.clone()),
+        }
+    }
+
+    /// Lower left corner of the intersection of the boxes, which have to intersect.
+    /// It identifies the pair independent of its order, so each pair is only reported once.
+    pub fn reference_point(&self, other: &Self) -> (T, T) {
+        (
+            self.x_min.clone().max(other.x_min.clone()),
+            self.y_min.clone().max(other.y_min.clone()),
+        )
+    }
+
+    /// Center as float, e.g. to sort boxes.
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.x_min.to_f64() + self.x_max.to_f64()) / 2.0,
+            (self.y_min.to_f64() + self.y_max.to_f64()) / 2.0,
+        )
+    }
+}
+
+fn ordered<'a, T: Ord>(a: &'a T, b: &'a T) -> (&'a T, &'a T) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_touching_at_a_corner_should_intersect() {
+        let a = BoundingBox::of(&Line2D::new(2.0, 2.0, 0.0, 0.0));
+        let b = BoundingBox::of(&Line2D::new(2.0, 2.0, 3.0, 5.0));
+        let c = BoundingBox::of(&Line2D::new(2.5, 0.0, 3.0, 1.0));
+
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+        assert_eq!(a.reference_point(&b), b.reference_point(&a));
+        assert_eq!(
+            a.union(&c),
+            BoundingBox::of(&Line2D::new(0.0, 0.0, 3.0, 2.0))
+        );
+    }
+}
@@ -1,100 +1,102 @@
+use crate::geometry::algorithm;
+use crate::geometry::counting::{self, IntersectionCounts};
 use crate::geometry::intersection::Intersection;
 use crate::geometry::line::Line2D;
+use crate::geometry::parallel::{self, Collector};
+use crate::geometry::scalar::Scalar;
 use ordered_float::OrderedFloat;
-use std::cmp::max;
 use std::collections::HashSet;
 
-pub struct BruteForceHandler {
-    lines: Vec<Line2D>,
-    intersections: HashSet<Intersection>,
+/// Number of rows a thread takes at once, rows get shorter towards the end
+const BLOCK_ROWS: usize = 16;
+
+pub struct BruteForceHandler<T: Scalar = OrderedFloat<f64>> {
+    lines: Vec<Line2D<T>>,
+    intersections: HashSet<Intersection<T>>,
+    /// number of red lines at the start of the lines in the bichromatic mode
+    red_count: Option<usize>,
+    threads: usize,
 }
 
-impl BruteForceHandler {
-    pub fn new(mut lines: Vec<Line2D>) -> Self {
+impl<T: Scalar> BruteForceHandler<T> {
+    pub fn new(lines: Vec<Line2D<T>>) -> Self {
         Self {
-            lines: lines.drain(..).collect(),
+            lines,
             intersections: HashSet::new(),
+            red_count: None,
+            threads: 1,
         }
     }
 
-    pub fn run(&mut self) -> HashSet<Intersection> {
-        for i in 0..self.lines.len() {
-            for j in i + 1..self.lines.len() {
-                let line1 = self.lines[i];
-                let line2 = self.lines[j];
-                if line1.intersects(line2) {
-                    let intersection = line1
-                        .find_intersection(line2)
-                        .expect("Intersection not found!");
-
-                    self.intersections.insert(intersection);
-                }
-            }
+    /// Creates a handler which only reports intersections of red with blue lines.
+    pub fn new_bichromatic(red: Vec<Line2D<T>>, blue: Vec<Line2D<T>>) -> Self {
+        let red_count = red.len();
+        let mut lines = red;
+        lines.extend(blue);
+        Self {
+            lines,
+            intersections: HashSet::new(),
+            red_count: Some(red_count),
+            threads: 1,
         }
-
-        self.intersections.clone()
     }
 
-    pub fn analyze(&self) {
-        if self.intersections.len() == 0 {
-            println!("No intersections found!");
-            return;
-        }
+    /// Intersects the rows of lines on the given number of threads, see [parallel].
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
 
-        let mut x_coords: HashSet<OrderedFloat<f64>> = HashSet::new();
-        let mut same_x_coords_counter = 0;
+    pub fn run(&mut self) -> HashSet<Intersection<T>> {
+        let found = self.collect::<HashSet<_>>();
+        self.intersections.extend(found);
 
-        let mut zero_length_lines = Vec::new();
-        let mut touching_lines = Vec::new();
-        let mut vertical_lines = Vec::new();
-        let mut overlapping_lines = Vec::new();
+        self.intersections.clone()
+    }
 
-        for line in self.lines.clone() {
-            if line.is_zero_length() {
-                zero_length_lines.push(line);
+    /// Counts the intersections [run](Self::run) would return without storing them.
+    pub fn count(&self) -> IntersectionCounts {
+        counting::count_distinct(&self.lines, self.red_count, |lines, red_count| {
+            Self {
+                red_count,
+                ..Self::new(lines)
             }
+            .with_threads(self.threads)
+            .collect()
+        })
+    }
 
-            if line.is_vertical() {
-                vertical_lines.push(line);
+    fn collect<C: Collector<T>>(&self) -> C {
+        // in the bichromatic mode every red line is only tested against the blue lines
+        let rows = self.red_count.unwrap_or(self.lines.len());
+        parallel::collect_blocks(rows, BLOCK_ROWS, self.threads, |rows, found| {
+            for i in rows {
+                self.intersect_row(i, found);
             }
+        })
+    }
 
-            let start_x = max(line.start.x, line.end.x);
-            if x_coords.contains(&start_x) {
-                same_x_coords_counter += 1;
-            } else {
-                x_coords.insert(start_x);
-            }
-        }
+    /// Intersects the line with the following lines, or with the blue lines in the bichromatic mode.
+    fn intersect_row(&self, i: usize, intersections: &mut impl Collector<T>) {
+        let first = self.red_count.unwrap_or(i + 1);
+        for j in first..self.lines.len() {
+            let line1 = &self.lines[i];
+            let line2 = &self.lines[j];
+            if line1.intersects(line2.clone()) {
+                let intersection = line1
+                    .find_intersection(line2.clone())
+                    .expect("Intersection not found!");
 
-        for intersection in &self.intersections {
-            match intersection {
-                Intersection::Crossing { point, .. } => {
-                    if x_coords.contains(&point.x) {
-                        same_x_coords_counter += 1;
-                    } else {
-                        x_coords.insert(point.x);
-                    }
-                }
-                Intersection::Touching { .. } => {
-                    touching_lines.push(intersection);
-                }
-                Intersection::PartialOverlap { .. }
-                | Intersection::ContainedOverlap { .. }
-                | Intersection::IdenticalOverlap { .. } => {
-                    overlapping_lines.push(intersection.clone());
-                }
+                intersections.add(intersection);
             }
         }
-        println!("Analysis:");
-        println!("Analyzed {} lines", self.lines.len());
-        println!("Found {} intersections", self.intersections.len());
-        println!("Number of zero length lines: {}", zero_length_lines.len());
-        println!("Number of vertical lines: {}", vertical_lines.len());
-        println!("Number of touching lines: {}", touching_lines.len());
-        println!("Number of overlapping lines: {}", overlapping_lines.len());
-        println!(
-            "Number of duplicate x-coordinates among intersections and endpoints: {}",
-            same_x_coords_counter
-        );
+    }
+
+    pub fn analyze(&self) {
+        algorithm::analyze(&self.lines, &self.intersections);
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
     }
 }
@@ -0,0 +1,356 @@
+//! Counting intersections without enumerating them.
+//!
+//! [IntersectionCounts] holds the number of intersections by variant. The brute force, the grid and
+//! the R-tree count the pairs they find directly instead of collecting them into a set, see
+//! [IntersectionAlgorithm::count](crate::geometry::algorithm::IntersectionAlgorithm::count). A set of
+//! intersections holds identical lines only once, so the lines are made distinct first, which needs
+//! O(n) extra memory, and the counts equal the size of the set the algorithm would return.
+//!
+//! The sweep line counts every intersection at the event point where it's found, see
+//! [Handler::count](crate::geometry::sweep_line::handler::Handler::count), so it only remembers the
+//! intersections near the sweep line.
+//!
+//! [count_slab_crossings] counts the crossings of segments which all span a common vertical slab
+//! in O(n log n): two segments cross inside the slab exactly if their order at the left boundary is
+//! the inverse of their order at the right boundary, so the crossings are the inversions between
+//! both orders.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::parallel::Collector;
+use crate::geometry::scalar::Scalar;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// Number of intersections by variant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IntersectionCounts {
+    pub crossing: usize,
+    pub touching: usize,
+    pub partial_overlap: usize,
+    pub contained_overlap: usize,
+    pub identical_overlap: usize,
+}
+
+impl IntersectionCounts {
+    /// Counts the intersections, e.g. the result of an algorithm.
+    pub fn of<'a, T: Scalar>(intersections: impl IntoIterator<Item = &'a Intersection<T>>) -> Self {
+        let mut counts = IntersectionCounts::default();
+        for intersection in intersections {
+            counts.add(intersection);
+        }
+        counts
+    }
+
+    pub fn add<T: Scalar>(&mut self, intersection: &Intersection<T>) {
+        match intersection {
+            Intersection::Crossing { .. } => self.crossing += 1,
+            Intersection::Touching { .. } => self.touching += 1,
+            Intersection::PartialOverlap { .. } => self.partial_overlap += 1,
+            Intersection::ContainedOverlap { .. } => self.contained_overlap += 1,
+            Intersection::IdenticalOverlap { .. } => self.identical_overlap += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.by_variant().iter().map(|(_, count)| count).sum()
+    }
+
+    /// Counts with the names of the variants, see [Intersection::variant].
+    pub fn by_variant(&self) -> [(&'static str, usize); 5] {
+        [
+            ("Crossing", self.crossing),
+            ("Touching", self.touching),
+            ("PartialOverlap", self.partial_overlap),
+            ("ContainedOverlap", self.contained_overlap),
+            ("IdenticalOverlap", self.identical_overlap),
+        ]
+    }
+
+    pub(crate) fn merge(&mut self, other: Self) {
+        self.crossing += other.crossing;
+        self.touching += other.touching;
+        self.partial_overlap += other.partial_overlap;
+        self.contained_overlap += other.contained_overlap;
+        self.identical_overlap += other.identical_overlap;
+    }
+
+    fn subtract(&mut self, other: &Self) {
+        self.crossing -= other.crossing;
+        self.touching -= other.touching;
+        self.partial_overlap -= other.partial_overlap;
+        self.contained_overlap -= other.contained_overlap;
+        self.identical_overlap -= other.identical_overlap;
+    }
+}
+
+impl Display for IntersectionCounts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} intersections", self.total())?;
+        for (variant, count) in self.by_variant() {
+            write!(f, ", {} {}", count, variant)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Scalar> Collector<T> for IntersectionCounts {
+    fn add(&mut self, intersection: Intersection<T>) {
+        IntersectionCounts::add(self, &intersection);
+    }
+
+    fn merge(&mut self, other: Self) {
+        IntersectionCounts::merge(self, other);
+    }
+}
+
+/// Counts the intersections the algorithm would return as a set, given a function counting the pairs
+/// of distinct lines, in the bichromatic mode with the number of red lines at their start.
+///
+/// Copies of a line only add one identical overlap. In the bichromatic mode a pair of lines which
+/// are both red and blue is found twice, as a red-blue and as a blue-red pair, so the pairs of these
+/// lines are counted separately and subtracted.
+pub(crate) fn count_distinct<T: Scalar>(
+    lines: &[Line2D<T>],
+    red_count: Option<usize>,
+    count: impl Fn(Vec<Line2D<T>>, Option<usize>) -> IntersectionCounts,
+) -> IntersectionCounts {
+    match red_count {
+        Some(red_count) => {
+            let red_count = red_count.min(lines.len());
+            let (red, _) = distinct(&lines[..red_count]);
+            let (blue, _) = distinct(&lines[red_count..]);
+            let red_lines: HashSet<&Line2D<T>> = red.iter().collect();
+            let shared: Vec<Line2D<T>> = blue
+                .iter()
+                .filter(|line| red_lines.contains(line))
+                .cloned()
+                .collect();
+
+            let red_count = red.len();
+            let mut lines = red;
+            lines.extend(blue);
+            let mut counts = count(lines, Some(red_count));
+            if shared.len() > 1 {
+                counts.subtract(&count(shared, None));
+            }
+            counts
+        }
+        None => {
+            let (lines, repeated) = distinct(lines);
+            let mut counts = count(lines, None);
+            counts.identical_overlap += repeated;
+            counts
+        }
+    }
+}
+
+/// Distinct lines in the order of their first occurrence and the number of lines occurring several
+/// times.
+fn distinct<T: Scalar>(lines: &[Line2D<T>]) -> (Vec<Line2D<T>>, usize) {
+    let mut occurrences: HashMap<&Line2D<T>, usize> = HashMap::new();
+    let mut distinct = Vec::new();
+    for line in lines {
+        let count = occurrences.entry(line).or_insert(0);
+        if *count == 0 {
+            distinct.push(line.clone());
+        }
+        *count += 1;
+    }
+    let repeated = occurrences.values().filter(|&&count| count > 1).count();
+    (distinct, repeated)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlabError {
+    /// The left boundary isn't smaller than the right one
+    Empty,
+    /// The line at the index doesn't span the slab
+    NotSpanning { index: usize },
+}
+
+impl Display for SlabError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SlabError::Empty => write!(
+                f,
+                "The left boundary of the slab has to be smaller than the right one"
+            ),
+            SlabError::NotSpanning { index } => write!(f, "Line {} doesn't span the slab", index),
+        }
+    }
+}
+
+impl std::error::Error for SlabError {}
+
+/// Number of crossings in the interior of the slab `left < x < right`, which every line has to span.
+/// Lines meeting only on a boundary of the slab and overlapping lines aren't counted, and copies of a
+/// line are counted once like in the set of intersections. Takes O(n log n) time and O(n) memory,
+/// and is exact for exact scalars.
+pub fn count_slab_crossings<T: Scalar>(
+    lines: &[Line2D<T>],
+    left: &T,
+    right: &T,
+) -> Result<usize, SlabError> {
+    if left >= right {
+        return Err(SlabError::Empty);
+    }
+    if let Some(index) = lines.iter().position(|line| {
+        let (start, end) = line.sorted_endpoints();
+        &start.x > left || &end.x < right
+    }) {
+        return Err(SlabError::NotSpanning { index });
+    }
+
+    let (lines, _) = distinct(lines);
+    let (left, right) = (left.to_field(), right.to_field());
+    let mut heights = Vec::with_capacity(lines.len());
+    for line in &lines {
+        let (start, end) = line.sorted_endpoints();
+        let (start, end) = (start.to_field(), end.to_field());
+        let y_at = |x: &T::Field| {
+            start.y.clone()
+                + (x.clone() - start.x.clone()) * (end.y.clone() - start.y.clone())
+                    / (end.x.clone() - start.x.clone())
+        };
+        heights.push((y_at(&left), y_at(&right)));
+    }
+
+    // lines meeting on the left boundary are ordered by their height on the right, so they don't
+    // form inversions
+    heights.sort();
+    let mut right_heights: Vec<T::Field> = heights.into_iter().map(|(_, right)| right).collect();
+    Ok(count_inversions(&mut right_heights))
+}
+
+/// Number of pairs i < j with values[i] > values[j], sorts the values by merge sort.
+fn count_inversions<V: Ord + Clone>(values: &mut [V]) -> usize {
+    if values.len() < 2 {
+        return 0;
+    }
+    let middle = values.len() / 2;
+    let mut inversions =
+        count_inversions(&mut values[..middle]) + count_inversions(&mut values[middle..]);
+
+    let mut merged = Vec::with_capacity(values.len());
+    let (mut i, mut j) = (0, middle);
+    while i < middle && j < values.len() {
+        if values[j] < values[i] {
+            // values[j] is smaller than all remaining values of the left half
+            inversions += middle - i;
+            merged.push(values[j].clone());
+            j += 1;
+        } else {
+            merged.push(values[i].clone());
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&values[i..middle]);
+    merged.extend_from_slice(&values[j..]);
+    values.clone_from_slice(&merged);
+    inversions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::point::Point2D;
+    use num_rational::BigRational;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn inversions_should_equal_the_pairs_in_wrong_order() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let values: Vec<u8> = (0..300).map(|_| rng.gen_range(0..50)).collect();
+        let expected = (0..values.len())
+            .flat_map(|i| (i + 1..values.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| values[i] > values[j])
+            .count();
+
+        let mut sorted = values.clone();
+
+        assert_eq!(count_inversions(&mut sorted), expected);
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn slab_crossings_should_equal_the_crossings_of_the_brute_force() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        // ties at the boundaries give touching lines and overlaps, which aren't counted
+        let lines: Vec<Line2D<i64>> = (0..400)
+            .map(|_| Line2D {
+                start: Point2D {
+                    x: rng.gen_range(-5..=0),
+                    y: rng.gen_range(0..100),
+                },
+                end: Point2D {
+                    x: rng.gen_range(10..=15),
+                    y: rng.gen_range(0..100),
+                },
+            })
+            .collect();
+        let (left, right) = (
+            BigRational::from_integer(0.into()),
+            BigRational::from_integer(10.into()),
+        );
+        let expected = BruteForceHandler::new(lines.clone())
+            .run()
+            .iter()
+            .filter(|intersection| match intersection {
+                Intersection::Crossing { point, .. } => left < point.x && point.x < right,
+                _ => false,
+            })
+            .count();
+
+        assert_eq!(count_slab_crossings(&lines, &0, &10), Ok(expected));
+        assert_eq!(
+            count_slab_crossings(&lines, &0, &11),
+            Err(SlabError::NotSpanning {
+                index: lines.iter().position(|line| line.end.x == 10).unwrap()
+            })
+        );
+        assert_eq!(count_slab_crossings(&lines, &3, &3), Err(SlabError::Empty));
+    }
+
+    /// Counts the intersecting pairs, without collapsing equal intersections like a set.
+    fn pairs(lines: Vec<Line2D>, red_count: Option<usize>) -> IntersectionCounts {
+        let mut counts = IntersectionCounts::default();
+        for i in 0..red_count.unwrap_or(lines.len()) {
+            for j in red_count.unwrap_or(i + 1)..lines.len() {
+                if let Some(intersection) = lines[i].find_intersection(lines[j]) {
+                    counts.add(&intersection);
+                }
+            }
+        }
+        counts
+    }
+
+    fn brute_force(mut lines: Vec<Line2D>, red_count: Option<usize>) -> IntersectionCounts {
+        let intersections = match red_count {
+            Some(red_count) => {
+                let blue = lines.split_off(red_count);
+                BruteForceHandler::new_bichromatic(lines, blue).run()
+            }
+            None => BruteForceHandler::new(lines).run(),
+        };
+        IntersectionCounts::of(&intersections)
+    }
+
+    #[test]
+    fn repeated_lines_should_be_counted_like_the_set() {
+        let a = Line2D::new(0.0, 0.0, 2.0, 2.0);
+        let b = Line2D::new(0.0, 2.0, 2.0, 0.0);
+        let c = Line2D::new(1.0, 1.0, 3.0, 3.0);
+        let lines = vec![a, b, a, c, a, b];
+
+        let monochromatic = count_distinct(&lines, None, pairs);
+        // a-b is found as red a with blue b and as red b with blue a
+        let bichromatic = count_distinct(&lines, Some(3), pairs);
+
+        assert_eq!(monochromatic, brute_force(lines.clone(), None));
+        assert_eq!(monochromatic.identical_overlap, 2);
+        assert_eq!(bichromatic, brute_force(lines, Some(3)));
+        assert_eq!(bichromatic.total(), 5);
+    }
+}
@@ -0,0 +1,513 @@
+//! Reader of `.dat` files with one line segment per row: `x1 y1 x2 y2`.
+//!
+//! Coordinates are separated by whitespace or commas. Blank lines, comments and a given number of
+//! header lines are skipped. Malformed rows are reported with their position, either as error or,
+//! in the lenient mode, collected next to the lines which could be read.
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, BufRead};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Separator {
+    Whitespace,
+    Comma,
+    /// Commas if the row contains one, whitespace otherwise
+    Auto,
+}
+
+#[derive(Clone, Debug)]
+pub struct DatOptions {
+    pub separator: Separator,
+    /// Everything from this prefix to the end of the row is ignored
+    pub comment_prefix: Option<String>,
+    /// Number of rows at the start of the file which are skipped
+    pub header_lines: usize,
+    /// Collects malformed rows instead of failing on the first one
+    pub lenient: bool,
+    /// Swaps the endpoints of lines so that every line starts at its smaller x-coordinate
+    pub normalize_endpoints: bool,
+}
+
+impl Default for DatOptions {
+    /// Strict reading of whitespace or comma separated rows with `#` comments, keeping the endpoints as given.
+    fn default() -> Self {
+        DatOptions {
+            separator: Separator::Auto,
+            comment_prefix: Some("#".to_string()),
+            header_lines: 0,
+            lenient: false,
+            normalize_endpoints: false,
+        }
+    }
+}
+
+impl DatOptions {
+    /// Collects malformed rows instead of failing.
+    pub fn lenient() -> Self {
+        DatOptions {
+            lenient: true,
+            ..DatOptions::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The token isn't a number or can't be represented by the scalar
+    InvalidCoordinate { scalar: &'static str },
+    /// The row doesn't consist of exactly four coordinates
+    WrongCoordinateCount { found: usize },
+    /// The token doesn't fit the structure of a WKT or GeoJSON file
+    Syntax { expected: &'static str },
+    /// Geometry type other than a linestring, the token is the type
+    UnsupportedGeometry,
+    /// The file isn't valid JSON, with the message of the JSON parser
+    Json(String),
+}
+
+/// Malformed row or geometry of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: PathBuf,
+    /// 1-based number of the row, 0 if the error is located by its pointer
+    pub line: usize,
+    /// 1-based column of the token, in characters
+    pub column: usize,
+    /// JSON pointer to the offending value of a GeoJSON file, e.g. `/features/3/geometry`
+    pub pointer: Option<String>,
+    /// Offending token, empty if coordinates are missing
+    pub token: String,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.pointer {
+            Some(pointer) if self.line == 0 => write!(f, "{}#{}: ", self.file.display(), pointer)?,
+            _ => write!(f, "{}:{}:{}: ", self.file.display(), self.line, self.column)?,
+        }
+        match &self.kind {
+            ParseErrorKind::InvalidCoordinate { scalar } => {
+                write!(f, "invalid coordinate for {}: '{}'", scalar, self.token)
+            }
+            ParseErrorKind::WrongCoordinateCount { found } if self.token.is_empty() => {
+                write!(f, "expected 4 coordinates, found {}", found)
+            }
+            ParseErrorKind::WrongCoordinateCount { found } => {
+                write!(
+                    f,
+                    "expected 4 coordinates, found {}: '{}'",
+                    found, self.token
+                )
+            }
+            ParseErrorKind::Syntax { expected } if self.token.is_empty() => {
+                write!(f, "expected {}, found end of input", expected)
+            }
+            ParseErrorKind::Syntax { expected } => {
+                write!(f, "expected {}, found '{}'", expected, self.token)
+            }
+            ParseErrorKind::UnsupportedGeometry => {
+                write!(f, "unsupported geometry '{}'", self.token)
+            }
+            ParseErrorKind::Json(message) => write!(f, "invalid JSON: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io { file: PathBuf, source: io::Error },
+    Parse(ParseError),
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io { file, source } => write!(f, "{}: {}", file.display(), source),
+            ReadError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io { source, .. } => Some(source),
+            ReadError::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// Lines read from a file and, in the lenient mode, the rows which were skipped.
+#[derive(Clone, Debug)]
+pub struct DatReport<T> {
+    pub segments: LineSegments2D<T>,
+    pub errors: Vec<ParseError>,
+}
+
+/// Reads the file with the options.
+pub fn read<T: Scalar>(path: &Path, options: &DatOptions) -> Result<DatReport<T>, ReadError> {
+    let io_error = |source| ReadError::Io {
+        file: path.to_path_buf(),
+        source,
+    };
+    let file = fs::File::open(path).map_err(io_error)?;
+    parse(io::BufReader::new(file), path, options)
+}
+
+/// Reads the rows of the reader, the path is only used for the errors.
+pub fn parse<T: Scalar, R: BufRead>(
+    reader: R,
+    path: &Path,
+    options: &DatOptions,
+) -> Result<DatReport<T>, ReadError> {
+    let mut rows = DatReader::new(reader, path, options);
+    let lines = rows.by_ref().collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DatReport {
+        segments: LineSegments2D { lines },
+        errors: rows.into_errors(),
+    })
+}
+
+/// Opens the file for reading its lines one by one.
+pub fn open<T: Scalar>(
+    path: &Path,
+    options: &DatOptions,
+) -> Result<DatReader<T, io::BufReader<fs::File>>, ReadError> {
+    let file = fs::File::open(path).map_err(|source| ReadError::Io {
+        file: path.to_path_buf(),
+        source,
+    })?;
+    Ok(DatReader::new(io::BufReader::new(file), path, options))
+}
+
+/// Reads the lines lazily, row by row, so only the current row is held in memory.
+/// Iteration ends after the first error. In the lenient mode malformed rows are skipped and
+/// collected instead.
+pub struct DatReader<T, R> {
+    rows: std::iter::Skip<std::iter::Enumerate<io::Lines<R>>>,
+    path: PathBuf,
+    options: DatOptions,
+    errors: Vec<ParseError>,
+    failed: bool,
+    scalar: PhantomData<T>,
+}
+
+impl<T: Scalar, R: BufRead> DatReader<T, R> {
+    pub fn new(reader: R, path: &Path, options: &DatOptions) -> Self {
+        DatReader {
+            rows: reader.lines().enumerate().skip(options.header_lines),
+            path: path.to_path_buf(),
+            options: options.clone(),
+            errors: Vec::new(),
+            failed: false,
+            scalar: PhantomData,
+        }
+    }
+
+    /// Malformed rows skipped so far in the lenient mode.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ParseError> {
+        self.errors
+    }
+}
+
+impl<T: Scalar, R: BufRead> Iterator for DatReader<T, R> {
+    type Item = Result<Line2D<T>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        for (index, row) in self.rows.by_ref() {
+            let row = match row {
+                Ok(row) => row,
+                Err(source) => {
+                    self.failed = true;
+                    return Some(Err(ReadError::Io {
+                        file: self.path.clone(),
+                        source,
+                    }));
+                }
+            };
+            match parse_row(&row, &self.options) {
+                Ok(Some(line)) => return Some(Ok(line)),
+                Ok(None) => {}
+                Err((column, token, kind)) => {
+                    let error = ParseError {
+                        file: self.path.clone(),
+                        line: index + 1,
+                        column,
+                        pointer: None,
+                        token,
+                        kind,
+                    };
+                    if !self.options.lenient {
+                        self.failed = true;
+                        return Some(Err(ReadError::Parse(error)));
+                    }
+                    self.errors.push(error);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parses a row, returns None for blank rows and comments.
+/// Fails with the column, the token and the kind of the error.
+fn parse_row<T: Scalar>(
+    row: &str,
+    options: &DatOptions,
+) -> Result<Option<Line2D<T>>, (usize, String, ParseErrorKind)> {
+    let content = match &options.comment_prefix {
+        Some(prefix) => row.find(prefix.as_str()).map_or(row, |end| &row[..end]),
+        None => row,
+    };
+    let tokens = tokenize(content, options.separator);
+    if tokens.is_empty() || tokens.iter().all(|(_, token)| token.is_empty()) {
+        return Ok(None);
+    }
+
+    let mut coordinates = Vec::with_capacity(4);
+    for (offset, token) in &tokens {
+        let column = content[..*offset].chars().count() + 1;
+        if let Some(coordinate) = T::parse(token) {
+            coordinates.push(coordinate);
+        } else {
+            let kind = ParseErrorKind::InvalidCoordinate { scalar: T::NAME };
+            return Err((column, token.to_string(), kind));
+        }
+    }
+
+    let found = coordinates.len();
+    let [x1, y1, x2, y2] = <[T; 4]>::try_from(coordinates).map_err(|_| {
+        let kind = ParseErrorKind::WrongCoordinateCount { found };
+        match tokens.get(4) {
+            Some((offset, token)) => (
+                content[..*offset].chars().count() + 1,
+                token.to_string(),
+                kind,
+            ),
+            None => (content.trim_end().chars().count() + 1, String::new(), kind),
+        }
+    })?;
+
+    let start = Point2D { x: x1, y: y1 };
+    let end = Point2D { x: x2, y: y2 };
+    if options.normalize_endpoints && end.x < start.x {
+        Ok(Some(Line2D {
+            start: end,
+            end: start,
+        }))
+    } else {
+        Ok(Some(Line2D { start, end }))
+    }
+}
+
+/// Splits the row into its tokens with their byte offsets.
+fn tokenize(row: &str, separator: Separator) -> Vec<(usize, &str)> {
+    let comma = match separator {
+        Separator::Whitespace => false,
+        Separator::Comma => true,
+        Separator::Auto => row.contains(','),
+    };
+    if comma {
+        let mut offset = 0;
+        row.split(',')
+            .map(|field| {
+                let start = offset + (field.len() - field.trim_start().len());
+                offset += field.len() + 1;
+                (start, field.trim())
+            })
+            .collect()
+    } else {
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (i, c) in row.char_indices().chain([(row.len(), ' ')]) {
+            match (c.is_whitespace(), start) {
+                (true, Some(s)) => {
+                    tokens.push((s, &row[s..i]));
+                    start = None;
+                }
+                (false, None) => start = Some(i),
+                _ => {}
+            }
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::intersection::Intersection;
+    use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+    use ordered_float::OrderedFloat;
+    use std::collections::HashSet;
+
+    fn parse_str<T: Scalar>(
+        content: &str,
+        options: &DatOptions,
+    ) -> Result<DatReport<T>, ReadError> {
+        parse(content.as_bytes(), Path::new("test.dat"), options)
+    }
+
+    fn parse_error<T: Scalar>(content: &str) -> ParseError {
+        match parse_str::<T>(content, &DatOptions::default()) {
+            Err(ReadError::Parse(error)) => error,
+            other => panic!("Expected parse error, got {:?}", other.map(|r| r.segments)),
+        }
+    }
+
+    #[test]
+    fn should_read_comments_blank_lines_and_separators() {
+        let content = "# x1 y1 x2 y2\n\n0 0 1 1\n  2,3 , 4,5  # comment\n6\t7 8 9\n";
+
+        let report = parse_str::<OrderedFloat<f64>>(content, &DatOptions::default()).unwrap();
+
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            report.segments.lines,
+            vec![
+                Line2D::new(0.0, 0.0, 1.0, 1.0),
+                Line2D::new(2.0, 3.0, 4.0, 5.0),
+                Line2D::new(6.0, 7.0, 8.0, 9.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_skip_header_lines() {
+        let options = DatOptions {
+            header_lines: 1,
+            ..DatOptions::default()
+        };
+
+        let report = parse_str::<i64>("x1,y1,x2,y2\n1,2,3,4\n", &options).unwrap();
+
+        assert_eq!(report.segments.lines.len(), 1);
+    }
+
+    #[test]
+    fn should_keep_endpoints_unless_normalized() {
+        let content = "4 0 1 1\n";
+        let normalize = DatOptions {
+            normalize_endpoints: true,
+            ..DatOptions::default()
+        };
+
+        let kept = parse_str::<OrderedFloat<f64>>(content, &DatOptions::default()).unwrap();
+        let normalized = parse_str::<OrderedFloat<f64>>(content, &normalize).unwrap();
+
+        assert_eq!(kept.segments.lines, vec![Line2D::new(4.0, 0.0, 1.0, 1.0)]);
+        assert_eq!(
+            normalized.segments.lines,
+            vec![Line2D::new(1.0, 1.0, 4.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn reversed_collinear_lines_should_overlap_without_normalizing() {
+        let report =
+            parse_str::<OrderedFloat<f64>>("0 0 2 0\n3 0 1 0\n", &DatOptions::default()).unwrap();
+        let lines = report.segments.lines;
+        let expected = HashSet::from([Intersection::PartialOverlap {
+            line1: lines[0],
+            line2: lines[1],
+            overlap: Line2D::new(1.0, 0.0, 2.0, 0.0),
+        }]);
+
+        let brute_force = BruteForceHandler::new(lines.clone()).run();
+        let sweep = Handler::new(lines, SweepLineOptions::degenerate_handling()).run();
+
+        assert_eq!(brute_force, expected);
+        assert_eq!(sweep, expected);
+    }
+
+    #[test]
+    fn should_report_position_of_invalid_token() {
+        let error = parse_error::<OrderedFloat<f64>>("0 0 1 1\n0 0 x1 1\n");
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 5);
+        assert_eq!(error.token, "x1");
+        assert_eq!(
+            error.to_string(),
+            "test.dat:2:5: invalid coordinate for f64: 'x1'"
+        );
+    }
+
+    #[test]
+    fn should_report_wrong_coordinate_count() {
+        let missing = parse_error::<i64>("1 2 3\n");
+        let surplus = parse_error::<i64>("1,2,3,4,5\n");
+        let not_integer = parse_error::<i64>("1 2 3 4.5\n");
+
+        assert_eq!(
+            missing.kind,
+            ParseErrorKind::WrongCoordinateCount { found: 3 }
+        );
+        assert_eq!((missing.column, missing.token.as_str()), (6, ""));
+        assert_eq!(
+            surplus.kind,
+            ParseErrorKind::WrongCoordinateCount { found: 5 }
+        );
+        assert_eq!((surplus.column, surplus.token.as_str()), (9, "5"));
+        assert_eq!(
+            not_integer.kind,
+            ParseErrorKind::InvalidCoordinate { scalar: "i64" }
+        );
+    }
+
+    #[test]
+    fn lenient_mode_should_collect_bad_lines() {
+        let content = "0 0 1 1\nfoo\n1 1 2 2\n1 2 3\n";
+
+        let report = parse_str::<OrderedFloat<f64>>(content, &DatOptions::lenient()).unwrap();
+
+        assert_eq!(report.segments.lines.len(), 2);
+        let lines: Vec<usize> = report.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+    }
+
+    #[test]
+    fn reader_should_read_lazily_until_the_first_error() {
+        let content = "0 0 1 1\nfoo\n1 1 2 2\n";
+        let path = Path::new("test.dat");
+
+        let strict: Vec<_> =
+            DatReader::<i64, _>::new(content.as_bytes(), path, &DatOptions::default()).collect();
+        let mut lenient =
+            DatReader::<i64, _>::new(content.as_bytes(), path, &DatOptions::lenient());
+        let first = lenient.next().unwrap().unwrap();
+
+        assert_eq!(strict.len(), 2);
+        assert!(matches!(strict[1], Err(ReadError::Parse(ref error)) if error.line == 2));
+        assert_eq!(first.end.x, 1);
+        assert!(lenient.errors().is_empty());
+        assert_eq!(lenient.by_ref().count(), 1);
+        assert_eq!(lenient.errors().len(), 1);
+    }
+
+    #[test]
+    fn missing_file_should_be_io_error() {
+        let result =
+            read::<OrderedFloat<f64>>(Path::new("data/missing.dat"), &DatOptions::default());
+
+        assert!(matches!(result, Err(ReadError::Io { .. })));
+    }
+}
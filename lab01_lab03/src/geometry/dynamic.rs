@@ -0,0 +1,362 @@
+//! Set of line segments which are inserted and removed one by one, e.g. by an editor, with the
+//! intersections kept up to date after every change.
+//!
+//! The segments are indexed by a uniform grid of their bounding boxes like [GridHandler]. Inserting
+//! a segment only intersects it with the segments sharing a cell, removing it only forgets its own
+//! intersections, so a change costs O(c) with c the number of segments in the cells of the segment,
+//! instead of recomputing all intersections. Segments overlapping more than [MAX_CELLS] cells, e.g.
+//! long segments in a set of short ones, aren't added to the cells but to a list of large segments,
+//! which every insertion checks. Inserting a large segment checks all segments.
+//!
+//! Every segment is identified by the [SegmentId] returned on insertion, identical segments can be
+//! inserted several times. Pairs are intersected in the order of their ids, exactly as the brute
+//! force intersects the lines in the order of their ids, so the intersections equal the ones of the
+//! brute force for the current segments.
+//!
+//! [GridHandler]: crate::geometry::grid::handler::GridHandler
+use crate::geometry::bounding_box::BoundingBox;
+use crate::geometry::grid;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Column and row of a cell
+type Cell = (i64, i64);
+
+/// Largest number of cells a segment is added to, larger segments are kept in a separate list
+pub const MAX_CELLS: usize = 16;
+
+/// Identifier of an inserted segment, never reused after the segment is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SegmentId(usize);
+
+impl SegmentId {
+    /// Position of the segment in the order of insertion.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+struct Segment<T: Scalar> {
+    line: Line2D<T>,
+    bounds: BoundingBox<T>,
+    /// Intersections with the other segments by their ids
+    intersections: BTreeMap<SegmentId, Intersection<T>>,
+}
+
+pub struct DynamicSegments<T: Scalar = OrderedFloat<f64>> {
+    segments: HashMap<SegmentId, Segment<T>>,
+    cells: HashMap<Cell, Vec<SegmentId>>,
+    /// Segments overlapping more than [MAX_CELLS] cells
+    large: BTreeSet<SegmentId>,
+    cell_size: f64,
+    next_id: usize,
+    /// Number of intersecting pairs
+    intersection_count: usize,
+}
+
+impl<T: Scalar> DynamicSegments<T> {
+    /// Creates an empty set indexed by cells of the given size, which should be about the extent of
+    /// a typical segment.
+    pub fn new(cell_size: f64) -> Self {
+        assert!(
+            cell_size.is_finite() && cell_size > 0.0,
+            "Cell size has to be positive: {}",
+            cell_size
+        );
+        DynamicSegments {
+            segments: HashMap::new(),
+            cells: HashMap::new(),
+            large: BTreeSet::new(),
+            cell_size,
+            next_id: 0,
+            intersection_count: 0,
+        }
+    }
+
+    /// Creates a set of the lines with the cell size the grid backend would choose for them.
+    /// The ids of the lines are their positions.
+    pub fn from_lines(lines: Vec<Line2D<T>>) -> Self {
+        let boxes: Vec<BoundingBox<T>> = lines.iter().map(BoundingBox::of).collect();
+        let (_, cell_size) = grid::handler::cell_size(&boxes);
+        let mut segments = Self::new(cell_size);
+        for line in lines {
+            segments.insert(line);
+        }
+        segments
+    }
+
+    /// Adds the segment and computes its intersections with the segments sharing a cell and the
+    /// large segments.
+    pub fn insert(&mut self, line: Line2D<T>) -> SegmentId {
+        let id = SegmentId(self.next_id);
+        self.next_id += 1;
+        let bounds = BoundingBox::of(&line);
+
+        let mut candidates: HashSet<SegmentId> = HashSet::new();
+        match self.cells_of(&bounds) {
+            Some(cells) => {
+                for cell in cells {
+                    let ids = self.cells.entry(cell).or_default();
+                    candidates.extend(ids.iter());
+                    ids.push(id);
+                }
+                candidates.extend(&self.large);
+            }
+            None => {
+                candidates.extend(self.segments.keys());
+                self.large.insert(id);
+            }
+        }
+
+        let mut intersections = BTreeMap::new();
+        for other in candidates {
+            let segment = &self.segments[&other];
+            if !segment.bounds.intersects(&bounds) || !segment.line.intersects(line.clone()) {
+                continue;
+            }
+            // the older segment first, as the brute force does
+            let intersection = segment
+                .line
+                .find_intersection(line.clone())
+                .expect("Intersection not found!");
+            intersections.insert(other, intersection);
+        }
+
+        for (other, intersection) in &intersections {
+            if let Some(segment) = self.segments.get_mut(other) {
+                segment.intersections.insert(id, intersection.clone());
+            }
+        }
+        self.intersection_count += intersections.len();
+        self.segments.insert(
+            id,
+            Segment {
+                line,
+                bounds,
+                intersections,
+            },
+        );
+        id
+    }
+
+    /// Removes the segment with its intersections, None if there is no segment with the id.
+    pub fn remove(&mut self, id: SegmentId) -> Option<Line2D<T>> {
+        let segment = self.segments.remove(&id)?;
+        match self.cells_of(&segment.bounds) {
+            Some(cells) => {
+                for cell in cells {
+                    if let Some(ids) = self.cells.get_mut(&cell) {
+                        ids.retain(|&other| other != id);
+                        if ids.is_empty() {
+                            self.cells.remove(&cell);
+                        }
+                    }
+                }
+            }
+            None => {
+                self.large.remove(&id);
+            }
+        }
+        for other in segment.intersections.keys() {
+            if let Some(other) = self.segments.get_mut(other) {
+                other.intersections.remove(&id);
+            }
+        }
+        self.intersection_count -= segment.intersections.len();
+        Some(segment.line)
+    }
+
+    pub fn get(&self, id: SegmentId) -> Option<&Line2D<T>> {
+        self.segments.get(&id).map(|segment| &segment.line)
+    }
+
+    /// Intersections involving the segment, with the ids of the other segments in ascending order.
+    /// Empty if there is no segment with the id.
+    pub fn intersections_of(
+        &self,
+        id: SegmentId,
+    ) -> impl Iterator<Item = (SegmentId, &Intersection<T>)> + '_ {
+        self.segments
+            .get(&id)
+            .into_iter()
+            .flat_map(|segment| segment.intersections.iter())
+            .map(|(&other, intersection)| (other, intersection))
+    }
+
+    /// All current intersections.
+    pub fn intersections(&self) -> HashSet<Intersection<T>> {
+        self.segments
+            .iter()
+            .flat_map(|(id, segment)| {
+                // every pair is stored at both segments
+                segment
+                    .intersections
+                    .range(*id..)
+                    .map(|(_, intersection)| intersection.clone())
+            })
+            .collect()
+    }
+
+    /// Number of intersecting pairs of segments.
+    pub fn intersection_count(&self) -> usize {
+        self.intersection_count
+    }
+
+    /// Ids and segments in the order of insertion.
+    pub fn segments(&self) -> Vec<(SegmentId, &Line2D<T>)> {
+        let mut segments: Vec<(SegmentId, &Line2D<T>)> = self
+            .segments
+            .iter()
+            .map(|(&id, segment)| (id, &segment.line))
+            .collect();
+        segments.sort_by_key(|(id, _)| *id);
+        segments
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Cells overlapped by the box, None if these are more than [MAX_CELLS] or the box exceeds the
+    /// range of floats. Rounding the coordinates to floats is monotonic, so boxes which intersect
+    /// always share a cell.
+    fn cells_of(&self, bounds: &BoundingBox<T>) -> Option<Vec<Cell>> {
+        let cell = |x: &T, y: &T| {
+            (
+                (x.to_f64() / self.cell_size).floor(),
+                (y.to_f64() / self.cell_size).floor(),
+            )
+        };
+        let (column_min, row_min) = cell(&bounds.x_min, &bounds.y_min);
+        let (column_max, row_max) = cell(&bounds.x_max, &bounds.y_max);
+        let count = (column_max - column_min + 1.0) * (row_max - row_min + 1.0);
+        if !count.is_finite() || count > MAX_CELLS as f64 {
+            return None;
+        }
+
+        // the indices saturate for huge coordinates, which keeps intersecting boxes in a shared cell
+        let (column_min, row_min) = (column_min as i64, row_min as i64);
+        let (column_max, row_max) = (column_max as i64, row_max as i64);
+        let mut cells = Vec::new();
+        for column in column_min..=column_max {
+            for row in row_min..=row_max {
+                cells.push((column, row));
+            }
+        }
+        Some(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::generator;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn brute_force(segments: &DynamicSegments) -> HashSet<Intersection> {
+        let lines = segments
+            .segments()
+            .into_iter()
+            .map(|(_, line)| *line)
+            .collect();
+        BruteForceHandler::new(lines).run()
+    }
+
+    #[test]
+    fn random_edits_should_keep_the_intersections_of_the_brute_force() {
+        let lines = generator::degenerate_lines(600, 5);
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let mut segments = DynamicSegments::new(4.0);
+        let mut ids = Vec::new();
+
+        for (step, line) in lines.into_iter().enumerate() {
+            ids.push(segments.insert(line));
+            if rng.gen_bool(0.3) {
+                let id = ids.swap_remove(rng.gen_range(0..ids.len()));
+                assert!(segments.remove(id).is_some());
+                assert!(segments.remove(id).is_none());
+            }
+            if step % 100 == 99 {
+                let expected = brute_force(&segments);
+                assert_eq!(segments.intersections(), expected);
+                assert_eq!(segments.intersection_count(), expected.len());
+            }
+        }
+        assert_eq!(segments.len(), ids.len());
+    }
+
+    #[test]
+    fn intersections_of_a_segment_should_be_queried_by_its_id() {
+        let mut segments: DynamicSegments = DynamicSegments::new(1.0);
+        let diagonal = segments.insert(Line2D::new(0.0, 0.0, 4.0, 4.0));
+        let crossing = segments.insert(Line2D::new(0.0, 4.0, 4.0, 0.0));
+        let far = segments.insert(Line2D::new(10.0, 10.0, 11.0, 10.0));
+        let copy = segments.insert(Line2D::new(0.0, 0.0, 4.0, 4.0));
+
+        let others: Vec<SegmentId> = segments
+            .intersections_of(diagonal)
+            .map(|(id, _)| id)
+            .collect();
+
+        assert_eq!(others, vec![crossing, copy]);
+        assert!(matches!(
+            segments.intersections_of(copy).next(),
+            Some((id, Intersection::IdenticalOverlap { .. })) if id == diagonal
+        ));
+        assert_eq!(segments.intersections_of(far).count(), 0);
+        assert_eq!(segments.intersection_count(), 3);
+
+        segments.remove(diagonal);
+
+        assert_eq!(segments.intersections_of(diagonal).count(), 0);
+        assert_eq!(segments.intersections_of(crossing).count(), 1);
+        assert_eq!(segments.intersection_count(), 1);
+    }
+
+    #[test]
+    fn large_segments_should_be_kept_out_of_the_cells() {
+        let mut segments: DynamicSegments = DynamicSegments::from_lines(Vec::new());
+        let long = segments.insert(Line2D::new(0.0, 0.0, 1e10, 1e10));
+        let huge = segments.insert(Line2D::new(-1e300, 1.0, 1e300, 1.0));
+        let short = segments.insert(Line2D::new(5.0, 6.0, 6.0, 5.0));
+
+        assert!(segments.cells.values().flatten().all(|&id| id == short));
+        assert_eq!(segments.intersection_count(), 2);
+        assert_eq!(segments.intersections(), brute_force(&segments));
+
+        segments.remove(long);
+        segments.remove(huge);
+
+        assert!(segments.large.is_empty());
+        assert_eq!(segments.intersection_count(), 0);
+        assert_eq!(segments.intersections_of(short).count(), 0);
+    }
+
+    #[test]
+    fn set_of_lines_should_number_them_by_position() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+            Line2D::new(1.0, 0.0, 1.0, 5.0),
+        ];
+
+        let segments = DynamicSegments::from_lines(lines.clone());
+
+        assert_eq!(
+            segments.intersections(),
+            BruteForceHandler::new(lines.clone()).run()
+        );
+        assert_eq!(segments.get(SegmentId(2)), Some(&lines[2]));
+        assert_eq!(segments.segments()[1].0.index(), 1);
+        assert!(DynamicSegments::<i64>::from_lines(Vec::new()).is_empty());
+    }
+}
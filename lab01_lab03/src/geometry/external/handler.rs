@@ -1,45 +1,79 @@
-use std::collections::HashSet;
-use geo::{coord, Line, LineIntersection};
-use geo::line_intersection::line_intersection;
 use crate::geometry::intersection::Intersection;
 use crate::geometry::line::Line2D;
 use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use geo::line_intersection::line_intersection;
+use geo::{coord, Line, LineIntersection};
+use ordered_float::OrderedFloat;
+use std::collections::HashSet;
 
-pub struct GeoHandler {
-    lines: Vec<Line>
+/// Brute force with the intersection test of the geo library, which computes in f64.
+/// Lines of other scalars are converted to f64, the intersections refer to the original lines.
+/// Intersection points which aren't representable in the scalar panic, so the algorithm registry
+/// only creates it for f64.
+pub struct GeoHandler<T: Scalar = OrderedFloat<f64>> {
+    lines: Vec<Line>,
+    originals: Vec<Line2D<T>>,
+    /// number of red lines at the start of the lines in the bichromatic mode
+    red_count: Option<usize>,
 }
 
 impl GeoHandler {
     pub fn new(lines: Vec<Line2D>) -> Self {
-        let geo_lines: Vec<Line<f64>> = lines.iter().map(|line| GeoHandler::transform_to_geo_line(*line)).collect();
+        GeoHandler::from_lines(lines, None)
+    }
+}
+
+impl<T: Scalar> GeoHandler<T> {
+    /// Creates a handler which only reports intersections of the first red_count lines with the others, if given.
+    pub fn from_lines(lines: Vec<Line2D<T>>, red_count: Option<usize>) -> Self {
+        let geo_lines: Vec<Line<f64>> = lines.iter().map(Self::transform_to_geo_line).collect();
 
         Self {
-            lines: geo_lines
+            lines: geo_lines,
+            originals: lines,
+            red_count,
         }
     }
 
-    pub fn run(&self) -> HashSet<Intersection> {
+    pub fn run(&self) -> HashSet<Intersection<T>> {
         let mut intersections = HashSet::new();
 
         for i in 0..self.lines.len() {
-            for j in i + 1..self.lines.len() {
-                let line1 = self.lines[i];
-                let line2 = self.lines[j];
-                let intersection = line_intersection(line1, line2);
-                if intersection.is_some() {
-                    intersections.insert(Self::transform_to_intersection(intersection.unwrap(), line1, line2));
+            let first = match self.red_count {
+                Some(red_count) if i < red_count => red_count,
+                Some(_) => break,
+                None => i + 1,
+            };
+            for j in first..self.lines.len() {
+                if let Some(intersection) = line_intersection(self.lines[i], self.lines[j]) {
+                    intersections.insert(self.transform_to_intersection(intersection, i, j));
                 }
             }
         }
         intersections
     }
 
-    fn transform_to_intersection(geo_intersection: LineIntersection<f64>, line_a: Line, line_b: Line) -> Intersection {
-        let line1 = Line2D::new(line_a.start.x, line_a.start.y, line_a.end.x, line_a.end.y);
-        let line2 = Line2D::new(line_b.start.x, line_b.start.y, line_b.end.x, line_b.end.y);
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn transform_to_intersection(
+        &self,
+        geo_intersection: LineIntersection<f64>,
+        i: usize,
+        j: usize,
+    ) -> Intersection<T> {
+        let line1 = self.originals[i].clone();
+        let line2 = self.originals[j].clone();
         match geo_intersection {
-            LineIntersection::SinglePoint { intersection, is_proper } => {
-                let point = Point2D::new(intersection.x, intersection.y);
+            LineIntersection::SinglePoint {
+                intersection,
+                is_proper,
+            } => {
+                let point = Point2D::new(intersection.x, intersection.y)
+                    .convert()
+                    .expect("Intersection point not representable");
                 if is_proper {
                     Intersection::Crossing {
                         line1,
@@ -50,24 +84,29 @@ impl GeoHandler {
                     Intersection::Touching {
                         line1,
                         line2,
-                        point
+                        point,
                     }
                 }
             }
-            LineIntersection::Collinear { intersection } => {
-                Intersection::PartialOverlap {
-                    line1,
-                    line2,
-                    overlap: Line2D::new(intersection.start.x, intersection.start.y, intersection.end.x, intersection.end.y)
-                }
-
-            }
+            LineIntersection::Collinear { intersection } => Intersection::PartialOverlap {
+                line1,
+                line2,
+                overlap: Line2D::new(
+                    intersection.start.x,
+                    intersection.start.y,
+                    intersection.end.x,
+                    intersection.end.y,
+                )
+                .convert()
+                .expect("Overlap not representable"),
+            },
         }
-
     }
 
-    fn transform_to_geo_line(line: Line2D) -> Line<f64> {
-        Line::new(coord!(x: line.start.x.0, y: line.start.y.0), coord!(x: line.end.x.0, y: line.end.y.0))
+    fn transform_to_geo_line(line: &Line2D<T>) -> Line<f64> {
+        Line::new(
+            coord!(x: line.start.x.to_f64(), y: line.start.y.to_f64()),
+            coord!(x: line.end.x.to_f64(), y: line.end.y.to_f64()),
+        )
     }
 }
-
@@ -0,0 +1,69 @@
+//! Comma separated values: segments as `x1,y1,x2,y2` rows below a header, intersections as
+//! `variant,geometry,line1,line2` rows with the geometries in WKT.
+use super::wkt::to_wkt;
+use super::{geometry, positions};
+use crate::geometry::dat::{self, DatOptions, DatReport, ReadError, Separator};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
+use std::io::{self, Write};
+use std::path::Path;
+
+const SEGMENT_HEADER: &str = "x1,y1,x2,y2";
+
+/// Parses the rows like a comma separated `.dat` file, skipping the header if present.
+pub fn parse<T: Scalar>(
+    content: &str,
+    path: &Path,
+    options: &DatOptions,
+) -> Result<DatReport<T>, ReadError> {
+    let header = content
+        .lines()
+        .next()
+        .is_some_and(|row| row.trim().eq_ignore_ascii_case(SEGMENT_HEADER));
+    let options = DatOptions {
+        separator: Separator::Comma,
+        header_lines: options.header_lines + usize::from(header),
+        ..options.clone()
+    };
+    dat::parse(content.as_bytes(), path, &options)
+}
+
+pub fn write_segments<T: Scalar, W: Write>(writer: &mut W, lines: &[Line2D<T>]) -> io::Result<()> {
+    writeln!(writer, "{}", SEGMENT_HEADER)?;
+    for line in lines {
+        let [[x1, y1], [x2, y2]] = positions(line);
+        writeln!(writer, "{},{},{},{}", x1, y1, x2, y2)?;
+    }
+    Ok(())
+}
+
+pub fn write_intersections<T: Scalar, W: Write>(
+    writer: &mut W,
+    intersections: &[&Intersection<T>],
+) -> io::Result<()> {
+    write_intersection_header(writer)?;
+    for intersection in intersections {
+        write_intersection(writer, intersection)?;
+    }
+    Ok(())
+}
+
+pub fn write_intersection_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "variant,geometry,line1,line2")
+}
+
+pub fn write_intersection<T: Scalar, W: Write>(
+    writer: &mut W,
+    intersection: &Intersection<T>,
+) -> io::Result<()> {
+    let (line1, line2) = intersection.lines();
+    writeln!(
+        writer,
+        "{},\"{}\",\"{}\",\"{}\"",
+        intersection.variant(),
+        to_wkt(&geometry(intersection)),
+        to_wkt(&positions(line1)),
+        to_wkt(&positions(line2))
+    )
+}
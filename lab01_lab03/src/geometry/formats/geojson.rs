@@ -0,0 +1,369 @@
+//! GeoJSON: `LineString` and `MultiLineString` geometries, bare or inside features, feature
+//! collections and geometry collections. Features without geometry are skipped.
+//!
+//! Segments are written as feature collection of `LineString` features with their index,
+//! intersections as `Point` or `LineString` features with the variant and both lines as properties.
+use super::{approximate, exact_number, explode, geometry, positions};
+use crate::geometry::dat::{DatOptions, DatReport, ParseError, ParseErrorKind, ReadError};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use serde_json::{json, Map, Number, Value};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Error without the file: the pointer to the offending value, the token and the kind.
+type Failure = (String, String, ParseErrorKind);
+
+/// Parses the document, the path is only used for the errors.
+pub fn parse<T: Scalar>(
+    content: &str,
+    path: &Path,
+    options: &DatOptions,
+) -> Result<DatReport<T>, ReadError> {
+    let document: Value = serde_json::from_str(content).map_err(|error| {
+        ReadError::Parse(ParseError {
+            file: path.to_path_buf(),
+            line: error.line(),
+            column: error.column(),
+            pointer: None,
+            token: String::new(),
+            kind: ParseErrorKind::Json(error.to_string()),
+        })
+    })?;
+
+    let mut lines = Vec::new();
+    let mut failures = Vec::new();
+    collect::<T>(&document, String::new(), &mut lines, &mut failures);
+
+    let errors: Vec<ParseError> = failures
+        .into_iter()
+        .map(|(pointer, token, kind)| ParseError {
+            file: path.to_path_buf(),
+            line: 0,
+            column: 0,
+            pointer: Some(pointer),
+            token,
+            kind,
+        })
+        .collect();
+    if let (false, Some(error)) = (options.lenient, errors.first()) {
+        return Err(ReadError::Parse(error.clone()));
+    }
+
+    Ok(DatReport {
+        segments: LineSegments2D { lines },
+        errors,
+    })
+}
+
+/// Collects the segments of the object at the pointer, malformed geometries are skipped.
+fn collect<T: Scalar>(
+    object: &Value,
+    pointer: String,
+    lines: &mut Vec<Line2D<T>>,
+    failures: &mut Vec<Failure>,
+) {
+    let kind = match object.get("type").and_then(Value::as_str) {
+        Some(kind) => kind,
+        None => {
+            let token = object.get("type").map_or(String::new(), Value::to_string);
+            failures.push((
+                format!("{}/type", pointer),
+                token,
+                ParseErrorKind::Syntax {
+                    expected: "GeoJSON type",
+                },
+            ));
+            return;
+        }
+    };
+
+    match kind {
+        "FeatureCollection" | "GeometryCollection" => {
+            let member = if kind == "FeatureCollection" {
+                "features"
+            } else {
+                "geometries"
+            };
+            match object.get(member).and_then(Value::as_array) {
+                Some(members) => {
+                    for (i, member_object) in members.iter().enumerate() {
+                        collect(
+                            member_object,
+                            format!("{}/{}/{}", pointer, member, i),
+                            lines,
+                            failures,
+                        );
+                    }
+                }
+                None => failures.push((
+                    format!("{}/{}", pointer, member),
+                    String::new(),
+                    ParseErrorKind::Syntax { expected: "array" },
+                )),
+            }
+        }
+        "Feature" => match object.get("geometry") {
+            None | Some(Value::Null) => {}
+            Some(geometry) => collect(geometry, format!("{}/geometry", pointer), lines, failures),
+        },
+        "LineString" | "MultiLineString" => {
+            let coordinates = object.get("coordinates").unwrap_or(&Value::Null);
+            let pointer = format!("{}/coordinates", pointer);
+            let linestrings = if kind == "LineString" {
+                parse_linestring(coordinates, &pointer).map(|linestring| vec![linestring])
+            } else {
+                as_array(coordinates, &pointer, "array of linestrings").and_then(|linestrings| {
+                    linestrings
+                        .iter()
+                        .enumerate()
+                        .map(|(i, linestring)| {
+                            parse_linestring(linestring, &format!("{}/{}", pointer, i))
+                        })
+                        .collect()
+                })
+            };
+            match linestrings {
+                Ok(linestrings) => {
+                    lines.extend(linestrings.iter().flat_map(|points| explode(points)))
+                }
+                Err(failure) => failures.push(failure),
+            }
+        }
+        other => failures.push((
+            format!("{}/type", pointer),
+            other.to_string(),
+            ParseErrorKind::UnsupportedGeometry,
+        )),
+    }
+}
+
+fn parse_linestring<T: Scalar>(
+    coordinates: &Value,
+    pointer: &str,
+) -> Result<Vec<Point2D<T>>, Failure> {
+    let positions = as_array(coordinates, pointer, "array of positions")?;
+    if positions.len() < 2 {
+        return Err((
+            pointer.to_string(),
+            coordinates.to_string(),
+            ParseErrorKind::Syntax {
+                expected: "at least two positions",
+            },
+        ));
+    }
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let pointer = format!("{}/{}", pointer, i);
+            match as_array(position, &pointer, "position")?.as_slice() {
+                [x, y, ..] => Ok(Point2D {
+                    x: parse_coordinate(x, &format!("{}/0", pointer))?,
+                    y: parse_coordinate(y, &format!("{}/1", pointer))?,
+                }),
+                _ => Err((
+                    pointer,
+                    position.to_string(),
+                    ParseErrorKind::Syntax {
+                        expected: "position",
+                    },
+                )),
+            }
+        })
+        .collect()
+}
+
+fn parse_coordinate<T: Scalar>(value: &Value, pointer: &str) -> Result<T, Failure> {
+    value
+        .as_number()
+        .and_then(|number| T::parse(&number.to_string()))
+        .ok_or_else(|| {
+            (
+                pointer.to_string(),
+                value.to_string(),
+                ParseErrorKind::InvalidCoordinate { scalar: T::NAME },
+            )
+        })
+}
+
+fn as_array<'a>(
+    value: &'a Value,
+    pointer: &str,
+    expected: &'static str,
+) -> Result<&'a Vec<Value>, Failure> {
+    value.as_array().ok_or_else(|| {
+        (
+            pointer.to_string(),
+            value.to_string(),
+            ParseErrorKind::Syntax { expected },
+        )
+    })
+}
+
+/// Writes the feature collection with one feature per row.
+fn write_features<W: Write>(
+    writer: &mut W,
+    features: impl Iterator<Item = Value>,
+) -> io::Result<()> {
+    write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+    for (i, feature) in features.enumerate() {
+        let separator = if i == 0 { "" } else { "," };
+        write!(writer, "{}\n{}", separator, feature)?;
+    }
+    writeln!(writer, "\n]}}")
+}
+
+/// Feature of the geometry with the named lines as properties. If a coordinate of the geometry or
+/// the lines isn't exact as JSON number, the `exact` property holds them with the coordinates as
+/// displayed by their type, e.g. `1/3`.
+fn feature<T: Scalar>(
+    geometry: &[[String; 2]],
+    lines: &[(&str, [[String; 2]; 2])],
+    mut properties: Map<String, Value>,
+) -> Value {
+    let (coordinates, mut exact) = to_json::<T>(geometry);
+    let mut exact_properties = Map::new();
+    exact_properties.insert("coordinates".to_string(), json!(geometry));
+    for (name, positions) in lines {
+        let (line, exact_line) = to_json::<T>(positions);
+        exact &= exact_line;
+        properties.insert(name.to_string(), json!(line));
+        exact_properties.insert(name.to_string(), json!(positions));
+    }
+    if !exact {
+        properties.insert("exact".to_string(), Value::Object(exact_properties));
+    }
+    let geometry = match coordinates.as_slice() {
+        [point] => json!({ "type": "Point", "coordinates": point }),
+        _ => json!({ "type": "LineString", "coordinates": coordinates }),
+    };
+    json!({ "type": "Feature", "geometry": geometry, "properties": properties })
+}
+
+/// Positions as JSON arrays of numbers and whether all of them are exact. A coordinate is exact if
+/// its number reads back as the same value of the field of T, which holds the coordinates of the
+/// lines and the intersections, otherwise it is written as nearest float.
+fn to_json<T: Scalar>(positions: &[[String; 2]]) -> (Vec<Value>, bool) {
+    let mut exact = true;
+    let mut coordinate = |text: &String| {
+        let number = exact_number(text)
+            .and_then(|literal| serde_json::from_str::<Number>(&literal).ok())
+            .filter(|number| T::Field::parse(&number.to_string()) == T::Field::parse(text));
+        match number {
+            Some(number) => Value::Number(number),
+            None => {
+                exact = false;
+                json!(approximate(text))
+            }
+        }
+    };
+    let coordinates = positions
+        .iter()
+        .map(|[x, y]| json!([coordinate(x), coordinate(y)]))
+        .collect();
+    (coordinates, exact)
+}
+
+pub fn write_segments<T: Scalar, W: Write>(writer: &mut W, lines: &[Line2D<T>]) -> io::Result<()> {
+    write_features(
+        writer,
+        lines.iter().enumerate().map(|(index, line)| {
+            let properties = Map::from_iter([("index".to_string(), json!(index))]);
+            feature::<T>(&positions(line), &[], properties)
+        }),
+    )
+}
+
+pub fn write_intersections<T: Scalar, W: Write>(
+    writer: &mut W,
+    intersections: &[&Intersection<T>],
+) -> io::Result<()> {
+    write_features(
+        writer,
+        intersections.iter().map(|intersection| {
+            let (line1, line2) = intersection.lines();
+            let properties =
+                Map::from_iter([("variant".to_string(), json!(intersection.variant()))]);
+            feature::<T>(
+                &geometry(intersection),
+                &[("line1", positions(line1)), ("line2", positions(line2))],
+                properties,
+            )
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordered_float::OrderedFloat;
+
+    fn parse_str<T: Scalar>(
+        content: &str,
+        options: &DatOptions,
+    ) -> Result<DatReport<T>, ReadError> {
+        parse(content, Path::new("test.geojson"), options)
+    }
+
+    #[test]
+    fn should_explode_features_and_collections() {
+        let content = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {}, "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1, 5], [2, 0]]}},
+            {"type": "Feature", "properties": {}, "geometry": null},
+            {"type": "Feature", "properties": {}, "geometry": {"type": "GeometryCollection", "geometries": [
+                {"type": "MultiLineString", "coordinates": [[[0, 1], [1, 2]], [[3, 3], [4, 4]]]}
+            ]}}
+        ]}"#;
+
+        let report = parse_str::<i64>(content, &DatOptions::default()).unwrap();
+
+        let expected: Vec<Line2D<i64>> = [(0, 0, 1, 1), (1, 1, 2, 0), (0, 1, 1, 2), (3, 3, 4, 4)]
+            .iter()
+            .map(|&(x1, y1, x2, y2)| Line2D {
+                start: Point2D { x: x1, y: y1 },
+                end: Point2D { x: x2, y: y2 },
+            })
+            .collect();
+        assert_eq!(report.segments.lines, expected);
+    }
+
+    #[test]
+    fn should_locate_errors_by_pointer() {
+        let content = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, "a"]]}}
+        ]}"#;
+
+        let strict = parse_str::<OrderedFloat<f64>>(content, &DatOptions::default());
+        let lenient = parse_str::<OrderedFloat<f64>>(content, &DatOptions::lenient()).unwrap();
+
+        match strict {
+            Err(ReadError::Parse(error)) => assert_eq!(
+                error.to_string(),
+                "test.geojson#/features/1/geometry/type: unsupported geometry 'Point'"
+            ),
+            other => panic!("Expected parse error, got {:?}", other.map(|r| r.segments)),
+        }
+        assert_eq!(lenient.segments.lines.len(), 1);
+        assert_eq!(
+            lenient.errors[1].pointer.as_deref(),
+            Some("/features/2/geometry/coordinates/1/1")
+        );
+    }
+
+    #[test]
+    fn invalid_json_should_report_position() {
+        match parse_str::<OrderedFloat<f64>>("{\n  \"type\": }", &DatOptions::default()) {
+            Err(ReadError::Parse(error)) => {
+                assert_eq!((error.line, error.column), (2, 11));
+                assert!(matches!(error.kind, ParseErrorKind::Json(_)));
+            }
+            other => panic!("Expected parse error, got {:?}", other.map(|r| r.segments)),
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! Reading and writing of line segments and intersections in common exchange formats.
+//!
+//! The format is chosen by the file extension: `.wkt`, `.geojson` or `.json`, `.csv` and `.dat`
+//! for everything else. Linestrings are exploded into their segments. Intersections are written as
+//! points (crossing and touching) or linestrings (overlaps) together with their variant, except for
+//! WKT, which has no properties.
+//!
+//! WKT and GeoJSON only allow numbers as coordinates. Integers, floats and fractions with a finite
+//! decimal expansion are written exactly, other fractions like `1/3` as their nearest float.
+//! GeoJSON features then keep the exact coordinates in their `exact` property. The segment rows of
+//! CSV files are written exactly as the coordinate type displays them, like `.dat` files, so
+//! fractions are read back with the rational coordinates.
+pub mod csv;
+pub mod geojson;
+pub mod wkt;
+
+use crate::geometry::dat::{self, DatOptions, DatReport, ReadError};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Dat,
+    Wkt,
+    GeoJson,
+    Csv,
+}
+
+impl Format {
+    /// Format of the file extension, `.dat` for unknown extensions.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("wkt") => Format::Wkt,
+            Some("geojson") | Some("json") => Format::GeoJson,
+            Some("csv") => Format::Csv,
+            _ => Format::Dat,
+        }
+    }
+}
+
+/// Reads the segments of the file in the format of its extension.
+/// Only the lenient option applies to formats other than `.dat`.
+pub fn read<T: Scalar>(path: &Path, options: &DatOptions) -> Result<DatReport<T>, ReadError> {
+    let format = Format::from_path(path);
+    if format == Format::Dat {
+        return dat::read(path, options);
+    }
+    let content = fs::read_to_string(path).map_err(|source| ReadError::Io {
+        file: path.to_path_buf(),
+        source,
+    })?;
+    match format {
+        Format::Wkt => wkt::parse(&content, path, options),
+        Format::GeoJson => geojson::parse(&content, path, options),
+        Format::Csv => csv::parse(&content, path, options),
+        Format::Dat => unreachable!(),
+    }
+}
+
+/// Writes the segments in the format of the file extension.
+pub fn write_segments<T: Scalar>(path: &Path, segments: &LineSegments2D<T>) -> io::Result<()> {
+    let format = Format::from_path(path);
+    if format == Format::Dat {
+        return segments.to_dat(path);
+    }
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    match format {
+        Format::Wkt => wkt::write_segments(&mut writer, &segments.lines)?,
+        Format::GeoJson => geojson::write_segments(&mut writer, &segments.lines)?,
+        Format::Csv => csv::write_segments(&mut writer, &segments.lines)?,
+        Format::Dat => unreachable!(),
+    }
+    writer.flush()
+}
+
+/// Writes the intersections in the format of the file extension, ordered by their variant and geometry.
+/// Fails for `.dat` files, which can only hold segments.
+pub fn write_intersections<T: Scalar>(
+    path: &Path,
+    intersections: &HashSet<Intersection<T>>,
+) -> io::Result<()> {
+    let format = Format::from_path(path);
+    if format == Format::Dat {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "intersections can't be written as .dat, use .wkt, .geojson or .csv",
+        ));
+    }
+    let intersections = sorted(intersections);
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    match format {
+        Format::Wkt => wkt::write_intersections(&mut writer, &intersections)?,
+        Format::GeoJson => geojson::write_intersections(&mut writer, &intersections)?,
+        Format::Csv => csv::write_intersections(&mut writer, &intersections)?,
+        Format::Dat => unreachable!(),
+    }
+    writer.flush()
+}
+
+/// Writes intersections one by one as they are found, in the order they are written.
+/// Only the row based formats `.csv` and `.wkt` can be written incrementally.
+pub struct IntersectionWriter<W: Write> {
+    writer: W,
+    format: Format,
+}
+
+impl IntersectionWriter<BufWriter<fs::File>> {
+    /// Creates the file, with the format of its extension.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let format = Format::from_path(path);
+        if !matches!(format, Format::Csv | Format::Wkt) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "intersections can only be written incrementally as .csv or .wkt",
+            ));
+        }
+        IntersectionWriter::new(BufWriter::new(fs::File::create(path)?), format)
+    }
+}
+
+impl<W: Write> IntersectionWriter<W> {
+    /// Writes the header of the format. Fails for formats which can't be written incrementally.
+    pub fn new(mut writer: W, format: Format) -> io::Result<Self> {
+        match format {
+            Format::Csv => csv::write_intersection_header(&mut writer)?,
+            Format::Wkt => {}
+            Format::Dat | Format::GeoJson => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{:?} can't be written incrementally", format),
+                ))
+            }
+        }
+        Ok(IntersectionWriter { writer, format })
+    }
+
+    pub fn write<T: Scalar>(&mut self, intersection: &Intersection<T>) -> io::Result<()> {
+        match self.format {
+            Format::Csv => csv::write_intersection(&mut self.writer, intersection),
+            _ => wkt::write_intersection(&mut self.writer, intersection),
+        }
+    }
+
+    /// Flushes the writer and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Orders the intersections for a deterministic output.
+fn sorted<T: Scalar>(intersections: &HashSet<Intersection<T>>) -> Vec<&Intersection<T>> {
+    let mut intersections: Vec<&Intersection<T>> = intersections.iter().collect();
+    intersections.sort_by_cached_key(|&intersection| {
+        (intersection.variant(), points(intersection), intersection)
+    });
+    intersections
+}
+
+/// Points of the intersection: one point or the two endpoints of the overlap.
+fn points<T: Scalar>(intersection: &Intersection<T>) -> Vec<Point2D<T::Field>> {
+    match intersection {
+        Intersection::Crossing { point, .. } | Intersection::Touching { point, .. } => {
+            vec![point.clone()]
+        }
+        Intersection::PartialOverlap { overlap, .. }
+        | Intersection::ContainedOverlap { overlap, .. }
+        | Intersection::IdenticalOverlap { overlap, .. } => {
+            vec![overlap.start.to_field(), overlap.end.to_field()]
+        }
+    }
+}
+
+/// Geometry of the intersection as exact positions: one point or the two endpoints of the overlap.
+fn geometry<T: Scalar>(intersection: &Intersection<T>) -> Vec<[String; 2]> {
+    points(intersection).iter().map(position).collect()
+}
+
+/// Endpoints of the line as exact positions.
+fn positions<T: Scalar>(line: &Line2D<T>) -> [[String; 2]; 2] {
+    [position(&line.start), position(&line.end)]
+}
+
+/// Coordinates of the point as displayed by their type, without rounding.
+fn position<T: Scalar>(point: &Point2D<T>) -> [String; 2] {
+    [point.x.to_string(), point.y.to_string()]
+}
+
+/// Numeric literal of a coordinate displayed by its type: the coordinate itself unless it is a
+/// fraction, the decimal expansion of a fraction if it is finite, None otherwise.
+fn exact_number(coordinate: &str) -> Option<String> {
+    if !coordinate.contains('/') {
+        return Some(coordinate.to_string());
+    }
+    let value = BigRational::from_str(coordinate).ok()?;
+    // the expansion is finite if the denominator only has the prime factors 2 and 5
+    let mut denominator = value.denom().clone();
+    let mut places = [0, 0];
+    for (factor, count) in [2, 5].into_iter().zip(&mut places) {
+        let factor = BigInt::from(factor);
+        while (&denominator % &factor).is_zero() {
+            denominator /= &factor;
+            *count += 1;
+        }
+    }
+    if !denominator.is_one() {
+        return None;
+    }
+    let places = places[0].max(places[1]);
+    let scaled = value.numer() * BigInt::from(10).pow(places) / value.denom();
+    let digits = format!("{:0>1$}", scaled.abs(), places as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - places as usize);
+    let sign = if scaled.is_negative() { "-" } else { "" };
+    Some(format!("{}{}.{}", sign, integer, fraction))
+}
+
+/// Numeric literal of a coordinate displayed by its type, the nearest float if it has no exact one.
+fn number(coordinate: &str) -> String {
+    exact_number(coordinate).unwrap_or_else(|| approximate(coordinate).to_string())
+}
+
+/// Nearest float of a coordinate displayed by its type.
+fn approximate(coordinate: &str) -> f64 {
+    <BigRational as Scalar>::parse(coordinate).map_or(f64::NAN, |value| Scalar::to_f64(&value))
+}
+
+/// Splits linestrings into their segments.
+fn explode<T: Scalar>(linestring: &[Point2D<T>]) -> Vec<Line2D<T>> {
+    linestring
+        .windows(2)
+        .map(|pair| Line2D {
+            start: pair[0].clone(),
+            end: pair[1].clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+    use num_rational::BigRational;
+    use ordered_float::OrderedFloat;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lab03_formats_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn format_should_follow_extension() {
+        assert_eq!(Format::from_path(Path::new("a.WKT")), Format::Wkt);
+        assert_eq!(Format::from_path(Path::new("a.geojson")), Format::GeoJson);
+        assert_eq!(Format::from_path(Path::new("a.json")), Format::GeoJson);
+        assert_eq!(Format::from_path(Path::new("a.csv")), Format::Csv);
+        assert_eq!(
+            Format::from_path(Path::new("data/s_1000_1.dat")),
+            Format::Dat
+        );
+        assert_eq!(Format::from_path(Path::new("lines")), Format::Dat);
+    }
+
+    #[test]
+    fn segments_should_round_trip_through_all_formats() {
+        let segments =
+            LineSegments2D::<OrderedFloat<f64>>::from_dat(Path::new("data/s_1000_10.dat")).unwrap();
+
+        for extension in ["wkt", "geojson", "csv", "dat"] {
+            let path = temp_file(&format!("segments.{}", extension));
+            write_segments(&path, &segments).unwrap();
+            let report = read::<OrderedFloat<f64>>(&path, &DatOptions::default()).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(report.segments.lines, segments.lines, "{}", extension);
+        }
+    }
+
+    #[test]
+    fn exact_numbers_should_expand_finite_fractions() {
+        assert_eq!(exact_number("-12"), Some("-12".to_string()));
+        assert_eq!(exact_number("0.1"), Some("0.1".to_string()));
+        assert_eq!(exact_number("3/4"), Some("0.75".to_string()));
+        assert_eq!(exact_number("-1/20"), Some("-0.05".to_string()));
+        assert_eq!(exact_number("41/8"), Some("5.125".to_string()));
+        assert_eq!(exact_number("1/3"), None);
+        assert_eq!(number("-2/3"), "-0.6666666666666666");
+    }
+
+    #[test]
+    fn exact_coordinates_should_round_trip_without_rounding() {
+        // 2^53 + 1 isn't a float
+        let big = (1_i64 << 53) + 1;
+        let integers = LineSegments2D {
+            lines: vec![Line2D {
+                start: Point2D { x: big, y: -big },
+                end: Point2D { x: 0, y: 1 },
+            }],
+        };
+        let rational = |numerator: i64, denominator: i64| {
+            BigRational::new(numerator.into(), denominator.into())
+        };
+        let rationals = LineSegments2D {
+            lines: vec![Line2D {
+                start: Point2D {
+                    x: rational(3, 4),
+                    y: rational(big, 1),
+                },
+                end: Point2D {
+                    x: rational(-5, 8),
+                    y: rational(1, 3),
+                },
+            }],
+        };
+
+        for extension in ["wkt", "geojson", "csv"] {
+            let path = temp_file(&format!("exact.{}", extension));
+            write_segments(&path, &integers).unwrap();
+            let report = read::<i64>(&path, &DatOptions::default()).unwrap();
+            assert_eq!(report.segments.lines, integers.lines, "{}", extension);
+
+            write_segments(&path, &rationals).unwrap();
+            let report = read::<BigRational>(&path, &DatOptions::default()).unwrap();
+            fs::remove_file(&path).unwrap();
+            let end = &report.segments.lines[0].end;
+            assert_eq!(report.segments.lines[0].start, rationals.lines[0].start);
+            assert_eq!(end.x, rational(-5, 8));
+            // only CSV keeps a fraction without finite decimal expansion
+            assert_eq!(end.y == rational(1, 3), extension == "csv", "{}", extension);
+        }
+    }
+
+    #[test]
+    fn fractions_should_be_written_as_numbers() {
+        let lines: Vec<Line2D<i64>> = vec![
+            Line2D {
+                start: Point2D { x: 0, y: 0 },
+                end: Point2D { x: 1, y: 1 },
+            },
+            Line2D {
+                start: Point2D { x: 0, y: 1 },
+                end: Point2D { x: 2, y: 0 },
+            },
+        ];
+        let intersections = Handler::new(lines, SweepLineOptions::degenerate_handling()).run();
+
+        for (extension, expected) in [
+            ("wkt", vec!["POINT (0.6666666666666666 0.6666666666666666)"]),
+            (
+                "geojson",
+                vec![
+                    "\"coordinates\":[0.6666666666666666,0.6666666666666666]",
+                    "\"exact\":{\"coordinates\":[[\"2/3\",\"2/3\"]]",
+                ],
+            ),
+        ] {
+            let path = temp_file(&format!("crossing.{}", extension));
+            write_intersections(&path, &intersections).unwrap();
+            let content = fs::read_to_string(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            for expected in expected {
+                assert!(content.contains(expected), "{}", content);
+            }
+        }
+    }
+
+    #[test]
+    fn intersections_should_be_written_with_their_variant() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+            Line2D::new(1.0, 1.0, 3.0, 3.0),
+        ];
+        let intersections = Handler::new(lines, SweepLineOptions::degenerate_handling()).run();
+
+        let path = temp_file("intersections.csv");
+        write_intersections(&path, &intersections).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let rows: Vec<&str> = content
+            .lines()
+            .map(|row| row.split(',').next().unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec!["variant", "Crossing", "PartialOverlap", "Touching"]
+        );
+        assert!(write_intersections(Path::new("intersections.dat"), &intersections).is_err());
+    }
+}
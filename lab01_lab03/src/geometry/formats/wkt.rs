@@ -0,0 +1,322 @@
+//! Well-known text: `LINESTRING` and `MULTILINESTRING` geometries, separated by whitespace.
+//!
+//! Keywords are case-insensitive, `EMPTY` geometries are skipped. Segments are written as
+//! `LINESTRING (x1 y1, x2 y2)`, one per row, intersections as `POINT` or `LINESTRING`.
+use super::{explode, geometry, number, positions};
+use crate::geometry::dat::{DatOptions, DatReport, ParseError, ParseErrorKind, ReadError};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Token with its 1-based row and column.
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+/// Error without the file: the index of the offending token and the kind.
+type Failure = (usize, ParseErrorKind);
+
+/// Parses the geometries of the text, the path is only used for the errors.
+pub fn parse<T: Scalar>(
+    content: &str,
+    path: &Path,
+    options: &DatOptions,
+) -> Result<DatReport<T>, ReadError> {
+    let tokens = tokenize(content);
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut position = 0;
+    while position < tokens.len() {
+        let start = position;
+        match parse_geometry(&tokens, &mut position) {
+            Ok(linestrings) => lines.extend(linestrings.iter().flat_map(|points| explode(points))),
+            Err((index, kind)) => {
+                let error = match tokens.get(index) {
+                    Some(token) => ParseError {
+                        file: path.to_path_buf(),
+                        line: token.line,
+                        column: token.column,
+                        pointer: None,
+                        token: token.text.to_string(),
+                        kind,
+                    },
+                    None => ParseError {
+                        file: path.to_path_buf(),
+                        line: content.lines().count().max(1),
+                        column: content.lines().last().map_or(0, |row| row.chars().count()) + 1,
+                        pointer: None,
+                        token: String::new(),
+                        kind,
+                    },
+                };
+                if !options.lenient {
+                    return Err(ReadError::Parse(error));
+                }
+                errors.push(error);
+                // continue with the next geometry
+                position = next_geometry(&tokens, start);
+            }
+        }
+    }
+
+    Ok(DatReport {
+        segments: LineSegments2D { lines },
+        errors,
+    })
+}
+
+/// Position of the first word outside of the parentheses after the geometry at the start.
+fn next_geometry(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0usize;
+    for (position, token) in tokens.iter().enumerate().skip(start + 1) {
+        match token.text {
+            "(" => depth += 1,
+            ")" => depth = depth.saturating_sub(1),
+            text if depth == 0
+                && !text.eq_ignore_ascii_case("EMPTY")
+                && text.chars().all(char::is_alphabetic) =>
+            {
+                return position
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Parses a geometry starting at the position into its linestrings.
+fn parse_geometry<T: Scalar>(
+    tokens: &[Token],
+    position: &mut usize,
+) -> Result<Vec<Vec<Point2D<T>>>, Failure> {
+    let keyword = *position;
+    *position += 1;
+    let multi = match tokens[keyword].text.to_ascii_uppercase().as_str() {
+        "LINESTRING" => false,
+        "MULTILINESTRING" => true,
+        text if text.chars().all(char::is_alphabetic) => {
+            return Err((keyword, ParseErrorKind::UnsupportedGeometry))
+        }
+        _ => {
+            return Err((
+                keyword,
+                ParseErrorKind::Syntax {
+                    expected: "LINESTRING or MULTILINESTRING",
+                },
+            ))
+        }
+    };
+    if peek(tokens, *position).is_some_and(|text| text.eq_ignore_ascii_case("EMPTY")) {
+        *position += 1;
+        return Ok(Vec::new());
+    }
+
+    if !multi {
+        return Ok(vec![parse_points(tokens, position)?]);
+    }
+    expect(tokens, position, "(")?;
+    let mut linestrings = vec![parse_points(tokens, position)?];
+    while peek(tokens, *position) == Some(",") {
+        *position += 1;
+        linestrings.push(parse_points(tokens, position)?);
+    }
+    expect(tokens, position, ")")?;
+    Ok(linestrings)
+}
+
+/// Parses `(x1 y1, x2 y2, ...)`.
+fn parse_points<T: Scalar>(
+    tokens: &[Token],
+    position: &mut usize,
+) -> Result<Vec<Point2D<T>>, Failure> {
+    expect(tokens, position, "(")?;
+    let mut points = vec![parse_point(tokens, position)?];
+    while peek(tokens, *position) == Some(",") {
+        *position += 1;
+        points.push(parse_point(tokens, position)?);
+    }
+    if points.len() < 2 {
+        return Err((*position, ParseErrorKind::Syntax { expected: "','" }));
+    }
+    expect(tokens, position, ")")?;
+    Ok(points)
+}
+
+fn parse_point<T: Scalar>(tokens: &[Token], position: &mut usize) -> Result<Point2D<T>, Failure> {
+    let x = parse_coordinate(tokens, position)?;
+    let y = parse_coordinate(tokens, position)?;
+    Ok(Point2D { x, y })
+}
+
+fn parse_coordinate<T: Scalar>(tokens: &[Token], position: &mut usize) -> Result<T, Failure> {
+    let index = *position;
+    let coordinate = match peek(tokens, index) {
+        Some("(" | ")" | ",") | None => {
+            return Err((
+                index,
+                ParseErrorKind::Syntax {
+                    expected: "coordinate",
+                },
+            ))
+        }
+        Some(text) => {
+            T::parse(text).ok_or((index, ParseErrorKind::InvalidCoordinate { scalar: T::NAME }))?
+        }
+    };
+    *position += 1;
+    Ok(coordinate)
+}
+
+fn peek<'a>(tokens: &[Token<'a>], position: usize) -> Option<&'a str> {
+    tokens.get(position).map(|token| token.text)
+}
+
+fn expect(tokens: &[Token], position: &mut usize, expected: &'static str) -> Result<(), Failure> {
+    if peek(tokens, *position) != Some(expected) {
+        let expected = match expected {
+            "(" => "'('",
+            ")" => "')'",
+            _ => expected,
+        };
+        return Err((*position, ParseErrorKind::Syntax { expected }));
+    }
+    *position += 1;
+    Ok(())
+}
+
+/// Splits the text into parentheses, commas and the words or numbers between them.
+fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (index, row) in content.lines().enumerate() {
+        let mut start: Option<(usize, usize)> = None;
+        for (column, (offset, c)) in row.char_indices().chain([(row.len(), ' ')]).enumerate() {
+            let delimiter = c.is_whitespace() || matches!(c, '(' | ')' | ',');
+            if let (true, Some((begin, begin_column))) = (delimiter, start) {
+                tokens.push(Token {
+                    text: &row[begin..offset],
+                    line: index + 1,
+                    column: begin_column + 1,
+                });
+                start = None;
+            }
+            if matches!(c, '(' | ')' | ',') {
+                tokens.push(Token {
+                    text: &row[offset..offset + 1],
+                    line: index + 1,
+                    column: column + 1,
+                });
+            } else if !delimiter && start.is_none() {
+                start = Some((offset, column));
+            }
+        }
+    }
+    tokens
+}
+
+/// Writes each segment as `LINESTRING` on its own row.
+pub fn write_segments<T: Scalar, W: Write>(writer: &mut W, lines: &[Line2D<T>]) -> io::Result<()> {
+    for line in lines {
+        writeln!(writer, "{}", to_wkt(&positions(line)))?;
+    }
+    Ok(())
+}
+
+/// Writes the geometry of each intersection on its own row, the variant isn't written.
+pub fn write_intersections<T: Scalar, W: Write>(
+    writer: &mut W,
+    intersections: &[&Intersection<T>],
+) -> io::Result<()> {
+    for intersection in intersections {
+        write_intersection(writer, intersection)?;
+    }
+    Ok(())
+}
+
+pub fn write_intersection<T: Scalar, W: Write>(
+    writer: &mut W,
+    intersection: &Intersection<T>,
+) -> io::Result<()> {
+    writeln!(writer, "{}", to_wkt(&geometry(intersection)))
+}
+
+/// `POINT` of a single position, `LINESTRING` otherwise, with numeric coordinates.
+pub(super) fn to_wkt(positions: &[[String; 2]]) -> String {
+    let coordinates: Vec<String> = positions
+        .iter()
+        .map(|[x, y]| format!("{} {}", number(x), number(y)))
+        .collect();
+    match positions {
+        [_] => format!("POINT ({})", coordinates[0]),
+        _ => format!("LINESTRING ({})", coordinates.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordered_float::OrderedFloat;
+
+    fn parse_str<T: Scalar>(
+        content: &str,
+        options: &DatOptions,
+    ) -> Result<DatReport<T>, ReadError> {
+        parse(content, Path::new("test.wkt"), options)
+    }
+
+    #[test]
+    fn should_explode_linestrings() {
+        let content =
+            "LINESTRING (0 0, 1 1, 2 0)\nmultilinestring((0 1,1 2),(3 3, 4 4))\nLINESTRING EMPTY";
+
+        let report = parse_str::<OrderedFloat<f64>>(content, &DatOptions::default()).unwrap();
+
+        assert_eq!(
+            report.segments.lines,
+            vec![
+                Line2D::new(0.0, 0.0, 1.0, 1.0),
+                Line2D::new(1.0, 1.0, 2.0, 0.0),
+                Line2D::new(0.0, 1.0, 1.0, 2.0),
+                Line2D::new(3.0, 3.0, 4.0, 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_position_of_errors() {
+        let error = |content: &str| match parse_str::<i64>(content, &DatOptions::default()) {
+            Err(ReadError::Parse(error)) => error,
+            other => panic!("Expected parse error, got {:?}", other.map(|r| r.segments)),
+        };
+
+        let invalid = error("LINESTRING (0 0, 1 1)\nLINESTRING (0 0, 1.5 1)");
+        let unsupported = error("POINT (1 2)\nPOINT (3 4)");
+        let unclosed = error("LINESTRING (0 0, 1 1");
+
+        assert_eq!(
+            invalid.to_string(),
+            "test.wkt:2:18: invalid coordinate for i64: '1.5'"
+        );
+        assert_eq!(unsupported.kind, ParseErrorKind::UnsupportedGeometry);
+        assert_eq!(
+            unclosed.to_string(),
+            "test.wkt:1:21: expected ')', found end of input"
+        );
+    }
+
+    #[test]
+    fn lenient_mode_should_skip_bad_geometries() {
+        let content = "LINESTRING (0 0, x 1)\nPOINT (1 1)\nLINESTRING (0 0, 1 1)";
+
+        let report = parse_str::<OrderedFloat<f64>>(content, &DatOptions::lenient()).unwrap();
+
+        assert_eq!(report.segments.lines, vec![Line2D::new(0.0, 0.0, 1.0, 1.0)]);
+        assert_eq!(report.errors.len(), 2);
+    }
+}
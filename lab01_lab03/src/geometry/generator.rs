@@ -0,0 +1,545 @@
+//! Reproducible random line segments with injected degeneracies, for benchmarks and stress tests.
+//!
+//! Random segments start at a uniformly distributed point and point into a uniformly distributed
+//! direction, both chosen so that the segment lies within the bounds. They avoid the x-coordinates
+//! of the previous segments as far as possible, so that only the injected degeneracies are degenerate.
+//!
+//! All coordinates are multiples of the grid spacing. For powers of two (including 1) the arithmetic
+//! is exact, so the injected overlaps are exactly collinear and the multi-way crossings exactly
+//! meet in a single point, also for floating point coordinates.
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::svg::Window;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// Number of attempts to find a segment avoiding used x-coordinates before accepting a duplicate
+const ATTEMPTS: usize = 100;
+
+/// Distribution of the lengths of the segments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthDistribution {
+    Fixed(f64),
+    Uniform { min: f64, max: f64 },
+    Exponential { mean: f64 },
+}
+
+impl LengthDistribution {
+    fn sample(&self, rng: &mut ChaCha8Rng) -> f64 {
+        match *self {
+            LengthDistribution::Fixed(length) => length,
+            LengthDistribution::Uniform { min, max } if min < max => rng.gen_range(min..max),
+            LengthDistribution::Uniform { min, .. } => min,
+            LengthDistribution::Exponential { mean } => -mean * (1.0 - rng.gen::<f64>()).ln(),
+        }
+    }
+}
+
+impl FromStr for LengthDistribution {
+    type Err = String;
+
+    /// Parses `fixed:LENGTH`, `uniform:MIN,MAX` or `exponential:MEAN`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid length distribution '{}', expected fixed:LENGTH, uniform:MIN,MAX or exponential:MEAN",
+                s
+            )
+        };
+        let (kind, values) = s.split_once(':').ok_or_else(invalid)?;
+        let values: Vec<f64> = values
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        if values
+            .iter()
+            .any(|value| !value.is_finite() || *value < 0.0)
+        {
+            return Err(invalid());
+        }
+        match (kind, values.as_slice()) {
+            ("fixed", &[length]) => Ok(LengthDistribution::Fixed(length)),
+            ("uniform", &[min, max]) if min <= max => Ok(LengthDistribution::Uniform { min, max }),
+            ("exponential", &[mean]) => Ok(LengthDistribution::Exponential { mean }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Numbers of injected degenerate segments, they are part of the total number of segments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Degeneracies {
+    /// Vertical segments
+    pub vertical: usize,
+    /// Segments starting at an endpoint of a random segment
+    pub shared_endpoints: usize,
+    /// Pairs of collinear segments partially overlapping each other
+    pub overlaps: usize,
+    /// Segments starting at the x-coordinate of an endpoint of a random segment
+    pub shared_x: usize,
+    /// Groups of `crossing_degree` segments crossing in a single point
+    pub multi_crossings: usize,
+    pub crossing_degree: usize,
+}
+
+impl Default for Degeneracies {
+    fn default() -> Self {
+        Degeneracies {
+            vertical: 0,
+            shared_endpoints: 0,
+            overlaps: 0,
+            shared_x: 0,
+            multi_crossings: 0,
+            crossing_degree: 3,
+        }
+    }
+}
+
+impl Degeneracies {
+    /// Total number of degenerate segments.
+    pub fn segments(&self) -> usize {
+        self.vertical
+            + self.shared_endpoints
+            + 2 * self.overlaps
+            + self.shared_x
+            + self.multi_crossings * self.crossing_degree
+    }
+}
+
+impl FromStr for Degeneracies {
+    type Err = String;
+
+    /// Parses comma separated `name=count` pairs with the names `vertical`, `shared-endpoints`,
+    /// `overlaps`, `shared-x`, `crossings` and `degree`, e.g. `vertical=10,crossings=5,degree=4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut degeneracies = Degeneracies::default();
+        for pair in s.split(',') {
+            let (name, count) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid degeneracy '{}', expected name=count", pair))?;
+            let count = count
+                .trim()
+                .parse::<usize>()
+                .map_err(|error| format!("Invalid count of degeneracy '{}': {}", pair, error))?;
+            let field = match name.trim() {
+                "vertical" => &mut degeneracies.vertical,
+                "shared-endpoints" => &mut degeneracies.shared_endpoints,
+                "overlaps" => &mut degeneracies.overlaps,
+                "shared-x" => &mut degeneracies.shared_x,
+                "crossings" => &mut degeneracies.multi_crossings,
+                "degree" => &mut degeneracies.crossing_degree,
+                other => return Err(format!(
+                    "Unknown degeneracy '{}', expected one of vertical, shared-endpoints, overlaps, shared-x, crossings, degree",
+                    other
+                )),
+            };
+            *field = count;
+        }
+        if degeneracies.crossing_degree < 2 {
+            return Err(format!(
+                "Invalid degree {}, at least two segments have to cross",
+                degeneracies.crossing_degree
+            ));
+        }
+        Ok(degeneracies)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GeneratorOptions {
+    /// Total number of segments, including the degenerate ones
+    pub count: usize,
+    pub bounds: Window,
+    /// Segments per unit area: replaces the bounds by a square at their lower left corner
+    pub density: Option<f64>,
+    pub lengths: LengthDistribution,
+    /// Spacing of the coordinates
+    pub grid: f64,
+    pub seed: u64,
+    pub degeneracies: Degeneracies,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            count: 1000,
+            bounds: Window::new(0.0, 0.0, 100.0, 100.0),
+            density: None,
+            lengths: LengthDistribution::Uniform { min: 0.0, max: 1.0 },
+            grid: 1.0 / 1024.0,
+            seed: 0,
+            degeneracies: Degeneracies::default(),
+        }
+    }
+}
+
+impl GeneratorOptions {
+    /// The bounds, adjusted to the density if one is given. Without segments the bounds are kept,
+    /// as the square would be empty.
+    pub fn effective_bounds(&self) -> Window {
+        match self.density {
+            Some(density) if self.count > 0 => {
+                let side = (self.count as f64 / density).sqrt();
+                Window::new(
+                    self.bounds.x_min,
+                    self.bounds.y_min,
+                    self.bounds.x_min + side,
+                    self.bounds.y_min + side,
+                )
+            }
+            _ => self.bounds,
+        }
+    }
+}
+
+/// Generates the segments in random order, the same options always generate the same segments.
+pub fn generate(options: &GeneratorOptions) -> LineSegments2D {
+    let degeneracies = &options.degeneracies;
+    assert!(
+        degeneracies.segments() <= options.count,
+        "{} degenerate segments requested, but only {} segments in total",
+        degeneracies.segments(),
+        options.count
+    );
+    assert!(
+        options.grid.is_finite() && options.grid > 0.0,
+        "Grid spacing has to be positive: {}",
+        options.grid
+    );
+    if let Some(density) = options.density {
+        assert!(
+            density.is_finite() && density > 0.0,
+            "Density has to be positive: {}",
+            density
+        );
+    }
+
+    let mut generator = Generator {
+        rng: ChaCha8Rng::seed_from_u64(options.seed),
+        bounds: options.effective_bounds(),
+        lengths: options.lengths,
+        grid: options.grid,
+        used_x: HashSet::new(),
+    };
+
+    let mut lines: Vec<Line2D> = (0..options.count - degeneracies.segments())
+        .map(|_| generator.random_line())
+        .collect();
+    let random = lines.len();
+
+    for _ in 0..degeneracies.vertical {
+        lines.push(generator.vertical_line());
+    }
+    for _ in 0..degeneracies.shared_endpoints {
+        let start = generator.endpoint(&lines[..random]);
+        lines.push(generator.line_from(start));
+    }
+    for _ in 0..degeneracies.overlaps {
+        lines.extend(generator.overlap());
+    }
+    for _ in 0..degeneracies.shared_x {
+        let [x, _] = generator.endpoint(&lines[..random]);
+        let [_, y] = generator.point();
+        lines.push(generator.line_from([x, y]));
+    }
+    for _ in 0..degeneracies.multi_crossings {
+        lines.extend(generator.multi_crossing(degeneracies.crossing_degree));
+    }
+
+    lines.shuffle(&mut generator.rng);
+    LineSegments2D { lines }
+}
+
+/// Random segments with about 5% of each kind of degeneracy and groups of 4 segments crossing in a
+/// single point, on the default grid of the generator.
+#[cfg(test)]
+pub(crate) fn degenerate_lines(count: usize, seed: u64) -> Vec<Line2D> {
+    let share = count / 20;
+    generate(&GeneratorOptions {
+        count,
+        lengths: LengthDistribution::Exponential { mean: 4.0 },
+        degeneracies: Degeneracies {
+            vertical: share,
+            shared_endpoints: share,
+            overlaps: share / 2,
+            shared_x: share,
+            multi_crossings: share / 4,
+            crossing_degree: 4,
+        },
+        seed,
+        ..GeneratorOptions::default()
+    })
+    .lines
+}
+
+struct Generator {
+    rng: ChaCha8Rng,
+    bounds: Window,
+    lengths: LengthDistribution,
+    grid: f64,
+    /// Bit patterns of the x-coordinates of the generated segments
+    used_x: HashSet<u64>,
+}
+
+impl Generator {
+    fn snap(&self, value: f64) -> f64 {
+        (value / self.grid).round() * self.grid
+    }
+
+    fn point(&mut self) -> [f64; 2] {
+        let x = self.rng.gen_range(self.bounds.x_min..=self.bounds.x_max);
+        let y = self.rng.gen_range(self.bounds.y_min..=self.bounds.y_max);
+        [self.snap(x), self.snap(y)]
+    }
+
+    fn is_used(&self, x: f64) -> bool {
+        self.used_x.contains(&x.to_bits())
+    }
+
+    /// Creates the line and marks its x-coordinates as used.
+    fn line(&mut self, [x1, y1]: [f64; 2], [x2, y2]: [f64; 2]) -> Line2D {
+        self.used_x.insert(x1.to_bits());
+        self.used_x.insert(x2.to_bits());
+        Line2D::new(x1, y1, x2, y2)
+    }
+
+    /// Non-vertical offset of the sampled length in a random direction.
+    fn offset(&mut self, scale: f64) -> [f64; 2] {
+        for _ in 0..ATTEMPTS {
+            let length = self.lengths.sample(&mut self.rng) * scale;
+            let angle = self.rng.gen_range(0.0..2.0 * PI);
+            let offset = [
+                self.snap(length * angle.cos()),
+                self.snap(length * angle.sin()),
+            ];
+            if offset[0] != 0.0 {
+                return offset;
+            }
+        }
+        [self.grid, 0.0]
+    }
+
+    /// Random coordinate within the interval such that adding the extent stays within it.
+    fn start_within(&mut self, min: f64, max: f64, extent: f64) -> f64 {
+        let (low, high) = (min - extent.min(0.0), max - extent.max(0.0));
+        if low < high {
+            let value = self.rng.gen_range(low..=high);
+            // round towards the interval
+            (value / self.grid).ceil().min((high / self.grid).floor()) * self.grid
+        } else {
+            self.snap(min)
+        }
+    }
+
+    fn random_line(&mut self) -> Line2D {
+        let mut candidate = ([0.0; 2], [0.0; 2]);
+        for _ in 0..ATTEMPTS {
+            let [dx, dy] = self.offset(1.0);
+            let x = self.start_within(self.bounds.x_min, self.bounds.x_max, dx);
+            let y = self.start_within(self.bounds.y_min, self.bounds.y_max, dy);
+            candidate = ([x, y], [x + dx, y + dy]);
+            if !self.is_used(x) && !self.is_used(x + dx) {
+                break;
+            }
+        }
+        self.line(candidate.0, candidate.1)
+    }
+
+    fn vertical_line(&mut self) -> Line2D {
+        let mut x = self.point()[0];
+        for _ in 0..ATTEMPTS {
+            if !self.is_used(x) {
+                break;
+            }
+            x = self.point()[0];
+        }
+        let mut length = 0.0;
+        for _ in 0..ATTEMPTS {
+            let sample = self.lengths.sample(&mut self.rng);
+            length = self.snap(sample);
+            if length != 0.0 {
+                break;
+            }
+        }
+        let length = if length == 0.0 { self.grid } else { length };
+        let y = self.start_within(self.bounds.y_min, self.bounds.y_max, length);
+        self.line([x, y], [x, y + length])
+    }
+
+    /// Random endpoint of the lines, a random point if there are none.
+    fn endpoint(&mut self, lines: &[Line2D]) -> [f64; 2] {
+        match lines.choose(&mut self.rng) {
+            Some(line) => {
+                let point = if self.rng.gen() { line.start } else { line.end };
+                [point.x.0, point.y.0]
+            }
+            None => self.point(),
+        }
+    }
+
+    /// Line of the sampled length from the start, pointing into the bounds if possible.
+    fn line_from(&mut self, start: [f64; 2]) -> Line2D {
+        let [dx, dy] = self.offset(1.0);
+        let inside = |[x, y]: [f64; 2], bounds: &Window| {
+            (bounds.x_min..=bounds.x_max).contains(&x) && (bounds.y_min..=bounds.y_max).contains(&y)
+        };
+        let forward = [start[0] + dx, start[1] + dy];
+        let end = if inside(forward, &self.bounds) {
+            forward
+        } else {
+            [start[0] - dx, start[1] - dy]
+        };
+        self.line(start, end)
+    }
+
+    /// Segments from p to p + 2d and from p + d to p + 3d.
+    fn overlap(&mut self) -> [Line2D; 2] {
+        let [dx, dy] = self.offset(0.5);
+        let x = self.start_within(self.bounds.x_min, self.bounds.x_max, 3.0 * dx);
+        let y = self.start_within(self.bounds.y_min, self.bounds.y_max, 3.0 * dy);
+        let at = |k: f64| [x + k * dx, y + k * dy];
+        [self.line(at(0.0), at(2.0)), self.line(at(1.0), at(3.0))]
+    }
+
+    /// Segments from c - d to c + d crossing in their common center c.
+    fn multi_crossing(&mut self, degree: usize) -> Vec<Line2D> {
+        let offsets: Vec<[f64; 2]> = (0..degree).map(|_| self.offset(0.5)).collect();
+        let extent = |i: usize| {
+            offsets
+                .iter()
+                .map(|offset| offset[i].abs())
+                .fold(0.0, f64::max)
+        };
+        let (x_extent, y_extent) = (extent(0), extent(1));
+        let x = self.start_within(self.bounds.x_min + x_extent, self.bounds.x_max, x_extent);
+        let y = self.start_within(self.bounds.y_min + y_extent, self.bounds.y_max, y_extent);
+        offsets
+            .into_iter()
+            .map(|[dx, dy]| self.line([x - dx, y - dy], [x + dx, y + dy]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::intersection::Intersection;
+    use std::collections::HashMap;
+
+    #[test]
+    fn same_seed_should_generate_same_segments() {
+        let options = GeneratorOptions {
+            count: 200,
+            ..GeneratorOptions::default()
+        };
+
+        let first = generate(&options);
+        let second = generate(&options);
+        let other = generate(&GeneratorOptions { seed: 1, ..options });
+
+        assert_eq!(first.lines.len(), 200);
+        assert_eq!(first.lines, second.lines);
+        assert_ne!(first.lines, other.lines);
+        let bounds = options.bounds;
+        assert!(first.lines.iter().all(|line| {
+            [line.start, line.end].iter().all(|point| {
+                (bounds.x_min..=bounds.x_max).contains(&point.x.0)
+                    && (bounds.y_min..=bounds.y_max).contains(&point.y.0)
+            })
+        }));
+        assert!(first
+            .lines
+            .iter()
+            .all(|line| !line.is_vertical() && !line.is_zero_length()));
+
+        let empty = GeneratorOptions {
+            count: 0,
+            density: Some(1.0),
+            ..options
+        };
+        assert_eq!(empty.effective_bounds(), options.bounds);
+        assert!(generate(&empty).lines.is_empty());
+    }
+
+    #[test]
+    fn injected_degeneracies_should_be_found() {
+        let options = GeneratorOptions {
+            count: 60,
+            lengths: LengthDistribution::Fixed(4.0),
+            degeneracies: Degeneracies {
+                vertical: 3,
+                shared_endpoints: 2,
+                overlaps: 2,
+                shared_x: 2,
+                multi_crossings: 1,
+                crossing_degree: 4,
+            },
+            ..GeneratorOptions::default()
+        };
+
+        let lines = generate(&options).lines;
+        let intersections = BruteForceHandler::new(lines.clone()).run();
+
+        assert_eq!(lines.len(), 60);
+        assert_eq!(lines.iter().filter(|line| line.is_vertical()).count(), 3);
+        let overlaps = intersections
+            .iter()
+            .filter(|intersection| matches!(intersection, Intersection::PartialOverlap { .. }))
+            .count();
+        assert!(overlaps >= 2, "{} overlaps", overlaps);
+        let mut crossings_per_point = HashMap::new();
+        for intersection in &intersections {
+            if let Intersection::Crossing { point, .. } = intersection {
+                *crossings_per_point.entry(*point).or_insert(0) += 1;
+            }
+        }
+        // 4 segments through a point form 6 crossing pairs
+        assert!(crossings_per_point.values().any(|&count| count >= 6));
+        let mut x_coordinates = HashMap::new();
+        for line in &lines {
+            for x in [line.start.x, line.end.x] {
+                *x_coordinates.entry(x).or_insert(0) += 1;
+            }
+        }
+        assert!(x_coordinates.values().filter(|&&count| count > 1).count() >= 2 + 3);
+    }
+
+    #[test]
+    fn options_should_be_parsed() {
+        assert_eq!(
+            "uniform:0.5,2".parse::<LengthDistribution>(),
+            Ok(LengthDistribution::Uniform { min: 0.5, max: 2.0 })
+        );
+        assert_eq!(
+            "exponential:1".parse::<LengthDistribution>(),
+            Ok(LengthDistribution::Exponential { mean: 1.0 })
+        );
+        assert!("uniform:2,1".parse::<LengthDistribution>().is_err());
+        assert!("normal:1".parse::<LengthDistribution>().is_err());
+
+        assert_eq!(
+            "vertical=10,crossings=2,degree=5".parse::<Degeneracies>(),
+            Ok(Degeneracies {
+                vertical: 10,
+                multi_crossings: 2,
+                crossing_degree: 5,
+                ..Degeneracies::default()
+            })
+        );
+        assert_eq!(
+            "vertical=10,crossings=2,degree=5"
+                .parse::<Degeneracies>()
+                .unwrap()
+                .segments(),
+            20
+        );
+        assert!("vertical=x".parse::<Degeneracies>().is_err());
+        assert!("spiral=1".parse::<Degeneracies>().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Uniform grid (spatial hash) over the bounding boxes of the lines.
+//!
+//! Every line is added to all cells its bounding box overlaps, only lines sharing a cell are
+//! intersected exactly. A pair of lines is only tested in the cell containing the lower left corner of
+//! the intersection of their bounding boxes, so each pair is tested once. The cells are only used
+//! to find candidates, the tests are exact, so the result is the one of the brute force, including
+//! all degenerate cases.
+//!
+//! The cell size is chosen such that there are about as many cells as lines and a line of average
+//! extent overlaps few cells. Complexity: O(n + k + c) with c the number of candidate pairs, which
+//! is O(n) for evenly distributed short lines and O(n^2) in the worst case.
+use crate::geometry::algorithm;
+use crate::geometry::bounding_box::BoundingBox;
+use crate::geometry::counting::{self, IntersectionCounts};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::parallel::{self, Collector};
+use crate::geometry::scalar::Scalar;
+use ordered_float::OrderedFloat;
+use std::collections::{HashMap, HashSet};
+
+/// Column and row of a cell
+type Cell = (i64, i64);
+
+/// Number of cells a thread takes at once
+const BLOCK_CELLS: usize = 64;
+
+pub struct GridHandler<T: Scalar = OrderedFloat<f64>> {
+    lines: Vec<Line2D<T>>,
+    boxes: Vec<BoundingBox<T>>,
+    /// lower left corner of the cell (0, 0)
+    origin: (f64, f64),
+    cell_size: f64,
+    intersections: HashSet<Intersection<T>>,
+    /// number of red lines at the start of the lines in the bichromatic mode
+    red_count: Option<usize>,
+    threads: usize,
+}
+
+impl<T: Scalar> GridHandler<T> {
+    pub fn new(lines: Vec<Line2D<T>>) -> Self {
+        let boxes: Vec<BoundingBox<T>> = lines.iter().map(BoundingBox::of).collect();
+        let (origin, cell_size) = cell_size(&boxes);
+        Self {
+            lines,
+            boxes,
+            origin,
+            cell_size,
+            intersections: HashSet::new(),
+            red_count: None,
+            threads: 1,
+        }
+    }
+
+    /// Creates a handler which only reports intersections of red with blue lines.
+    pub fn new_bichromatic(red: Vec<Line2D<T>>, blue: Vec<Line2D<T>>) -> Self {
+        let red_count = red.len();
+        let mut lines = red;
+        lines.extend(blue);
+        Self {
+            red_count: Some(red_count),
+            ..Self::new(lines)
+        }
+    }
+
+    /// Intersects the lines of the cells on the given number of threads, see [parallel].
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn run(&mut self) -> HashSet<Intersection<T>> {
+        let found = self.collect::<HashSet<_>>();
+        self.intersections.extend(found);
+
+        self.intersections.clone()
+    }
+
+    /// Counts the intersections [run](Self::run) would return without storing them.
+    pub fn count(&self) -> IntersectionCounts {
+        counting::count_distinct(&self.lines, self.red_count, |lines, red_count| {
+            Self {
+                red_count,
+                ..Self::new(lines)
+            }
+            .with_threads(self.threads)
+            .collect()
+        })
+    }
+
+    fn collect<C: Collector<T>>(&self) -> C {
+        let mut cells: HashMap<Cell, Vec<usize>> = HashMap::new();
+        for (i, bounds) in self.boxes.iter().enumerate() {
+            let (column_min, row_min) = self.cell(&bounds.x_min, &bounds.y_min);
+            let (column_max, row_max) = self.cell(&bounds.x_max, &bounds.y_max);
+            for column in column_min..=column_max {
+                for row in row_min..=row_max {
+                    cells.entry((column, row)).or_default().push(i);
+                }
+            }
+        }
+
+        let cells: Vec<(Cell, Vec<usize>)> = cells.into_iter().collect();
+        parallel::collect_blocks(cells.len(), BLOCK_CELLS, self.threads, |block, found| {
+            for (cell, indices) in &cells[block] {
+                self.intersect_cell(*cell, indices, found);
+            }
+        })
+    }
+
+    /// Intersects the pairs of lines in the cell whose reference point lies in the cell.
+    fn intersect_cell(&self, cell: Cell, indices: &[usize], intersections: &mut impl Collector<T>) {
+        // the indices are ascending, as the lines were added in order
+        for (a, &i) in indices.iter().enumerate() {
+            for &j in &indices[a + 1..] {
+                if !algorithm::is_tested_pair(self.red_count, i, j)
+                    || !self.boxes[i].intersects(&self.boxes[j])
+                {
+                    continue;
+                }
+                let (x, y) = self.boxes[i].reference_point(&self.boxes[j]);
+                if self.cell(&x, &y) != cell {
+                    continue;
+                }
+                let line1 = &self.lines[i];
+                let line2 = &self.lines[j];
+                if line1.intersects(line2.clone()) {
+                    let intersection = line1
+                        .find_intersection(line2.clone())
+                        .expect("Intersection not found!");
+
+                    intersections.add(intersection);
+                }
+            }
+        }
+    }
+
+    /// Cell containing the point. Rounding the coordinates to floats is monotonic, so a point within
+    /// a bounding box always lies in one of the cells the box was added to.
+    fn cell(&self, x: &T, y: &T) -> Cell {
+        (
+            ((x.to_f64() - self.origin.0) / self.cell_size).floor() as i64,
+            ((y.to_f64() - self.origin.1) / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// Origin and size of the cells: at least the average extent of the boxes and large enough that
+/// the whole area and each of its sides have at most as many cells as boxes.
+pub(crate) fn cell_size<T: Scalar>(boxes: &[BoundingBox<T>]) -> ((f64, f64), f64) {
+    if boxes.is_empty() {
+        return ((0.0, 0.0), 1.0);
+    }
+    let n = boxes.len() as f64;
+    let x_min = boxes
+        .iter()
+        .map(|b| b.x_min.to_f64())
+        .fold(f64::MAX, f64::min);
+    let y_min = boxes
+        .iter()
+        .map(|b| b.y_min.to_f64())
+        .fold(f64::MAX, f64::min);
+    let x_max = boxes
+        .iter()
+        .map(|b| b.x_max.to_f64())
+        .fold(f64::MIN, f64::max);
+    let y_max = boxes
+        .iter()
+        .map(|b| b.y_max.to_f64())
+        .fold(f64::MIN, f64::max);
+    let (width, height) = (x_max - x_min, y_max - y_min);
+    let extent = boxes
+        .iter()
+        .map(|b| (b.x_max.to_f64() - b.x_min.to_f64()).max(b.y_max.to_f64() - b.y_min.to_f64()))
+        .sum::<f64>()
+        / n;
+
+    let size = extent
+        .max((width * height / n).sqrt())
+        .max(width.max(height) / n);
+    let size = if size.is_finite() && size > 0.0 {
+        size
+    } else {
+        1.0
+    };
+    ((x_min, y_min), size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_size_should_adapt_to_the_lines() {
+        let short: Vec<Line2D> = (0..100)
+            .map(|i| Line2D::new(i as f64, 0.0, i as f64 + 0.5, 0.5))
+            .collect();
+        let mut with_long = short.clone();
+        with_long.push(Line2D::new(0.0, 0.0, 10000.0, 10000.0));
+
+        assert!(GridHandler::new(short).cell_size() < 1.0);
+        // a single long line mustn't create more cells than lines
+        assert!(GridHandler::new(with_long).cell_size() >= 100.0);
+        assert_eq!(GridHandler::<i64>::new(Vec::new()).run().len(), 0);
+    }
+}
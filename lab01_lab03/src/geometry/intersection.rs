@@ -1,42 +1,95 @@
 use crate::geometry::line::Line2D;
 use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 
+/// Intersection of two line segments.
+/// The points are expressed in the [Scalar::Field] of the scalar, as crossings generally don't lie
+/// on the grid of the input coordinates.
 #[derive(Copy, Clone, Debug)]
-pub enum Intersection {
+pub enum Intersection<T: Scalar = OrderedFloat<f64>> {
     Crossing {
-        line1: Line2D,
-        line2: Line2D,
-        point: Point2D,
+        line1: Line2D<T>,
+        line2: Line2D<T>,
+        point: Point2D<T::Field>,
     },
     Touching {
-        line1: Line2D,
-        line2: Line2D,
-        point: Point2D,
+        line1: Line2D<T>,
+        line2: Line2D<T>,
+        point: Point2D<T::Field>,
     },
     PartialOverlap {
-        line1: Line2D,
-        line2: Line2D,
-        overlap: Line2D,
+        line1: Line2D<T>,
+        line2: Line2D<T>,
+        overlap: Line2D<T>,
     },
     ContainedOverlap {
-        line1: Line2D,
-        line2: Line2D,
-        overlap: Line2D,
+        line1: Line2D<T>,
+        line2: Line2D<T>,
+        overlap: Line2D<T>,
     },
     IdenticalOverlap {
-        line1: Line2D,
-        line2: Line2D,
-        overlap: Line2D,
+        line1: Line2D<T>,
+        line2: Line2D<T>,
+        overlap: Line2D<T>,
     },
 }
 
-fn lines_equal_unordered(line1: &Line2D, line2: &Line2D, other1: &Line2D, other2: &Line2D) -> bool {
+impl<T: Scalar> Intersection<T> {
+    /// Name of the variant, e.g. `Crossing`.
+    pub fn variant(&self) -> &'static str {
+        match self {
+            Intersection::Crossing { .. } => "Crossing",
+            Intersection::Touching { .. } => "Touching",
+            Intersection::PartialOverlap { .. } => "PartialOverlap",
+            Intersection::ContainedOverlap { .. } => "ContainedOverlap",
+            Intersection::IdenticalOverlap { .. } => "IdenticalOverlap",
+        }
+    }
+
+    /// The two intersecting lines.
+    pub fn lines(&self) -> (&Line2D<T>, &Line2D<T>) {
+        match self {
+            Intersection::Crossing { line1, line2, .. }
+            | Intersection::Touching { line1, line2, .. }
+            | Intersection::PartialOverlap { line1, line2, .. }
+            | Intersection::ContainedOverlap { line1, line2, .. }
+            | Intersection::IdenticalOverlap { line1, line2, .. } => (line1, line2),
+        }
+    }
+
+    /// Point of a crossing or touching, overlap of the other variants.
+    fn geometry(&self) -> (Option<&Point2D<T::Field>>, Option<&Line2D<T>>) {
+        match self {
+            Intersection::Crossing { point, .. } | Intersection::Touching { point, .. } => {
+                (Some(point), None)
+            }
+            Intersection::PartialOverlap { overlap, .. }
+            | Intersection::ContainedOverlap { overlap, .. }
+            | Intersection::IdenticalOverlap { overlap, .. } => (None, Some(overlap)),
+        }
+    }
+
+    /// The two intersecting lines, the smaller one first.
+    fn sorted_lines(&self) -> (&Line2D<T>, &Line2D<T>) {
+        let (line1, line2) = self.lines();
+        (std::cmp::min(line1, line2), std::cmp::max(line1, line2))
+    }
+}
+
+fn lines_equal_unordered<T: PartialEq>(
+    line1: &Line2D<T>,
+    line2: &Line2D<T>,
+    other1: &Line2D<T>,
+    other2: &Line2D<T>,
+) -> bool {
     (line1 == other1 && line2 == other2) || (line1 == other2 && line2 == other1)
 }
 
-impl PartialEq for Intersection {
+impl<T: Scalar> PartialEq for Intersection<T> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (
@@ -107,9 +160,26 @@ impl PartialEq for Intersection {
     }
 }
 
-impl Eq for Intersection {}
+impl<T: Scalar> Eq for Intersection<T> {}
+
+impl<T: Scalar> PartialOrd for Intersection<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Scalar> Ord for Intersection<T> {
+    /// Orders by the variant, the point or overlap and then the lines regardless of their order,
+    /// consistent with the equality.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.variant()
+            .cmp(other.variant())
+            .then_with(|| self.geometry().cmp(&other.geometry()))
+            .then_with(|| self.sorted_lines().cmp(&other.sorted_lines()))
+    }
+}
 
-impl Hash for Intersection {
+impl<T: Scalar> Hash for Intersection<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         let (line1, line2) = match self {
             Intersection::Crossing {
@@ -157,7 +227,7 @@ impl Hash for Intersection {
     }
 }
 
-impl Display for Intersection {
+impl<T: Scalar> Display for Intersection<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Intersection::Crossing {
@@ -0,0 +1,59 @@
+//! Layers of the bichromatic (red-blue) intersection, where only intersections between lines of
+//! different layers are reported, e.g. roads against rivers.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Red,
+    Blue,
+}
+
+/// Layers of each line. A line occurring in both inputs belongs to both layers.
+#[derive(Clone, Debug)]
+pub struct Layers<T> {
+    /// whether the line is red and whether it is blue
+    layers: HashMap<Line2D<T>, (bool, bool)>,
+}
+
+impl<T: Scalar> Layers<T> {
+    pub fn new(red: &[Line2D<T>], blue: &[Line2D<T>]) -> Self {
+        let mut layers: HashMap<Line2D<T>, (bool, bool)> = HashMap::new();
+        for line in red {
+            layers.entry(line.clone()).or_default().0 = true;
+        }
+        for line in blue {
+            layers.entry(line.clone()).or_default().1 = true;
+        }
+        Layers { layers }
+    }
+
+    pub fn contains(&self, line: &Line2D<T>, layer: Layer) -> bool {
+        match (self.layers.get(line), layer) {
+            (Some((red, _)), Layer::Red) => *red,
+            (Some((_, blue)), Layer::Blue) => *blue,
+            (None, _) => false,
+        }
+    }
+
+    /// Checks whether one of the lines is red and the other one blue.
+    pub fn is_bichromatic(&self, line1: &Line2D<T>, line2: &Line2D<T>) -> bool {
+        (self.contains(line1, Layer::Red) && self.contains(line2, Layer::Blue))
+            || (self.contains(line1, Layer::Blue) && self.contains(line2, Layer::Red))
+    }
+
+    /// Checks whether the intersection is between lines of different layers.
+    pub fn reports(&self, intersection: &Intersection<T>) -> bool {
+        match intersection {
+            Intersection::Crossing { line1, line2, .. }
+            | Intersection::Touching { line1, line2, .. }
+            | Intersection::PartialOverlap { line1, line2, .. }
+            | Intersection::ContainedOverlap { line1, line2, .. }
+            | Intersection::IdenticalOverlap { line1, line2, .. } => {
+                self.is_bichromatic(line1, line2)
+            }
+        }
+    }
+}
@@ -1,13 +1,15 @@
 use crate::geometry::intersection::Intersection;
 use crate::geometry::point::Point2D;
+use crate::geometry::predicates::{self, SegmentRelation};
+use crate::geometry::scalar::Scalar;
 use ordered_float::OrderedFloat;
-use std::cmp::{Ordering, PartialEq};
+use std::cmp::Ordering;
 use std::fmt::Display;
 
-#[derive(Copy, Clone, Debug, Hash)]
-pub struct Line2D {
-    pub start: Point2D,
-    pub end: Point2D,
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Line2D<T = OrderedFloat<f64>> {
+    pub start: Point2D<T>,
+    pub end: Point2D<T>,
 }
 
 impl Line2D {
@@ -23,10 +25,12 @@ impl Line2D {
             },
         }
     }
+}
 
+impl<T: Scalar> Line2D<T> {
     pub fn get_length(&self) -> f64 {
-        let dx = self.end.x - self.start.x;
-        let dy = self.end.y - self.start.y;
+        let dx = self.end.x.to_f64() - self.start.x.to_f64();
+        let dy = self.end.y.to_f64() - self.start.y.to_f64();
         (dx * dx + dy * dy).sqrt()
     }
 
@@ -38,257 +42,132 @@ impl Line2D {
         self.start.x == self.end.x
     }
 
-    pub fn contains(&self, point: Point2D) -> bool {
-        let dx = self.end.x - self.start.x;
-        let dy = self.end.y - self.start.y;
-
-        // Handle the special case of a zero-length line (both dx and dy are zero)
-        if dx.abs() <= f64::EPSILON && dy.abs() <= f64::EPSILON {
-            return point.x == self.start.x && point.y == self.start.y;
-        }
-
-        // Check if the point lies on the line defined by the segment
-        let is_on_line = dx == OrderedFloat(0.0)
-            || (point.x - self.start.x) * dy == (point.y - self.start.y) * dx;
-
-        if !is_on_line {
-            return false;
-        }
-
-        // Check if the point is within the segment bounds
-        let within_x_bounds = if dx.abs() > f64::EPSILON {
-            (self.start.x <= point.x && point.x <= self.end.x)
-                || (self.end.x <= point.x && point.x <= self.start.x)
-        } else {
-            true
-        };
-
-        let within_y_bounds = if dy.abs() > f64::EPSILON {
-            (self.start.y <= point.y && point.y <= self.end.y)
-                || (self.end.y <= point.y && point.y <= self.start.y)
+    /// Returns the endpoints ordered by x and then y, i.e. in the order the sweep line passes them.
+    pub fn sorted_endpoints(&self) -> (&Point2D<T>, &Point2D<T>) {
+        if self.start <= self.end {
+            (&self.start, &self.end)
         } else {
-            true
-        };
+            (&self.end, &self.start)
+        }
+    }
 
-        within_x_bounds && within_y_bounds
+    /// Checks exactly whether the point lies on the line segment.
+    pub fn contains(&self, point: Point2D<T>) -> bool {
+        predicates::on_segment(&self.start, &self.end, &point)
     }
 
     /// Finds the intersection point between two line segments.
     /// If overlapping, it will return one of the overlapping endpoints.
-    pub fn find_intersection(&self, other: Line2D) -> Option<Intersection> {
-        let (x1, y1) = (self.start.x.0, self.start.y.0);
-        let (x2, y2) = (self.end.x.0, self.end.y.0);
-        let (x3, y3) = (other.start.x.0, other.start.y.0);
-        let (x4, y4) = (other.end.x.0, other.end.y.0);
-
-        let denominator = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
-
-        // collinear
-        if denominator.abs() < f64::EPSILON {
-            // Check if lines are identical
-            if *self == other {
-                return Some(Intersection::IdenticalOverlap {
-                    line1: *self,
-                    line2: other,
-                    overlap: *self,
-                });
-            }
-
-            // Check if one line is completely inside the other one
-            if self.contains(other.start) && self.contains(other.end) {
-                return Some(Intersection::ContainedOverlap {
-                    line1: *self,
-                    line2: other,
-                    overlap: other,
-                });
-            }
-            if other.contains(self.start) && other.contains(self.end) {
-                return Some(Intersection::ContainedOverlap {
-                    line1: *self,
-                    line2: other,
-                    overlap: *self,
-                });
-            }
-
-            // Check for partial overlap
-            if self.contains(other.start)
-                || self.contains(other.end)
-                || other.contains(self.start)
-                || other.contains(self.end)
-            {
-                let overlap_start = if self.contains(other.start) {
-                    other.start
-                } else {
-                    self.start
-                };
-                let overlap_end = if self.contains(other.end) {
-                    other.end
-                } else {
-                    self.end
-                };
-                let overlap = Line2D {
-                    start: overlap_start,
-                    end: overlap_end,
-                };
-                return Some(Intersection::PartialOverlap {
-                    line1: *self,
-                    line2: other,
-                    overlap,
-                });
-            }
-            return None;
+    /// The type of the intersection is determined exactly, only the point of a crossing is rounded
+    /// if the scalar isn't exact. The rounding doesn't depend on the order of the lines or of their
+    /// endpoints, so a crossing always has the same point.
+    pub fn find_intersection(&self, other: Line2D<T>) -> Option<Intersection<T>> {
+        match predicates::segment_relation(&self.start, &self.end, &other.start, &other.end) {
+            SegmentRelation::Disjoint => None,
+            SegmentRelation::Crossing => Some(Intersection::Crossing {
+                point: self.crossing_point(&other),
+                line1: self.clone(),
+                line2: other,
+            }),
+            SegmentRelation::Touching(point) => Some(Intersection::Touching {
+                line1: self.clone(),
+                line2: other,
+                point: point.to_field(),
+            }),
+            SegmentRelation::Collinear => Some(self.find_overlap(other)),
         }
+    }
 
-        let ua_numerator = (x4 - x3) * (y1 - y3) - (y4 - y3) * (x1 - x3);
-        let ub_numerator = (x2 - x1) * (y1 - y3) - (y2 - y1) * (x1 - x3);
-
-        let ua = ua_numerator / denominator;
-        let ub = ub_numerator / denominator;
-
-        let tolerance = f64::EPSILON;
-
-        // Check if the intersection point is within both line segments or at their endpoints
-        if (ua >= -tolerance && ua <= 1.0 + tolerance)
-            && (ub >= -tolerance && ub <= 1.0 + tolerance)
-        {
-            let intersection_x = x1 + ua * (x2 - x1);
-            let intersection_y = y1 + ua * (y2 - y1);
-            let point = Point2D {
-                x: OrderedFloat(intersection_x),
-                y: OrderedFloat(intersection_y),
-            };
-
-            let is_touching = (ua.abs() < tolerance || (ua - 1.0).abs() < tolerance)
-                || (ub.abs() < tolerance || (ub - 1.0).abs() < tolerance);
-
-            if is_touching {
-                Some(Intersection::Touching {
-                    line1: *self,
-                    line2: other,
-                    point,
-                })
-            } else {
-                Some(Intersection::Crossing {
-                    line1: *self,
-                    line2: other,
-                    point,
-                })
+    pub fn intersects(&self, other: Line2D<T>) -> bool {
+        predicates::segments_intersect(&self.start, &self.end, &other.start, &other.end)
+    }
+
+    /// Crossing point computed from both lines with their endpoints sorted, the smaller line first.
+    fn crossing_point(&self, other: &Line2D<T>) -> Point2D<T::Field> {
+        let sorted = |line: &Line2D<T>| {
+            let (start, end) = line.sorted_endpoints();
+            Line2D {
+                start: start.clone(),
+                end: end.clone(),
             }
-        } else {
-            None
+        };
+        let (line1, line2) = (sorted(self), sorted(other));
+        match line1 <= line2 {
+            true => T::crossing_point(&line1, &line2),
+            false => T::crossing_point(&line2, &line1),
         }
     }
 
-    pub fn intersects(&self, other: Line2D) -> bool {
-        // if self.is_zero_length() && other.is_zero_length() {
-        //     let res = self.start.approx_eq(&other.start, 1e-6);
-        //     return res;
-        // }
-
-        let ccw1 = Line2D::ccw(self.start, self.end, other.start);
-        let ccw2 = Line2D::ccw(self.start, self.end, other.end);
-        let ccw3 = Line2D::ccw(other.start, other.end, self.start);
-        let ccw4 = Line2D::ccw(other.start, other.end, self.end);
-
-        if ccw1 == 0.0 && ccw2 == 0.0 {
-            // They are colinear -> check whether they overlap
-            // script says parametric_overlap, but shouldn't bbox overlap be enough?
-            self.bbox_overlap(&other)
-        } else {
-            // If line segments straddle each other, they intersect
-            ccw1 * ccw2 <= OrderedFloat::from(0.0) && ccw3 * ccw4 <= OrderedFloat::from(0.0)
+    /// Determines the overlap of two collinear line segments, which have at least one point in common.
+    /// The overlap runs from the larger to the smaller of the sorted endpoints of both lines, so it
+    /// doesn't depend on the direction of the lines. Lines with the same endpoints are identical.
+    fn find_overlap(&self, other: Line2D<T>) -> Intersection<T> {
+        let (start1, end1) = self.sorted_endpoints();
+        let (start2, end2) = other.sorted_endpoints();
+        let overlap = Line2D {
+            start: std::cmp::max(start1, start2).clone(),
+            end: std::cmp::min(end1, end2).clone(),
+        };
+        let covers_self = overlap.start == *start1 && overlap.end == *end1;
+        let covers_other = overlap.start == *start2 && overlap.end == *end2;
+        let (line1, line2) = (self.clone(), other);
+
+        match (covers_self, covers_other) {
+            (true, true) => Intersection::IdenticalOverlap {
+                line1,
+                line2,
+                overlap,
+            },
+            (true, false) | (false, true) => Intersection::ContainedOverlap {
+                line1,
+                line2,
+                overlap,
+            },
+            (false, false) => Intersection::PartialOverlap {
+                line1,
+                line2,
+                overlap,
+            },
         }
     }
 
+    /// Approximates the y-coordinate of the line at x with floating point arithmetic.
     pub fn y_at(&self, x: f64) -> Option<f64> {
-        if self.start.x == self.end.x {
+        let (x1, y1) = (self.start.x.to_f64(), self.start.y.to_f64());
+        let (x2, y2) = (self.end.x.to_f64(), self.end.y.to_f64());
+        if x1 == x2 {
             // Vertical line, return y1 if x matches
-            if x == *self.start.x {
-                Some(*self.start.y)
+            if x == x1 {
+                Some(y1)
             } else {
                 None
             }
         } else {
-            let t = (x - *self.start.x) / (*self.end.x - *self.start.x);
-            if t >= 0.0 && t <= 1.0 {
-                Some(*self.start.y + t * (*self.end.y - *self.start.y))
+            let t = (x - x1) / (x2 - x1);
+            if (0.0..=1.0).contains(&t) {
+                Some(y1 + t * (y2 - y1))
             } else {
                 None
             }
         }
     }
 
-    /// Calculates whether two colinear lines overlap.
-    fn parametric_overlap(&self, other: &Line2D) -> bool {
-        if self.is_zero_length() {
-            return other.contains(self.start);
-        } else if other.is_zero_length() {
-            return self.contains(other.start);
-        }
-
-        // calculate direction. Take care if one line is a point
-        let direction = Point2D {
-            x: self.end.x - self.start.x,
-            y: self.end.y - self.start.y,
-        };
-
-        let to_param = |p: &Point2D| {
-            if direction.x.abs() > f64::EPSILON {
-                (p.x - self.start.x) / direction.x
-            } else {
-                (p.y - self.start.y) / direction.y
-            }
-        };
-
-        let lambda1 = to_param(&other.start);
-        let lambda2 = to_param(&other.end);
-
-        lambda1.min(lambda2) <= OrderedFloat::from(1.0)
-            && lambda1.max(lambda2) >= OrderedFloat::from(0.0)
-    }
-
-    fn bbox_overlap(&self, other: &Line2D) -> bool {
-        // Determine the bounding boxes of both line segments
-        let min_x1 = self.start.x.min(self.end.x);
-        let max_x1 = self.start.x.max(self.end.x);
-        let min_y1 = self.start.y.min(self.end.y);
-        let max_y1 = self.start.y.max(self.end.y);
-
-        let min_x2 = other.start.x.min(other.end.x);
-        let max_x2 = other.start.x.max(other.end.x);
-        let min_y2 = other.start.y.min(other.end.y);
-        let max_y2 = other.start.y.max(other.end.y);
-
-        // Check for overlap in both x and y dimensions
-        max_x1 >= min_x2 && min_x1 <= max_x2 && max_y1 >= min_y2 && min_y1 <= max_y2
-    }
-
-    /// Calculates whether a triangle is clockwise or counterclockwise oriented.
-    /// That means whether r is on the left or on the right of a line between p and q.
-    ///
-    /// <0 if clockwise
-    /// =0 if collinear
-    /// >0 if counterclockwise
-    fn ccw(p: Point2D, q: Point2D, r: Point2D) -> OrderedFloat<f64> {
-        (p.x * q.y - p.y * q.x) + (q.x * r.y - q.y * r.x) + (p.y * r.x - p.x * r.y)
-    }
-}
-
-impl PartialEq for Line2D {
-    fn eq(&self, other: &Self) -> bool {
-        self.start == other.start && self.end == other.end
+    /// Converts the line to another scalar, returns None if it can't be represented exactly.
+    pub fn convert<U: Scalar>(&self) -> Option<Line2D<U>> {
+        Some(Line2D {
+            start: self.start.convert()?,
+            end: self.end.convert()?,
+        })
     }
 }
 
-impl Eq for Line2D {}
-
-impl PartialOrd for Line2D {
+impl<T: Ord> PartialOrd for Line2D<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Line2D {
+impl<T: Ord> Ord for Line2D<T> {
     fn cmp(&self, other: &Self) -> Ordering {
         self.start
             .y
@@ -299,15 +178,12 @@ impl Ord for Line2D {
     }
 }
 
-impl Display for Line2D {
+impl<T: Display> Display for Line2D<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}",
-            format!(
-                "({}, {}) -> ({}, {})",
-                self.start.x.0, self.start.y.0, self.end.x.0, self.end.y.0
-            )
+            "({}, {}) -> ({}, {})",
+            self.start.x, self.start.y, self.end.x, self.end.y
         )
     }
 }
@@ -799,6 +675,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn find_intersection_should_not_depend_on_the_direction_of_overlapping_lines() {
+        let reversed = |line: Line2D| Line2D {
+            start: line.end,
+            end: line.start,
+        };
+        let cases = [
+            (
+                Line2D::new(0.0, 0.0, 2.0, 0.0),
+                Line2D::new(1.0, 0.0, 3.0, 0.0),
+                "PartialOverlap",
+                Line2D::new(1.0, 0.0, 2.0, 0.0),
+            ),
+            (
+                Line2D::new(0.0, 0.0, 5.0, 5.0),
+                Line2D::new(1.0, 1.0, 2.0, 2.0),
+                "ContainedOverlap",
+                Line2D::new(1.0, 1.0, 2.0, 2.0),
+            ),
+            (
+                Line2D::new(0.0, 0.0, 0.0, 4.0),
+                Line2D::new(0.0, 0.0, 0.0, 4.0),
+                "IdenticalOverlap",
+                Line2D::new(0.0, 0.0, 0.0, 4.0),
+            ),
+        ];
+
+        for (line1, line2, variant, expected) in cases {
+            for line1 in [line1, reversed(line1)] {
+                for line2 in [line2, reversed(line2)] {
+                    for (a, b) in [(line1, line2), (line2, line1)] {
+                        let actual = match a.find_intersection(b) {
+                            Some(Intersection::PartialOverlap { overlap, .. }) => {
+                                ("PartialOverlap", overlap)
+                            }
+                            Some(Intersection::ContainedOverlap { overlap, .. }) => {
+                                ("ContainedOverlap", overlap)
+                            }
+                            Some(Intersection::IdenticalOverlap { overlap, .. }) => {
+                                ("IdenticalOverlap", overlap)
+                            }
+                            other => {
+                                panic!("Expected an overlap of {} and {}, got {:?}", a, b, other)
+                            }
+                        };
+
+                        assert_eq!(actual, (variant, expected), "{} and {}", a, b);
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn find_intersection_when_parallel_lines() {
         let line1 = Line2D::new(1.0, 1.0, 3.0, 3.0);
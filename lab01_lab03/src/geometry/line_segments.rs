@@ -1,51 +1,52 @@
 use ordered_float::OrderedFloat;
 use std::{
     fs,
-    io::{self, BufRead},
+    io::{self, BufWriter, Write},
     path::Path,
 };
 
+use super::dat::{self, DatOptions, DatReport, ReadError};
+use super::formats;
 use super::line::Line2D;
+use super::scalar::Scalar;
 
 #[derive(Clone, Debug)]
-pub struct LineSegments2D {
-    pub lines: Vec<Line2D>,
+pub struct LineSegments2D<T = OrderedFloat<f64>> {
+    pub lines: Vec<Line2D<T>>,
 }
 
-impl LineSegments2D {
-    pub fn from_dat(path: &Path) -> io::Result<Self> {
-        let file = fs::File::open(&path)?;
-        let reader = io::BufReader::new(file);
+impl<T: Scalar> LineSegments2D<T> {
+    /// Reads the lines of a file with one line per row: `x1 y1 x2 y2`, see [dat] for the format.
+    /// Fails on the first malformed row or if a coordinate can't be represented exactly by the scalar.
+    pub fn from_dat(path: &Path) -> Result<Self, ReadError> {
+        Ok(dat::read(path, &DatOptions::default())?.segments)
+    }
 
-        let lines: io::Result<Vec<Line2D>> = reader
-            .lines()
-            .map(|line| {
-                line.and_then(|v| {
-                    let nums: Vec<f64> = v
-                        .split_whitespace()
-                        .map(|num| num.parse::<f64>().unwrap())
-                        .collect();
-                    if nums.len() == 4 {
-                        let x1 = nums[0];
-                        let y1 = nums[1];
-                        let x2 = nums[2];
-                        let y2 = nums[3];
+    /// Reads the lines of a file with the given options.
+    pub fn read_dat(path: &Path, options: &DatOptions) -> Result<DatReport<T>, ReadError> {
+        dat::read(path, options)
+    }
 
-                        if OrderedFloat(x1) < OrderedFloat(x2) {
-                            Ok(Line2D::new(x1, y1, x2, y2))
-                        } else {
-                            Ok(Line2D::new(x2, y2, x1, y1))
-                        }
-                    } else {
-                        Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "Invalid number of points in line segment",
-                        ))
-                    }
-                })
-            })
-            .collect();
+    /// Reads the lines of a file in the format of its extension, see [formats].
+    pub fn read(path: &Path, options: &DatOptions) -> Result<DatReport<T>, ReadError> {
+        formats::read(path, options)
+    }
+
+    /// Writes the lines in the format of the file extension, see [formats].
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        formats::write_segments(path, self)
+    }
 
-        Ok(LineSegments2D { lines: lines? })
+    /// Writes the lines in the format read by [LineSegments2D::from_dat].
+    pub fn to_dat(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        for line in &self.lines {
+            writeln!(
+                writer,
+                "{} {} {} {}",
+                line.start.x, line.start.y, line.end.x, line.end.y
+            )?;
+        }
+        writer.flush()
     }
 }
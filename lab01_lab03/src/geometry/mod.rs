@@ -1,8 +1,28 @@
-#![allow(dead_code)]
-pub mod brute_force;
-pub mod intersection;
-pub mod line;
-pub mod line_segments;
-pub mod point;
-pub mod sweep_line;
+pub(crate) mod algorithm;
+pub mod analysis;
+pub mod arrangement;
+pub mod benchmark;
+pub(crate) mod bounding_box;
+pub(crate) mod brute_force;
+pub mod counting;
+pub mod dat;
+pub(crate) mod dynamic;
 pub(crate) mod external;
+pub mod formats;
+pub mod generator;
+pub(crate) mod grid;
+pub(crate) mod intersection;
+pub(crate) mod layer;
+pub(crate) mod line;
+pub(crate) mod line_segments;
+pub mod parallel;
+pub(crate) mod point;
+pub(crate) mod predicates;
+pub mod repair;
+pub(crate) mod rtree;
+pub(crate) mod scalar;
+pub mod snap_rounding;
+pub mod stream;
+pub mod svg;
+pub mod sweep_line;
+pub mod verify;
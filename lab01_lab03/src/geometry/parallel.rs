@@ -0,0 +1,115 @@
+//! Parallel execution of the intersection algorithms on std threads.
+//!
+//! The work is split into blocks of consecutive indices (rows of the brute force, cells of the grid,
+//! queries of the R-tree). Every thread takes the next block when it's done with its current one, so
+//! threads finishing early steal the remaining work of the others. Each thread collects its
+//! intersections separately, the sets (or counts, see [Collector]) are merged at the end. As the
+//! union doesn't depend on the order of the blocks, the result equals the sequential one.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::scalar::Scalar;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Receives the intersections found by a thread, the results of the threads are merged at the end.
+pub(crate) trait Collector<T: Scalar>: Default + Send {
+    fn add(&mut self, intersection: Intersection<T>);
+
+    fn merge(&mut self, other: Self);
+}
+
+impl<T: Scalar> Collector<T> for HashSet<Intersection<T>> {
+    fn add(&mut self, intersection: Intersection<T>) {
+        self.insert(intersection);
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+}
+
+/// Calls `work` for blocks of the indices `0..count` on the threads and merges the intersections
+/// found. Runs on the calling thread if only one thread is requested. A panic of a thread is
+/// propagated.
+pub(crate) fn collect_blocks<T: Scalar, C: Collector<T>>(
+    count: usize,
+    block_size: usize,
+    threads: usize,
+    work: impl Fn(Range<usize>, &mut C) + Sync,
+) -> C {
+    let mut collected = C::default();
+    if threads <= 1 {
+        work(0..count, &mut collected);
+        return collected;
+    }
+
+    let block_size = block_size.max(1);
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut found = C::default();
+                    loop {
+                        let start = next.fetch_add(block_size, Ordering::Relaxed);
+                        if start >= count {
+                            return found;
+                        }
+                        work(start..(start + block_size).min(count), &mut found);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(found) => collected.merge(found),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+    });
+    collected
+}
+
+/// Number of threads available to the process, 1 if it can't be determined.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::line::Line2D;
+    use crate::geometry::point::Point2D;
+
+    #[test]
+    fn all_blocks_should_be_processed_once() {
+        let work = |range: Range<usize>, found: &mut HashSet<Intersection>| {
+            for i in range {
+                let x = i as f64;
+                found.insert(Intersection::Crossing {
+                    line1: Line2D::new(x, 0.0, x + 1.0, 1.0),
+                    line2: Line2D::new(x, 1.0, x + 1.0, 0.0),
+                    point: Point2D::new(x + 0.5, 0.5),
+                });
+            }
+        };
+
+        let sequential = collect_blocks(1000, 7, 1, work);
+        let parallel = collect_blocks(1000, 7, 4, work);
+
+        assert_eq!(sequential.len(), 1000);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    #[should_panic(expected = "block 3")]
+    fn panics_of_threads_should_be_propagated() {
+        collect_blocks::<i64, HashSet<Intersection<i64>>>(10, 1, 3, |range, _| {
+            if range.start == 3 {
+                panic!("block 3");
+            }
+        });
+    }
+}
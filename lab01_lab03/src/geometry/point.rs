@@ -1,13 +1,12 @@
+use crate::geometry::scalar::Scalar;
 use ordered_float::OrderedFloat;
-use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 
-const EPSILON: f64 = 1e-12;
-
-#[derive(Copy, Clone, Debug, Eq, Hash)]
-pub struct Point2D {
-    pub x: OrderedFloat<f64>,
-    pub y: OrderedFloat<f64>,
+/// Points are compared exactly and ordered by x and then y.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2D<T = OrderedFloat<f64>> {
+    pub x: T,
+    pub y: T,
 }
 
 impl Point2D {
@@ -17,38 +16,32 @@ impl Point2D {
             y: OrderedFloat(y),
         }
     }
-
-    fn nearly_equals(&self, other: &Point2D) -> bool {
-        (self.x - other.x).abs() < EPSILON && (self.y - other.y).abs() < EPSILON
-    }
 }
 
-impl PartialOrd for Point2D {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl<T: Scalar> Point2D<T> {
+    /// Converts the point into the type intersection points are expressed in.
+    pub fn to_field(&self) -> Point2D<T::Field> {
+        Point2D {
+            x: self.x.to_field(),
+            y: self.y.to_field(),
+        }
     }
-}
 
-impl Ord for Point2D {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.nearly_equals(other) {
-            Ordering::Equal
-        } else {
-            self.x
-                .partial_cmp(&other.x)
-                .unwrap_or(Ordering::Equal)
-                .then_with(|| self.y.partial_cmp(&other.y).unwrap_or(Ordering::Equal))
-        }
+    /// Converts the point to the nearest float position, e.g. for rendering.
+    pub fn to_f64(&self) -> [f64; 2] {
+        [self.x.to_f64(), self.y.to_f64()]
     }
-}
 
-impl PartialEq for Point2D {
-    fn eq(&self, other: &Self) -> bool {
-        self.nearly_equals(other)
+    /// Converts the point to another scalar, returns None if it can't be represented exactly.
+    pub fn convert<U: Scalar>(&self) -> Option<Point2D<U>> {
+        Some(Point2D {
+            x: U::from_f64(self.x.to_f64())?,
+            y: U::from_f64(self.y.to_f64())?,
+        })
     }
 }
 
-impl Display for Point2D {
+impl<T: Display> Display for Point2D<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
     }
@@ -0,0 +1,368 @@
+//! Robust geometric predicates based on
+//! [Shewchuk: Adaptive Precision Floating-Point Arithmetic and Fast Robust Geometric Predicates](https://www.cs.cmu.edu/~quake/robust.html)
+//!
+//! The orientation test is first evaluated with plain floating point arithmetic. Only if the result
+//! is within the error bound, it is refined step by step with floating point expansions, which
+//! finally yields the exact sign.
+//! Exact scalars evaluate the orientation directly, see [Scalar::orient2d].
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use std::cmp::{max, min, Ordering};
+
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const RESULT_ERR_BOUND: f64 = (3.0 + 8.0 * EPSILON) * EPSILON;
+const CCW_ERR_BOUND_A: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const CCW_ERR_BOUND_B: f64 = (2.0 + 12.0 * EPSILON) * EPSILON;
+const CCW_ERR_BOUND_C: f64 = (9.0 + 64.0 * EPSILON) * EPSILON * EPSILON;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    Collinear,
+}
+
+/// Relation of two line segments, determined exactly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SegmentRelation<T = ordered_float::OrderedFloat<f64>> {
+    /// The segments have no point in common
+    Disjoint,
+    /// The segments cross in a single point, which is not an endpoint of either segment
+    Crossing,
+    /// An endpoint of one segment lies on the other segment
+    Touching(Point2D<T>),
+    /// The segments lie on the same line and have at least one point in common
+    Collinear,
+}
+
+/// Calculates whether r is on the left or on the right of the line through p and q.
+/// The sign of the result is exact, its value is an approximation of twice the area of the triangle.
+///
+/// <0 if clockwise
+/// =0 if collinear
+/// >0 if counterclockwise
+pub fn orient2d(p: Point2D, q: Point2D, r: Point2D) -> f64 {
+    let det_left = (p.x.0 - r.x.0) * (q.y.0 - r.y.0);
+    let det_right = (p.y.0 - r.y.0) * (q.x.0 - r.x.0);
+    let det = det_left - det_right;
+
+    let det_sum = if det_left > 0.0 {
+        if det_right <= 0.0 {
+            return det;
+        }
+        det_left + det_right
+    } else if det_left < 0.0 {
+        if det_right >= 0.0 {
+            return det;
+        }
+        -det_left - det_right
+    } else {
+        return det;
+    };
+
+    let err_bound = CCW_ERR_BOUND_A * det_sum;
+    if det >= err_bound || -det >= err_bound {
+        return det;
+    }
+
+    orient2d_adapt(p, q, r, det_sum)
+}
+
+pub fn orientation<T: Scalar>(p: &Point2D<T>, q: &Point2D<T>, r: &Point2D<T>) -> Orientation {
+    match T::orient2d(p, q, r) {
+        Ordering::Greater => Orientation::CounterClockwise,
+        Ordering::Less => Orientation::Clockwise,
+        Ordering::Equal => Orientation::Collinear,
+    }
+}
+
+/// Checks whether r lies on the segment between p and q.
+pub fn on_segment<T: Scalar>(p: &Point2D<T>, q: &Point2D<T>, r: &Point2D<T>) -> bool {
+    orientation(p, q, r) == Orientation::Collinear && in_bounding_box(p, q, r)
+}
+
+/// Checks whether r lies in the bounding box of p and q.
+/// For collinear points, this is equivalent to r lying on the segment.
+pub fn in_bounding_box<T: Ord>(p: &Point2D<T>, q: &Point2D<T>, r: &Point2D<T>) -> bool {
+    min(&p.x, &q.x) <= &r.x
+        && &r.x <= max(&p.x, &q.x)
+        && min(&p.y, &q.y) <= &r.y
+        && &r.y <= max(&p.y, &q.y)
+}
+
+/// Determines how the segments (a1, a2) and (b1, b2) intersect.
+/// If a touching endpoint lies on both segments, the endpoint of the first segment is returned.
+pub fn segment_relation<T: Scalar>(
+    a1: &Point2D<T>,
+    a2: &Point2D<T>,
+    b1: &Point2D<T>,
+    b2: &Point2D<T>,
+) -> SegmentRelation<T> {
+    let o1 = orientation(a1, a2, b1);
+    let o2 = orientation(a1, a2, b2);
+
+    if o1 == Orientation::Collinear && o2 == Orientation::Collinear {
+        // zero-length segments are collinear to every other segment
+        let overlapping = if a1 == a2 {
+            on_segment(b1, b2, a1)
+        } else {
+            in_bounding_box(a1, a2, b1)
+                || in_bounding_box(a1, a2, b2)
+                || in_bounding_box(b1, b2, a1)
+                || in_bounding_box(b1, b2, a2)
+        };
+        return if overlapping {
+            SegmentRelation::Collinear
+        } else {
+            SegmentRelation::Disjoint
+        };
+    }
+
+    let o3 = orientation(b1, b2, a1);
+    let o4 = orientation(b1, b2, a2);
+
+    if !straddles(o1, o2) || !straddles(o3, o4) {
+        return SegmentRelation::Disjoint;
+    }
+
+    if o3 == Orientation::Collinear {
+        SegmentRelation::Touching(a1.clone())
+    } else if o4 == Orientation::Collinear {
+        SegmentRelation::Touching(a2.clone())
+    } else if o1 == Orientation::Collinear {
+        SegmentRelation::Touching(b1.clone())
+    } else if o2 == Orientation::Collinear {
+        SegmentRelation::Touching(b2.clone())
+    } else {
+        SegmentRelation::Crossing
+    }
+}
+
+pub fn segments_intersect<T: Scalar>(
+    a1: &Point2D<T>,
+    a2: &Point2D<T>,
+    b1: &Point2D<T>,
+    b2: &Point2D<T>,
+) -> bool {
+    segment_relation(a1, a2, b1, b2) != SegmentRelation::Disjoint
+}
+
+fn straddles(o1: Orientation, o2: Orientation) -> bool {
+    o1 == Orientation::Collinear || o2 == Orientation::Collinear || o1 != o2
+}
+
+fn orient2d_adapt(p: Point2D, q: Point2D, r: Point2D, det_sum: f64) -> f64 {
+    let (px, py, qx, qy, rx, ry) = (p.x.0, p.y.0, q.x.0, q.y.0, r.x.0, r.y.0);
+    let acx = px - rx;
+    let bcx = qx - rx;
+    let acy = py - ry;
+    let bcy = qy - ry;
+
+    let (det_left, det_left_tail) = two_product(acx, bcy);
+    let (det_right, det_right_tail) = two_product(acy, bcx);
+    let b = two_two_diff(det_left, det_left_tail, det_right, det_right_tail);
+
+    let mut det = estimate(&b);
+    let err_bound = CCW_ERR_BOUND_B * det_sum;
+    if det >= err_bound || -det >= err_bound {
+        return det;
+    }
+
+    let acx_tail = two_diff_tail(px, rx, acx);
+    let bcx_tail = two_diff_tail(qx, rx, bcx);
+    let acy_tail = two_diff_tail(py, ry, acy);
+    let bcy_tail = two_diff_tail(qy, ry, bcy);
+
+    if acx_tail == 0.0 && acy_tail == 0.0 && bcx_tail == 0.0 && bcy_tail == 0.0 {
+        return det;
+    }
+
+    let err_bound = CCW_ERR_BOUND_C * det_sum + RESULT_ERR_BOUND * det.abs();
+    det += (acx * bcy_tail + bcy * acx_tail) - (acy * bcx_tail + bcx * acy_tail);
+    if det >= err_bound || -det >= err_bound {
+        return det;
+    }
+
+    // exact evaluation of the remaining terms
+    let mut result = b.to_vec();
+    for (a, b, c, d) in [
+        (acx_tail, bcy, acy_tail, bcx),
+        (acx, bcy_tail, acy, bcx_tail),
+        (acx_tail, bcy_tail, acy_tail, bcx_tail),
+    ] {
+        let (s1, s0) = two_product(a, b);
+        let (t1, t0) = two_product(c, d);
+        result = expansion_sum(&result, &two_two_diff(s1, s0, t1, t0));
+    }
+
+    // the most significant component has the sign of the whole expansion
+    result.last().copied().unwrap_or(0.0)
+}
+
+/// Exact sum a + b = x + y, where x is the rounded sum and y the error
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_round = b - b_virtual;
+    let a_round = a - a_virtual;
+    (x, a_round + b_round)
+}
+
+/// Exact difference a - b = x + y, where x is the rounded difference and y the error
+fn two_diff(a: f64, b: f64) -> (f64, f64) {
+    let x = a - b;
+    (x, two_diff_tail(a, b, x))
+}
+
+fn two_diff_tail(a: f64, b: f64, x: f64) -> f64 {
+    let b_virtual = a - x;
+    let a_virtual = x + b_virtual;
+    let b_round = b_virtual - b;
+    let a_round = a - a_virtual;
+    a_round + b_round
+}
+
+/// Exact product a * b = x + y, the fused multiply-add yields the rounding error exactly
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+/// Exact difference (a1 + a0) - (b1 + b0) as expansion with increasing magnitude
+fn two_two_diff(a1: f64, a0: f64, b1: f64, b0: f64) -> [f64; 4] {
+    let (j, x0) = two_diff(a0, b0);
+    let (j, zero) = two_sum(a1, j);
+    let (i, x1) = two_diff(zero, b1);
+    let (x3, x2) = two_sum(j, i);
+    [x0, x1, x2, x3]
+}
+
+fn estimate(expansion: &[f64]) -> f64 {
+    expansion.iter().sum()
+}
+
+/// Sums two expansions, components that are zero are eliminated.
+fn expansion_sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter()
+        .fold(e.to_vec(), |expansion, &b| grow_expansion(&expansion, b))
+}
+
+fn grow_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut result = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &component in e {
+        let (sum, error) = two_sum(q, component);
+        if error != 0.0 {
+            result.push(error);
+        }
+        q = sum;
+    }
+    if q != 0.0 || result.is_empty() {
+        result.push(q);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orientation_of_simple_triangles() {
+        let p = Point2D::new(0.0, 0.0);
+        let q = Point2D::new(1.0, 0.0);
+
+        assert_eq!(
+            orientation(&p, &q, &Point2D::new(0.5, 1.0)),
+            Orientation::CounterClockwise
+        );
+        assert_eq!(
+            orientation(&p, &q, &Point2D::new(0.5, -1.0)),
+            Orientation::Clockwise
+        );
+        assert_eq!(
+            orientation(&p, &q, &Point2D::new(2.0, 0.0)),
+            Orientation::Collinear
+        );
+    }
+
+    #[test]
+    fn orientation_should_be_exact_for_nearly_collinear_points() {
+        // orientation of (p, q, r) is 12 * (p.y - p.x), so its sign is known exactly
+        let q = Point2D::new(12.0, 12.0);
+        let r = Point2D::new(24.0, 24.0);
+        let ulp = 0.5f64.powi(53);
+
+        let mut naive_errors = 0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let p = Point2D::new(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp);
+                let expected = match p.y.cmp(&p.x) {
+                    std::cmp::Ordering::Greater => Orientation::CounterClockwise,
+                    std::cmp::Ordering::Less => Orientation::Clockwise,
+                    std::cmp::Ordering::Equal => Orientation::Collinear,
+                };
+
+                assert_eq!(orientation(&p, &q, &r), expected);
+
+                let naive = (p.x.0 - r.x.0) * (q.y.0 - r.y.0) - (p.y.0 - r.y.0) * (q.x.0 - r.x.0);
+                if naive.signum() != orient2d(p, q, r).signum() {
+                    naive_errors += 1;
+                }
+            }
+        }
+        // the naive evaluation fails for many of these points
+        assert!(naive_errors > 0);
+    }
+
+    #[test]
+    fn segment_relation_of_crossing_and_touching_segments() {
+        let a1 = Point2D::new(0.0, 0.0);
+        let a2 = Point2D::new(2.0, 2.0);
+
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(0.0, 2.0), &Point2D::new(2.0, 0.0)),
+            SegmentRelation::Crossing
+        );
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(1.0, 1.0), &Point2D::new(2.0, 0.0)),
+            SegmentRelation::Touching(Point2D::new(1.0, 1.0))
+        );
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(2.0, 2.0), &Point2D::new(3.0, 2.0)),
+            SegmentRelation::Touching(Point2D::new(2.0, 2.0))
+        );
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(1.0, 1.1), &Point2D::new(2.0, 3.0)),
+            SegmentRelation::Disjoint
+        );
+    }
+
+    #[test]
+    fn segment_relation_of_collinear_segments() {
+        let a1 = Point2D::new(0.0, 0.0);
+        let a2 = Point2D::new(2.0, 2.0);
+
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(1.0, 1.0), &Point2D::new(3.0, 3.0)),
+            SegmentRelation::Collinear
+        );
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(3.0, 3.0), &Point2D::new(4.0, 4.0)),
+            SegmentRelation::Disjoint
+        );
+        assert_eq!(
+            segment_relation(&a1, &a1, &Point2D::new(0.0, 0.0), &Point2D::new(0.0, 0.0)),
+            SegmentRelation::Collinear
+        );
+        assert_eq!(
+            segment_relation(&a1, &a1, &Point2D::new(1.0, 1.0), &Point2D::new(1.0, 1.0)),
+            SegmentRelation::Disjoint
+        );
+        assert_eq!(
+            segment_relation(&a1, &a2, &Point2D::new(1.0, 1.0), &Point2D::new(1.0, 1.0)),
+            SegmentRelation::Collinear
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! Repair of inputs for the sweep line without degenerate handling: every change to the lines is
+//! recorded, so the cleaned file can be traced back to the input.
+//!
+//! The steps run in this order, each can be disabled in [RepairOptions]:
+//! 1. zero length lines are removed
+//! 2. copies of a line, also with swapped endpoints, are removed, the first occurrence is kept
+//! 3. collinear lines which overlap are merged into one line from the smallest to the largest of
+//!    their endpoints. Overlapping pairs are found with an R-tree, chains of overlaps are merged
+//!    together. The merged line only consists of input endpoints, so it is exact
+//! 4. the endpoints are perturbed, so that no two distinct endpoints share an x-coordinate, which
+//!    also breaks vertical lines. Lines sharing an endpoint keep sharing it, only the point moves.
+//!
+//! [Perturbation::Symbolic] breaks ties like simulation of simplicity: of the points sharing an
+//! x-coordinate, the i-th by first occurrence moves right by i·ε, with ε small enough that the order
+//! of distinct x-coordinates is kept. [Perturbation::Controlled] moves a point by the smallest
+//! multiple of the given ε making its x-coordinate unique, which may change the order, but keeps the
+//! coordinates on a grid of ε, e.g. integers for `i64`.
+use crate::geometry::bounding_box::BoundingBox;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::rtree::handler::RTreeHandler;
+use crate::geometry::scalar::Scalar;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Perturbation {
+    None,
+    /// Ties of x-coordinates are broken by the order of the points, without changing the order of
+    /// distinct x-coordinates
+    Symbolic,
+    /// Points are moved by multiples of epsilon
+    Controlled {
+        epsilon: f64,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RepairOptions {
+    pub remove_zero_length: bool,
+    pub deduplicate: bool,
+    pub merge_overlaps: bool,
+    pub perturbation: Perturbation,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        RepairOptions {
+            remove_zero_length: true,
+            deduplicate: true,
+            merge_overlaps: true,
+            perturbation: Perturbation::None,
+        }
+    }
+}
+
+/// Change to the input, lines are identified by their index in the input, starting at 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<T: Scalar> {
+    ZeroLength {
+        index: usize,
+        line: Line2D<T>,
+    },
+    Duplicate {
+        index: usize,
+        line: Line2D<T>,
+        /// Index of the kept copy
+        of: usize,
+    },
+    Merged {
+        indices: Vec<usize>,
+        merged: Line2D<T>,
+    },
+    Moved {
+        from: Point2D<T>,
+        to: Point2D<T>,
+        /// Lines with the point as endpoint, merged lines by their first index
+        indices: Vec<usize>,
+    },
+}
+
+impl<T: Scalar> Display for Change<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            Change::ZeroLength { index, line } => {
+                write!(f, "removed zero length line {}: {}", index, line)
+            }
+            Change::Duplicate { index, line, of } => {
+                write!(f, "removed line {}, a copy of line {}: {}", index, of, line)
+            }
+            Change::Merged { indices, merged } => {
+                write!(
+                    f,
+                    "merged overlapping lines {} into {}",
+                    join(indices),
+                    merged
+                )
+            }
+            Change::Moved { from, to, indices } => write!(
+                f,
+                "moved endpoint {} of lines {} to {}",
+                from,
+                join(indices),
+                to
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RepairError {
+    /// The epsilon of [Perturbation::Controlled] isn't finite and positive
+    InvalidEpsilon { epsilon: f64 },
+    /// The perturbed x-coordinate can't be represented by the scalar, or rounds onto another one
+    NotRepresentable { x: String },
+}
+
+impl Display for RepairError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepairError::InvalidEpsilon { epsilon } => {
+                write!(f, "Epsilon has to be positive: {}", epsilon)
+            }
+            RepairError::NotRepresentable { x } => write!(
+                f,
+                "The perturbation of x = {} can't be represented by the coordinate type",
+                x
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RepairError {}
+
+pub struct Repair<T: Scalar> {
+    /// Cleaned lines, in the order of their first input line
+    pub lines: Vec<Line2D<T>>,
+    pub changes: Vec<Change<T>>,
+}
+
+impl<T: Scalar> Repair<T> {
+    /// Writes every change on one row.
+    pub fn write_log<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for change in &self.changes {
+            writeln!(writer, "{}", change)?;
+        }
+        Ok(())
+    }
+
+    /// Number of removed zero length lines, removed copies, merged lines and moved points.
+    pub fn summary(&self) -> (usize, usize, usize, usize) {
+        let mut summary = (0, 0, 0, 0);
+        for change in &self.changes {
+            match change {
+                Change::ZeroLength { .. } => summary.0 += 1,
+                Change::Duplicate { .. } => summary.1 += 1,
+                Change::Merged { indices, .. } => summary.2 += indices.len(),
+                Change::Moved { .. } => summary.3 += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Line of the repair with the input lines it stems from.
+struct Tracked<T: Scalar> {
+    indices: Vec<usize>,
+    line: Line2D<T>,
+}
+
+pub fn repair<T: Scalar>(
+    lines: &[Line2D<T>],
+    options: &RepairOptions,
+) -> Result<Repair<T>, RepairError> {
+    if let Perturbation::Controlled { epsilon } = options.perturbation {
+        if !(epsilon.is_finite() && epsilon > 0.0) {
+            return Err(RepairError::InvalidEpsilon { epsilon });
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut kept = Vec::new();
+    let mut copies = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        if options.remove_zero_length && line.is_zero_length() {
+            changes.push(Change::ZeroLength {
+                index,
+                line: line.clone(),
+            });
+            continue;
+        }
+        if options.deduplicate {
+            match copies.entry(line.sorted_endpoints()) {
+                Entry::Occupied(first) => {
+                    changes.push(Change::Duplicate {
+                        index,
+                        line: line.clone(),
+                        of: *first.get(),
+                    });
+                    continue;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(index);
+                }
+            }
+        }
+        kept.push(Tracked {
+            indices: vec![index],
+            line: line.clone(),
+        });
+    }
+
+    if options.merge_overlaps {
+        kept = merge_overlaps(kept, &mut changes);
+    }
+    match options.perturbation {
+        Perturbation::None => {}
+        Perturbation::Symbolic => perturb(&mut kept, &mut changes, symbolic_shifts)?,
+        Perturbation::Controlled { epsilon } => perturb(&mut kept, &mut changes, |points| {
+            controlled_shifts(points, epsilon)
+        })?,
+    }
+
+    Ok(Repair {
+        lines: kept.into_iter().map(|tracked| tracked.line).collect(),
+        changes,
+    })
+}
+
+fn merge_overlaps<T: Scalar>(
+    kept: Vec<Tracked<T>>,
+    changes: &mut Vec<Change<T>>,
+) -> Vec<Tracked<T>> {
+    let lines: Vec<Line2D<T>> = kept.iter().map(|tracked| tracked.line.clone()).collect();
+    let tree = RTreeHandler::new(lines.clone());
+    let mut parents: Vec<usize> = (0..lines.len()).collect();
+    for (i, line) in lines.iter().enumerate() {
+        for j in tree.query(&BoundingBox::of(line)) {
+            if j <= i {
+                continue;
+            }
+            if let Some(
+                Intersection::PartialOverlap { .. }
+                | Intersection::ContainedOverlap { .. }
+                | Intersection::IdenticalOverlap { .. },
+            ) = line.find_intersection(lines[j].clone())
+            {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                // the smaller position becomes the root, so the group stays at its first line
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<Tracked<T>>> = BTreeMap::new();
+    for (i, tracked) in kept.into_iter().enumerate() {
+        let group = root(&mut parents, i);
+        groups.entry(group).or_default().push(tracked);
+    }
+    groups
+        .into_values()
+        .map(|group| {
+            if group.len() == 1 {
+                return group.into_iter().next().expect("Group is not empty");
+            }
+            // the lines are collinear, so the order of the points is their order along the line
+            let start = group
+                .iter()
+                .map(|tracked| tracked.line.sorted_endpoints().0)
+                .min()
+                .expect("Group is not empty")
+                .clone();
+            let end = group
+                .iter()
+                .map(|tracked| tracked.line.sorted_endpoints().1)
+                .max()
+                .expect("Group is not empty")
+                .clone();
+            let merged = Line2D { start, end };
+            let indices: Vec<usize> = group
+                .into_iter()
+                .flat_map(|tracked| tracked.indices)
+                .collect();
+            changes.push(Change::Merged {
+                indices: indices.clone(),
+                merged: merged.clone(),
+            });
+            Tracked {
+                indices,
+                line: merged,
+            }
+        })
+        .collect()
+}
+
+/// Root of the set in the union-find forest, halving the path on the way.
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Moves the endpoints by the new x-coordinates computed from the distinct endpoints in the order of
+/// their first occurrence.
+fn perturb<T: Scalar>(
+    kept: &mut [Tracked<T>],
+    changes: &mut Vec<Change<T>>,
+    shifts: impl FnOnce(&[&Point2D<T>]) -> Result<HashMap<Point2D<T>, T>, RepairError>,
+) -> Result<(), RepairError> {
+    let mut points: Vec<&Point2D<T>> = Vec::new();
+    let mut lines_of: HashMap<&Point2D<T>, Vec<usize>> = HashMap::new();
+    for tracked in kept.iter() {
+        for point in [&tracked.line.start, &tracked.line.end] {
+            let lines = lines_of.entry(point).or_insert_with(|| {
+                points.push(point);
+                Vec::new()
+            });
+            if !lines.contains(&tracked.indices[0]) {
+                lines.push(tracked.indices[0]);
+            }
+        }
+    }
+
+    let shifted = shifts(&points)?;
+    for point in &points {
+        if let Some(x) = shifted.get(*point) {
+            changes.push(Change::Moved {
+                from: (*point).clone(),
+                to: Point2D {
+                    x: x.clone(),
+                    y: point.y.clone(),
+                },
+                indices: lines_of[*point].clone(),
+            });
+        }
+    }
+
+    for tracked in kept.iter_mut() {
+        for point in [&mut tracked.line.start, &mut tracked.line.end] {
+            if let Some(x) = shifted.get(point) {
+                point.x = x.clone();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The i-th point of an x-coordinate moves by i·ε, with ε a fraction of the smallest gap between
+/// distinct x-coordinates, so the points stay between their neighbouring x-coordinates.
+fn symbolic_shifts<T: Scalar>(
+    points: &[&Point2D<T>],
+) -> Result<HashMap<Point2D<T>, T>, RepairError> {
+    let mut ties: BTreeMap<&T, Vec<&Point2D<T>>> = BTreeMap::new();
+    for point in points {
+        ties.entry(&point.x).or_default().push(point);
+    }
+    let xs: Vec<&T> = ties.keys().copied().collect();
+    let gap = xs
+        .windows(2)
+        .map(|pair| pair[1].to_f64() - pair[0].to_f64())
+        .fold(f64::INFINITY, f64::min);
+    let largest = ties.values().map(Vec::len).max().unwrap_or(1);
+    let scale = xs.iter().map(|x| x.to_f64().abs()).fold(1.0, f64::max);
+    let epsilon = gap.min(scale) / (2 * largest) as f64;
+
+    let mut shifted = HashMap::new();
+    for (position, (&x, tied)) in ties.iter().enumerate() {
+        let next = xs.get(position + 1);
+        for (i, point) in tied.iter().enumerate().skip(1) {
+            let moved = T::from_f64(x.to_f64() + i as f64 * epsilon)
+                .filter(|moved| moved > x && next.is_none_or(|next| moved < *next))
+                .ok_or_else(|| RepairError::NotRepresentable { x: x.to_string() })?;
+            shifted.insert((*point).clone(), moved);
+        }
+    }
+    Ok(shifted)
+}
+
+/// A point whose x-coordinate is taken moves right by the smallest multiple of ε which is free.
+/// ε has to be larger than the rounding of the coordinates, otherwise the point doesn't move.
+fn controlled_shifts<T: Scalar>(
+    points: &[&Point2D<T>],
+    epsilon: f64,
+) -> Result<HashMap<Point2D<T>, T>, RepairError> {
+    let mut taken: HashSet<T> = HashSet::new();
+    let mut shifted = HashMap::new();
+    for point in points {
+        if taken.insert(point.x.clone()) {
+            continue;
+        }
+        let mut step = 1.0;
+        let moved = loop {
+            let moved = T::from_f64(point.x.to_f64() + step * epsilon).ok_or_else(|| {
+                RepairError::NotRepresentable {
+                    x: point.x.to_string(),
+                }
+            })?;
+            if moved == point.x {
+                return Err(RepairError::NotRepresentable {
+                    x: point.x.to_string(),
+                });
+            }
+            if !taken.contains(&moved) {
+                break moved;
+            }
+            step += 1.0;
+        };
+        taken.insert(moved.clone());
+        shifted.insert((*point).clone(), moved);
+    }
+    Ok(shifted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(x1: i64, y1: i64, x2: i64, y2: i64) -> Line2D<i64> {
+        Line2D {
+            start: Point2D { x: x1, y: y1 },
+            end: Point2D { x: x2, y: y2 },
+        }
+    }
+
+    #[test]
+    fn degenerate_lines_should_be_removed_and_merged() {
+        let lines = vec![
+            line(0, 0, 4, 4),
+            line(3, 3, 3, 3),
+            line(4, 4, 0, 0),
+            line(2, 2, 6, 6),
+            line(6, 6, 7, 7),
+            line(0, 5, 5, 0),
+            line(5, 5, 9, 9),
+        ];
+
+        let repair = repair(&lines, &RepairOptions::default()).unwrap();
+
+        assert_eq!(repair.lines, vec![line(0, 0, 9, 9), line(0, 5, 5, 0)]);
+        assert_eq!(
+            repair.changes,
+            vec![
+                Change::ZeroLength {
+                    index: 1,
+                    line: line(3, 3, 3, 3)
+                },
+                Change::Duplicate {
+                    index: 2,
+                    line: line(4, 4, 0, 0),
+                    of: 0
+                },
+                // line 4 only touches line 3, but lies within line 6
+                Change::Merged {
+                    indices: vec![0, 3, 4, 6],
+                    merged: line(0, 0, 9, 9)
+                },
+            ]
+        );
+        assert_eq!(repair.summary(), (1, 1, 4, 0));
+    }
+
+    #[test]
+    fn controlled_perturbation_should_break_shared_x_and_verticals() {
+        let lines = vec![
+            line(0, 0, 4, 2),
+            line(4, 2, 8, 0),
+            line(4, 5, 4, 9),
+            line(0, 3, 5, 3),
+        ];
+        let options = RepairOptions {
+            perturbation: Perturbation::Controlled { epsilon: 1.0 },
+            ..RepairOptions::default()
+        };
+
+        let repair = repair(&lines, &options).unwrap();
+
+        assert_eq!(
+            repair.lines,
+            vec![
+                line(0, 0, 4, 2),
+                line(4, 2, 8, 0),
+                line(5, 5, 6, 9),
+                line(1, 3, 7, 3),
+            ]
+        );
+        assert_eq!(
+            repair.changes[0],
+            Change::Moved {
+                from: Point2D { x: 4, y: 5 },
+                to: Point2D { x: 5, y: 5 },
+                indices: vec![2],
+            }
+        );
+        // the shared endpoint is the only x-coordinate of two lines
+        let points: HashSet<&Point2D<i64>> = repair
+            .lines
+            .iter()
+            .flat_map(|line| [&line.start, &line.end])
+            .collect();
+        let xs: HashSet<i64> = points.iter().map(|point| point.x).collect();
+        assert_eq!(xs.len(), points.len());
+
+        for epsilon in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let options = RepairOptions {
+                perturbation: Perturbation::Controlled { epsilon },
+                ..RepairOptions::default()
+            };
+            assert!(matches!(
+                super::repair(&lines, &options),
+                Err(RepairError::InvalidEpsilon { .. })
+            ));
+        }
+        let below_rounding = RepairOptions {
+            perturbation: Perturbation::Controlled { epsilon: 1e-20 },
+            ..RepairOptions::default()
+        };
+        let floats: Vec<Line2D> = lines.iter().map(|line| line.convert().unwrap()).collect();
+        assert!(matches!(
+            super::repair(&floats, &below_rounding),
+            Err(RepairError::NotRepresentable { .. })
+        ));
+    }
+
+    #[test]
+    fn symbolic_perturbation_should_keep_the_order_of_distinct_x() {
+        let lines = vec![
+            Line2D::new(1.0, 0.0, 1.0, 2.0),
+            Line2D::new(1.0, 3.0, 2.0, 3.0),
+            Line2D::new(0.0, 1.0, 2.0, 1.0),
+        ];
+        let options = RepairOptions {
+            perturbation: Perturbation::Symbolic,
+            ..RepairOptions::default()
+        };
+
+        let repair = repair(&lines, &options).unwrap();
+
+        let xs: Vec<f64> = repair
+            .lines
+            .iter()
+            .flat_map(|line| [line.start.x.0, line.end.x.0])
+            .collect();
+        // three points at x = 1 and two at x = 2, the smallest gap is 1
+        assert_eq!(
+            xs,
+            vec![
+                1.0,
+                1.0 + 1.0 / 6.0,
+                1.0 + 2.0 / 6.0,
+                2.0,
+                0.0,
+                2.0 + 1.0 / 6.0
+            ]
+        );
+        assert_eq!(repair.changes.len(), 3);
+        let mut write = Vec::new();
+        repair.write_log(&mut write).unwrap();
+        assert!(String::from_utf8(write)
+            .unwrap()
+            .starts_with("moved endpoint (1, 2) of lines 0 to (1.1666666666666667, 2)\n"));
+    }
+}
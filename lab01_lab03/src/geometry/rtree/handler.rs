@@ -0,0 +1,246 @@
+//! R-tree over the bounding boxes of the lines, bulk loaded with Sort-Tile-Recursive packing
+//! ([Leutenegger et al.: STR: A Simple and Efficient Algorithm for R-Tree Packing](https://doi.org/10.1109/ICDE.1997.582015)).
+//!
+//! The boxes are sorted by the x-coordinate of their center into vertical slices, each slice by the
+//! y-coordinate into nodes of up to [NODE_CAPACITY] entries, and the nodes are packed the same way
+//! level by level. Every line then queries the tree with its bounding box and is intersected exactly
+//! with the lines after it whose boxes intersect, so the result is the one of the brute force,
+//! including all degenerate cases.
+//!
+//! Complexity: O(n log n) to build the tree, O(log n + c) per query with c the number of candidates.
+use crate::geometry::algorithm;
+use crate::geometry::bounding_box::BoundingBox;
+use crate::geometry::counting::{self, IntersectionCounts};
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::parallel::{self, Collector};
+use crate::geometry::scalar::Scalar;
+use ordered_float::OrderedFloat;
+use std::collections::HashSet;
+
+/// Maximum number of entries of a node
+pub const NODE_CAPACITY: usize = 16;
+
+/// Number of queries a thread takes at once
+const BLOCK_QUERIES: usize = 64;
+
+struct Node<T: Scalar> {
+    bounds: BoundingBox<T>,
+    /// Indices of the lines in a leaf, of the child nodes otherwise
+    entries: Vec<usize>,
+    leaf: bool,
+}
+
+pub struct RTreeHandler<T: Scalar = OrderedFloat<f64>> {
+    lines: Vec<Line2D<T>>,
+    boxes: Vec<BoundingBox<T>>,
+    /// Nodes of all levels, the root is the last one
+    nodes: Vec<Node<T>>,
+    intersections: HashSet<Intersection<T>>,
+    /// number of red lines at the start of the lines in the bichromatic mode
+    red_count: Option<usize>,
+    threads: usize,
+}
+
+impl<T: Scalar> RTreeHandler<T> {
+    pub fn new(lines: Vec<Line2D<T>>) -> Self {
+        let boxes: Vec<BoundingBox<T>> = lines.iter().map(BoundingBox::of).collect();
+        let nodes = pack(&boxes);
+        Self {
+            lines,
+            boxes,
+            nodes,
+            intersections: HashSet::new(),
+            red_count: None,
+            threads: 1,
+        }
+    }
+
+    /// Creates a handler which only reports intersections of red with blue lines.
+    pub fn new_bichromatic(red: Vec<Line2D<T>>, blue: Vec<Line2D<T>>) -> Self {
+        let red_count = red.len();
+        let mut lines = red;
+        lines.extend(blue);
+        Self {
+            red_count: Some(red_count),
+            ..Self::new(lines)
+        }
+    }
+
+    /// Runs the queries of the lines on the given number of threads, see [parallel].
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn run(&mut self) -> HashSet<Intersection<T>> {
+        let found = self.collect::<HashSet<_>>();
+        self.intersections.extend(found);
+
+        self.intersections.clone()
+    }
+
+    /// Counts the intersections [run](Self::run) would return without storing them.
+    pub fn count(&self) -> IntersectionCounts {
+        counting::count_distinct(&self.lines, self.red_count, |lines, red_count| {
+            Self {
+                red_count,
+                ..Self::new(lines)
+            }
+            .with_threads(self.threads)
+            .collect()
+        })
+    }
+
+    fn collect<C: Collector<T>>(&self) -> C {
+        // in the bichromatic mode the red lines find all pairs
+        let queries = self.red_count.unwrap_or(self.lines.len());
+        parallel::collect_blocks(queries, BLOCK_QUERIES, self.threads, |block, found| {
+            for i in block {
+                self.intersect_candidates(i, found);
+            }
+        })
+    }
+
+    /// Intersects the line with the following lines whose bounding boxes intersect its box.
+    fn intersect_candidates(&self, i: usize, intersections: &mut impl Collector<T>) {
+        for j in self.query(&self.boxes[i]) {
+            if j <= i || !algorithm::is_tested_pair(self.red_count, i, j) {
+                continue;
+            }
+            let line1 = &self.lines[i];
+            let line2 = &self.lines[j];
+            if line1.intersects(line2.clone()) {
+                let intersection = line1
+                    .find_intersection(line2.clone())
+                    .expect("Intersection not found!");
+
+                intersections.add(intersection);
+            }
+        }
+    }
+
+    /// Indices of the lines whose bounding boxes intersect the box.
+    pub fn query(&self, bounds: &BoundingBox<T>) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.nodes.len().checked_sub(1).into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bounds.intersects(bounds) {
+                continue;
+            }
+            if node.leaf {
+                found.extend(
+                    node.entries
+                        .iter()
+                        .filter(|&&i| self.boxes[i].intersects(bounds)),
+                );
+            } else {
+                stack.extend(&node.entries);
+            }
+        }
+        found
+    }
+
+    /// Number of levels of the tree, 0 without lines.
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut node = self.nodes.last();
+        while let Some(current) = node {
+            height += 1;
+            node = match current.leaf {
+                true => None,
+                false => current.entries.first().map(|&child| &self.nodes[child]),
+            };
+        }
+        height
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// Packs the boxes into the nodes of all levels, bottom up.
+fn pack<T: Scalar>(boxes: &[BoundingBox<T>]) -> Vec<Node<T>> {
+    let mut nodes: Vec<Node<T>> = Vec::new();
+    if boxes.is_empty() {
+        return nodes;
+    }
+
+    let mut level: Vec<usize> = (0..boxes.len()).collect();
+    let mut leaf = true;
+    loop {
+        let bounds = |i: usize| match leaf {
+            true => &boxes[i],
+            false => &nodes[i].bounds,
+        };
+        let groups = tile(level, |i| bounds(i).center());
+        let parents: Vec<Node<T>> = groups
+            .into_iter()
+            .map(|entries| Node {
+                bounds: entries[1..]
+                    .iter()
+                    .fold(bounds(entries[0]).clone(), |union, &i| {
+                        union.union(bounds(i))
+                    }),
+                entries,
+                leaf,
+            })
+            .collect();
+
+        let first = nodes.len();
+        nodes.extend(parents);
+        if nodes.len() - first == 1 {
+            return nodes;
+        }
+        level = (first..nodes.len()).collect();
+        leaf = false;
+    }
+}
+
+/// Sort-Tile-Recursive: groups the entries into nodes of up to [NODE_CAPACITY] neighbouring entries.
+fn tile(mut entries: Vec<usize>, center: impl Fn(usize) -> (f64, f64)) -> Vec<Vec<usize>> {
+    let node_count = entries.len().div_ceil(NODE_CAPACITY);
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count * NODE_CAPACITY;
+
+    entries.sort_by(|&a, &b| center(a).0.total_cmp(&center(b).0));
+    let mut groups = Vec::with_capacity(node_count);
+    for slice in entries.chunks_mut(slice_size) {
+        slice.sort_by(|&a, &b| center(a).1.total_cmp(&center(b).1));
+        groups.extend(slice.chunks(NODE_CAPACITY).map(<[usize]>::to_vec));
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_should_contain_all_lines() {
+        let lines: Vec<Line2D<i64>> = (0..1000)
+            .map(|i| Line2D {
+                start: crate::geometry::point::Point2D {
+                    x: i % 37,
+                    y: i / 37,
+                },
+                end: crate::geometry::point::Point2D {
+                    x: i % 37 + 1,
+                    y: i / 37 + 1,
+                },
+            })
+            .collect();
+        let handler = RTreeHandler::new(lines);
+
+        let everything = handler.nodes.last().unwrap().bounds.clone();
+        let mut found = handler.query(&everything);
+        found.sort();
+
+        assert_eq!(found, (0..1000).collect::<Vec<_>>());
+        // 1000 lines need 63 leaves, 4 nodes above them and the root
+        assert_eq!(handler.height(), 3);
+        assert_eq!(RTreeHandler::<i64>::new(Vec::new()).height(), 0);
+    }
+}
@@ -0,0 +1,341 @@
+//! Coordinate types the geometry is generic over.
+//!
+//! [Scalar] is implemented for
+//! - `OrderedFloat<f64>`: the default, crossing points are rounded to the nearest float
+//! - `i64`: exact, products are computed with `i128`, crossing points are [BigRational]s.
+//!   Coordinates have to lie within ±2^62, so that differences of coordinates fit into an `i64`
+//! - [BigRational]: exact with arbitrary precision
+//!
+//! The crossing point of two segments generally doesn't lie on the grid of the input coordinates,
+//! so every scalar names the [Scalar::Field] its intersection points are expressed in.
+use crate::geometry::line::Line2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::predicates;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
+
+/// Relative tolerance of floating point coordinates, e.g. to decide whether a line passes through an event point
+const TOLERANCE: f64 = 1e-9;
+
+/// Largest absolute value of an `i64` coordinate
+pub const MAX_INTEGER_COORDINATE: i64 = 1 << 62;
+
+pub trait Scalar: Clone + Debug + Display + Ord + Hash + Send + Sync + 'static {
+    /// Type of the coordinates of intersection points
+    type Field: Scalar<Field = Self::Field>
+        + Add<Output = Self::Field>
+        + Sub<Output = Self::Field>
+        + Mul<Output = Self::Field>
+        + Div<Output = Self::Field>;
+
+    /// Name of the scalar on the command line
+    const NAME: &'static str;
+
+    /// Whether the values are floating point numbers, i.e. rounded
+    const IS_FLOAT: bool;
+
+    /// Converts the value, returns None if it can't be represented exactly.
+    fn from_f64(value: f64) -> Option<Self>;
+
+    /// Parses a coordinate of an input file.
+    fn parse(value: &str) -> Option<Self> {
+        value.parse::<f64>().ok().and_then(Self::from_f64)
+    }
+
+    /// Converts the value to the nearest float.
+    fn to_f64(&self) -> f64;
+
+    fn to_field(&self) -> Self::Field;
+
+    /// Exact sign of the orientation of r relative to the line through p and q,
+    /// Greater if counterclockwise, Less if clockwise and Equal if collinear.
+    fn orient2d(p: &Point2D<Self>, q: &Point2D<Self>, r: &Point2D<Self>) -> Ordering;
+
+    /// Calculates the intersection point of two crossing line segments.
+    fn crossing_point(line1: &Line2D<Self>, line2: &Line2D<Self>) -> Point2D<Self::Field>;
+
+    /// Largest difference of two values close to the reference which are still considered equal.
+    /// Zero for exact scalars.
+    fn tolerance(reference: &Self) -> Self;
+}
+
+impl Scalar for OrderedFloat<f64> {
+    type Field = OrderedFloat<f64>;
+
+    const NAME: &'static str = "f64";
+
+    const IS_FLOAT: bool = true;
+
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(OrderedFloat(value))
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0
+    }
+
+    fn to_field(&self) -> Self::Field {
+        *self
+    }
+
+    fn orient2d(p: &Point2D<Self>, q: &Point2D<Self>, r: &Point2D<Self>) -> Ordering {
+        let det = predicates::orient2d(*p, *q, *r);
+        det.partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+    }
+
+    fn crossing_point(line1: &Line2D<Self>, line2: &Line2D<Self>) -> Point2D<Self::Field> {
+        let (x1, y1) = (line1.start.x.0, line1.start.y.0);
+        let (x2, y2) = (line1.end.x.0, line1.end.y.0);
+        let (x3, y3) = (line2.start.x.0, line2.start.y.0);
+        let (x4, y4) = (line2.end.x.0, line2.end.y.0);
+
+        let denominator = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
+        let ua_numerator = (x4 - x3) * (y1 - y3) - (y4 - y3) * (x1 - x3);
+        // rounding might move the parameter slightly out of the segment
+        let ua = (ua_numerator / denominator).clamp(0.0, 1.0);
+
+        Point2D::new(x1 + ua * (x2 - x1), y1 + ua * (y2 - y1))
+    }
+
+    fn tolerance(reference: &Self) -> Self {
+        OrderedFloat(TOLERANCE * reference.0.abs().max(1.0))
+    }
+}
+
+impl Scalar for i64 {
+    type Field = BigRational;
+
+    const NAME: &'static str = "i64";
+
+    const IS_FLOAT: bool = false;
+
+    fn from_f64(value: f64) -> Option<Self> {
+        if value.fract() == 0.0 && value.abs() <= MAX_INTEGER_COORDINATE as f64 {
+            Some(value as i64)
+        } else {
+            None
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.parse::<i64>() {
+            Ok(value) if value.unsigned_abs() <= MAX_INTEGER_COORDINATE as u64 => Some(value),
+            Ok(_) => None,
+            Err(_) => value.parse::<f64>().ok().and_then(Self::from_f64),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+
+    fn to_field(&self) -> Self::Field {
+        BigRational::from_integer(BigInt::from(*self))
+    }
+
+    fn orient2d(p: &Point2D<Self>, q: &Point2D<Self>, r: &Point2D<Self>) -> Ordering {
+        // differences fit into 63 bits, so the products and their difference fit into an i128
+        let det_left = (p.x as i128 - r.x as i128) * (q.y as i128 - r.y as i128);
+        let det_right = (p.y as i128 - r.y as i128) * (q.x as i128 - r.x as i128);
+        det_left.cmp(&det_right)
+    }
+
+    fn crossing_point(line1: &Line2D<Self>, line2: &Line2D<Self>) -> Point2D<Self::Field> {
+        let big = |value: i64| BigInt::from(value);
+        let (x1, y1) = (big(line1.start.x), big(line1.start.y));
+        let (x2, y2) = (big(line1.end.x), big(line1.end.y));
+        let (x3, y3) = (big(line2.start.x), big(line2.start.y));
+        let (x4, y4) = (big(line2.end.x), big(line2.end.y));
+
+        // products of differences need up to 127 bits, their difference may overflow an i128
+        let denominator = (&y4 - &y3) * (&x2 - &x1) - (&x4 - &x3) * (&y2 - &y1);
+        let ua_numerator = (&x4 - &x3) * (&y1 - &y3) - (&y4 - &y3) * (&x1 - &x3);
+
+        // start + ua * direction with ua = ua_numerator / denominator
+        let coordinate = |start: &BigInt, delta: BigInt| {
+            BigRational::new(
+                start * &denominator + &ua_numerator * delta,
+                denominator.clone(),
+            )
+        };
+        Point2D {
+            x: coordinate(&x1, &x2 - &x1),
+            y: coordinate(&y1, &y2 - &y1),
+        }
+    }
+
+    fn tolerance(_reference: &Self) -> Self {
+        0
+    }
+}
+
+impl Scalar for BigRational {
+    type Field = BigRational;
+
+    const NAME: &'static str = "rational";
+
+    const IS_FLOAT: bool = false;
+
+    fn from_f64(value: f64) -> Option<Self> {
+        BigRational::from_float(value)
+    }
+
+    /// Accepts fractions like `1/3` and decimal numbers, which are parsed exactly.
+    /// Numbers in scientific notation are converted through their nearest float.
+    fn parse(value: &str) -> Option<Self> {
+        BigRational::from_str(value)
+            .ok()
+            .or_else(|| parse_decimal(value))
+            .or_else(|| value.parse::<f64>().ok().and_then(Self::from_f64))
+    }
+
+    fn to_f64(&self) -> f64 {
+        ToPrimitive::to_f64(self).unwrap_or(f64::NAN)
+    }
+
+    fn to_field(&self) -> Self::Field {
+        self.clone()
+    }
+
+    fn orient2d(p: &Point2D<Self>, q: &Point2D<Self>, r: &Point2D<Self>) -> Ordering {
+        let det_left = (&p.x - &r.x) * (&q.y - &r.y);
+        let det_right = (&p.y - &r.y) * (&q.x - &r.x);
+        det_left.cmp(&det_right)
+    }
+
+    fn crossing_point(line1: &Line2D<Self>, line2: &Line2D<Self>) -> Point2D<Self::Field> {
+        let (x1, y1) = (&line1.start.x, &line1.start.y);
+        let (x2, y2) = (&line1.end.x, &line1.end.y);
+        let (x3, y3) = (&line2.start.x, &line2.start.y);
+        let (x4, y4) = (&line2.end.x, &line2.end.y);
+
+        let denominator = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
+        let ua = ((x4 - x3) * (y1 - y3) - (y4 - y3) * (x1 - x3)) / denominator;
+
+        Point2D {
+            x: x1 + &ua * (x2 - x1),
+            y: y1 + &ua * (y2 - y1),
+        }
+    }
+
+    fn tolerance(_reference: &Self) -> Self {
+        BigRational::zero()
+    }
+}
+
+/// Parses a decimal number like `-12.375` without rounding.
+fn parse_decimal(value: &str) -> Option<BigRational> {
+    let (integer, fraction) = value.split_once('.')?;
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let numerator = BigInt::from_str(&format!("{}{}", integer, fraction)).ok()?;
+    let denominator = BigInt::from(10).pow(fraction.len() as u32);
+    Some(BigRational::new(numerator, denominator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    #[test]
+    fn integer_orientation_should_be_exact_for_large_coordinates() {
+        let max = MAX_INTEGER_COORDINATE;
+        let p = Point2D { x: -max, y: -max };
+        let q = Point2D { x: max, y: max - 1 };
+        let r = Point2D { x: max, y: max };
+
+        assert_eq!(i64::orient2d(&p, &q, &r), Ordering::Greater);
+        assert_eq!(i64::orient2d(&p, &r, &q), Ordering::Less);
+        assert_eq!(
+            i64::orient2d(&p, &r, &Point2D { x: 0, y: 0 }),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn integer_crossing_point_should_be_rational() {
+        let line1 = Line2D {
+            start: Point2D { x: 0, y: 0 },
+            end: Point2D { x: 3, y: 1 },
+        };
+        let line2 = Line2D {
+            start: Point2D { x: 0, y: 1 },
+            end: Point2D { x: 1, y: 0 },
+        };
+
+        let point = i64::crossing_point(&line1, &line2);
+
+        assert_eq!(point.x, rational(3, 4));
+        assert_eq!(point.y, rational(1, 4));
+
+        let max = MAX_INTEGER_COORDINATE;
+        let line1 = Line2D {
+            start: Point2D { x: -max, y: -max },
+            end: Point2D { x: max, y: max },
+        };
+        let line2 = Line2D {
+            start: Point2D { x: max, y: -max },
+            end: Point2D { x: -max, y: max },
+        };
+
+        let point = i64::crossing_point(&line1, &line2);
+
+        assert_eq!(point.x, rational(0, 1));
+        assert_eq!(point.y, rational(0, 1));
+    }
+
+    #[test]
+    fn rational_crossing_point_should_be_exact() {
+        let third = rational(1, 3);
+        let line1 = Line2D {
+            start: Point2D {
+                x: BigRational::zero(),
+                y: BigRational::zero(),
+            },
+            end: Point2D {
+                x: BigRational::from_integer(BigInt::from(1)),
+                y: third.clone(),
+            },
+        };
+        let line2 = Line2D {
+            start: Point2D {
+                x: BigRational::zero(),
+                y: third.clone(),
+            },
+            end: Point2D {
+                x: BigRational::from_integer(BigInt::from(1)),
+                y: BigRational::zero(),
+            },
+        };
+
+        let point = BigRational::crossing_point(&line1, &line2);
+
+        assert_eq!(point.x, rational(1, 2));
+        assert_eq!(point.y, rational(1, 6));
+    }
+
+    #[test]
+    fn should_parse_coordinates() {
+        assert_eq!(i64::parse("42"), Some(42));
+        assert_eq!(i64::parse("42.0"), Some(42));
+        assert_eq!(i64::parse("42.5"), None);
+        assert_eq!(i64::parse("9223372036854775807"), None);
+        assert_eq!(i64::parse("-9223372036854775808"), None);
+        assert_eq!(BigRational::parse("1/3"), Some(rational(1, 3)));
+        assert_eq!(BigRational::parse("0.5"), Some(rational(1, 2)));
+        assert_eq!(BigRational::parse("-0.1"), Some(rational(-1, 10)));
+        assert_eq!(OrderedFloat::<f64>::parse("0.1"), Some(OrderedFloat(0.1)));
+    }
+}
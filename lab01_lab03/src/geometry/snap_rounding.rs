@@ -0,0 +1,263 @@
+//! Snap rounding of line segments and their intersections onto a grid, based on
+//! [Hobby: Practical segment intersection with finite precision output](https://doi.org/10.1016/S0925-7721(99)00021-8)
+//!
+//! Every grid cell (pixel) which contains an endpoint or an intersection point is hot. Each segment
+//! is replaced by the polyline through the centers of all hot pixels it passes, in the order it passes them.
+//! The rounded segments don't cross each other, they only meet at their endpoints or coincide.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::predicates::{self, Orientation};
+use crate::geometry::scalar::Scalar;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Column and row of a pixel, its center is at (column * grid, row * grid)
+type Pixel = (i64, i64);
+
+pub struct SnapRounding {
+    grid: f64,
+    /// rows of the hot pixels by their column
+    hot_pixels: BTreeMap<i64, BTreeSet<i64>>,
+}
+
+impl SnapRounding {
+    /// Creates a snap rounding onto a grid with the given spacing.
+    pub fn new(grid: f64) -> Self {
+        assert!(
+            grid.is_finite() && grid > 0.0,
+            "Grid spacing has to be positive: {}",
+            grid
+        );
+        SnapRounding {
+            grid,
+            hot_pixels: BTreeMap::new(),
+        }
+    }
+
+    /// Rounds the segments with their intersections onto the grid.
+    /// Returns the rounded sub-segments, each of them only once. Segments which collapse into a
+    /// single pixel are dropped.
+    ///
+    /// Complexity: O((n + k) log n + m) with m the number of hot pixels in the columns passed by the segments
+    pub fn round<T: Scalar>(
+        &mut self,
+        segments: &LineSegments2D<T>,
+        intersections: &HashSet<Intersection<T>>,
+    ) -> LineSegments2D {
+        self.hot_pixels.clear();
+        for line in &segments.lines {
+            self.add_hot_pixel(&line.start);
+            self.add_hot_pixel(&line.end);
+        }
+        for intersection in intersections {
+            match intersection {
+                Intersection::Crossing { point, .. } | Intersection::Touching { point, .. } => {
+                    self.add_hot_pixel(point)
+                }
+                // the endpoints of overlaps are endpoints of the segments
+                _ => {}
+            }
+        }
+
+        let mut edges: HashSet<(Pixel, Pixel)> = HashSet::new();
+        let mut lines = Vec::new();
+        for line in &segments.lines {
+            let pixels = self.pixels_along(line);
+            for pair in pixels.windows(2) {
+                let edge = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+                if edges.insert(edge) {
+                    let (start, end) = (self.center(edge.0), self.center(edge.1));
+                    lines.push(Line2D { start, end });
+                }
+            }
+        }
+        LineSegments2D { lines }
+    }
+
+    fn add_hot_pixel<S: Scalar>(&mut self, point: &Point2D<S>) {
+        let (column, row) = self.pixel_of(point.x.to_f64(), point.y.to_f64());
+        self.hot_pixels.entry(column).or_default().insert(row);
+    }
+
+    fn pixel_of(&self, x: f64, y: f64) -> Pixel {
+        (
+            (x / self.grid).round() as i64,
+            (y / self.grid).round() as i64,
+        )
+    }
+
+    fn center(&self, pixel: Pixel) -> Point2D {
+        Point2D::new(pixel.0 as f64 * self.grid, pixel.1 as f64 * self.grid)
+    }
+
+    /// Returns the hot pixels the line passes in the order from start to end.
+    fn pixels_along<T: Scalar>(&self, line: &Line2D<T>) -> Vec<Pixel> {
+        let start = Point2D::new(line.start.x.to_f64(), line.start.y.to_f64());
+        let end = Point2D::new(line.end.x.to_f64(), line.end.y.to_f64());
+        let (first_column, _) = self.pixel_of(start.x.0.min(end.x.0), 0.0);
+        let (last_column, _) = self.pixel_of(start.x.0.max(end.x.0), 0.0);
+
+        let mut pixels = Vec::new();
+        // neighboring columns and rows are included, as pixels are closed and rounding might be off
+        for (&column, rows) in self.hot_pixels.range(first_column - 1..=last_column + 1) {
+            let (low, high) = Self::y_range(&start, &end, self.column_bounds(column));
+            let (_, first_row) = self.pixel_of(0.0, low);
+            let (_, last_row) = self.pixel_of(0.0, high);
+            for &row in rows.range(first_row - 1..=last_row + 1) {
+                if self.intersects_pixel(&start, &end, (column, row)) {
+                    pixels.push((column, row));
+                }
+            }
+        }
+
+        let direction = (end.x.0 - start.x.0, end.y.0 - start.y.0);
+        let parameter = |pixel: &Pixel| {
+            let center = self.center(*pixel);
+            (center.x.0 - start.x.0) * direction.0 + (center.y.0 - start.y.0) * direction.1
+        };
+        pixels.sort_by(|a, b| parameter(a).total_cmp(&parameter(b)).then(a.cmp(b)));
+        pixels
+    }
+
+    fn column_bounds(&self, column: i64) -> (f64, f64) {
+        (
+            (column as f64 - 0.5) * self.grid,
+            (column as f64 + 0.5) * self.grid,
+        )
+    }
+
+    /// Range of the y-coordinates of the segment within the x-range.
+    fn y_range(start: &Point2D, end: &Point2D, (left, right): (f64, f64)) -> (f64, f64) {
+        if start.x == end.x {
+            return (start.y.0.min(end.y.0), start.y.0.max(end.y.0));
+        }
+        let y_at = |x: f64| {
+            let x = x.clamp(start.x.0.min(end.x.0), start.x.0.max(end.x.0));
+            start.y.0 + (x - start.x.0) * (end.y.0 - start.y.0) / (end.x.0 - start.x.0)
+        };
+        let (y1, y2) = (y_at(left), y_at(right));
+        (y1.min(y2), y1.max(y2))
+    }
+
+    /// Checks exactly whether the segment intersects the closed pixel.
+    fn intersects_pixel(&self, start: &Point2D, end: &Point2D, pixel: Pixel) -> bool {
+        let (left, right) = self.column_bounds(pixel.0);
+        let (bottom, top) = (
+            (pixel.1 as f64 - 0.5) * self.grid,
+            (pixel.1 as f64 + 0.5) * self.grid,
+        );
+
+        if start.x.0.max(end.x.0) < left
+            || start.x.0.min(end.x.0) > right
+            || start.y.0.max(end.y.0) < bottom
+            || start.y.0.min(end.y.0) > top
+        {
+            return false;
+        }
+        if start == end {
+            return true;
+        }
+
+        // the line through the segment separates the corners unless all of them lie on the same side
+        let orientations: Vec<Orientation> = [
+            Point2D::new(left, bottom),
+            Point2D::new(right, bottom),
+            Point2D::new(right, top),
+            Point2D::new(left, top),
+        ]
+        .iter()
+        .map(|corner| predicates::orientation(start, end, corner))
+        .collect();
+        !orientations.iter().all(|o| *o == Orientation::Clockwise)
+            && !orientations
+                .iter()
+                .all(|o| *o == Orientation::CounterClockwise)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use std::path::Path;
+
+    fn round(lines: Vec<Line2D>, grid: f64) -> Vec<Line2D> {
+        let segments = LineSegments2D { lines };
+        let intersections = BruteForceHandler::new(segments.lines.clone()).run();
+        SnapRounding::new(grid)
+            .round(&segments, &intersections)
+            .lines
+    }
+
+    /// Rounded segments may only meet at common endpoints.
+    fn assert_consistent(lines: &[Line2D]) {
+        let is_endpoint =
+            |line: &Line2D, point: &Point2D| line.start == *point || line.end == *point;
+        for intersection in BruteForceHandler::new(lines.to_vec()).run() {
+            match intersection {
+                Intersection::Touching {
+                    line1,
+                    line2,
+                    point,
+                } => assert!(
+                    is_endpoint(&line1, &point) && is_endpoint(&line2, &point),
+                    "{}",
+                    intersection
+                ),
+                Intersection::PartialOverlap { overlap, .. } => {
+                    assert!(overlap.is_zero_length(), "{}", intersection)
+                }
+                _ => panic!("Rounded segments intersect: {}", intersection),
+            }
+        }
+    }
+
+    #[test]
+    fn crossing_segments_should_be_split_at_the_rounded_intersection() {
+        let line1 = Line2D::new(0.0, 0.0, 4.0, 3.0);
+        let line2 = Line2D::new(0.0, 3.0, 4.0, 0.0);
+
+        let lines = round(vec![line1, line2], 1.0);
+
+        // crossing at (2, 1.5) is rounded to (2, 2)
+        assert_eq!(lines.len(), 4);
+        assert!(lines.contains(&Line2D::new(0.0, 0.0, 2.0, 2.0)));
+        assert!(lines.contains(&Line2D::new(2.0, 2.0, 4.0, 3.0)));
+        assert!(lines.contains(&Line2D::new(0.0, 3.0, 2.0, 2.0)));
+        assert!(lines.contains(&Line2D::new(2.0, 2.0, 4.0, 0.0)));
+        assert_consistent(&lines);
+    }
+
+    #[test]
+    fn segments_should_be_routed_through_hot_pixels_they_pass() {
+        // the horizontal segment passes the pixel of the endpoint (2.3, 0.4), which is rounded to (2, 0)
+        let line1 = Line2D::new(0.0, 0.1, 4.0, 0.1);
+        let line2 = Line2D::new(2.3, 0.4, 2.6, 3.0);
+
+        let lines = round(vec![line1, line2], 1.0);
+
+        assert!(lines.contains(&Line2D::new(0.0, 0.0, 2.0, 0.0)));
+        assert!(lines.contains(&Line2D::new(2.0, 0.0, 4.0, 0.0)));
+        assert!(lines.contains(&Line2D::new(2.0, 0.0, 3.0, 3.0)));
+        assert_eq!(lines.len(), 3);
+        assert_consistent(&lines);
+    }
+
+    #[test]
+    fn segments_within_one_pixel_should_be_dropped() {
+        let lines = round(vec![Line2D::new(0.1, 0.1, 0.3, 0.2)], 1.0);
+
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn rounded_data_file_should_be_consistent() {
+        let segments = LineSegments2D::from_dat(Path::new("data/s_1000_10.dat")).unwrap();
+
+        let lines = round(segments.lines, 0.5);
+
+        assert!(!lines.is_empty());
+        assert_consistent(&lines);
+    }
+}
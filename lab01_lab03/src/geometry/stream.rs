@@ -0,0 +1,524 @@
+//! Out-of-core intersection of segment files which don't fit into memory.
+//!
+//! The pipeline never holds all lines at once:
+//! 1. [dat::DatReader] reads the lines lazily, row by row.
+//! 2. [sort_lines] orders them by their left endpoint. Up to [StreamOptions::memory_budget] lines are
+//!    sorted in memory, larger inputs are written as sorted runs to temporary files, which are merged
+//!    while the sweep reads them.
+//! 3. [intersect_sorted] sweeps with degenerate handling and only adds a line to the event queue when
+//!    the sweep reaches its start, so the queue and the sweep line hold the active lines only.
+//! 4. Every intersection is passed to an [IntersectionSink] as soon as it is found, e.g. an
+//!    [IntersectionWriter] writing `.csv` or `.wkt`.
+//!
+//! A pair of lines can be found at several event points while both lines are active, the
+//! intersections of the active lines are remembered to report each of them once.
+//! The result equals the one of [Handler::run] with degenerate handling.
+use crate::geometry::dat::{self, DatOptions, ReadError};
+use crate::geometry::formats::IntersectionWriter;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, process, vec};
+
+/// Distinguishes the runs of concurrent sorts of the process
+static SORT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Debug)]
+pub struct StreamOptions {
+    /// Maximum number of lines sorted in memory, larger inputs are sorted in runs on disk
+    pub memory_budget: usize,
+    /// Directory of the sorted runs, they are removed when the sorted lines are dropped
+    pub temp_dir: PathBuf,
+}
+
+impl Default for StreamOptions {
+    /// Sorts up to a million lines in memory, in the temporary directory of the system otherwise.
+    fn default() -> Self {
+        StreamOptions {
+            memory_budget: 1_000_000,
+            temp_dir: env::temp_dir(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamStatistics {
+    pub lines: usize,
+    pub intersections: usize,
+    /// Number of sorted runs written to disk, 0 if the lines were sorted in memory
+    pub runs: usize,
+    /// Largest number of pending event points, a measure of the memory used by the sweep
+    pub peak_event_points: usize,
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Read(ReadError),
+    /// The sink failed to take an intersection
+    Write(io::Error),
+    /// The line at the position of the input starts left of the line before it
+    Unsorted {
+        index: usize,
+    },
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Read(error) => error.fmt(f),
+            StreamError::Write(error) => write!(f, "Error writing intersections: {}", error),
+            StreamError::Unsorted { index } => {
+                write!(f, "Line {} starts left of the line before it", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Read(error) => Some(error),
+            StreamError::Write(error) => Some(error),
+            StreamError::Unsorted { .. } => None,
+        }
+    }
+}
+
+impl From<ReadError> for StreamError {
+    fn from(error: ReadError) -> Self {
+        StreamError::Read(error)
+    }
+}
+
+/// Receives the intersections one by one.
+pub trait IntersectionSink<T: Scalar> {
+    fn report(&mut self, intersection: Intersection<T>) -> io::Result<()>;
+}
+
+impl<T: Scalar> IntersectionSink<T> for HashSet<Intersection<T>> {
+    fn report(&mut self, intersection: Intersection<T>) -> io::Result<()> {
+        self.insert(intersection);
+        Ok(())
+    }
+}
+
+impl<T: Scalar, W: Write> IntersectionSink<T> for IntersectionWriter<W> {
+    fn report(&mut self, intersection: Intersection<T>) -> io::Result<()> {
+        self.write(&intersection)
+    }
+}
+
+/// Order of the sorted lines: left endpoint, right endpoint, the line itself so that identical lines
+/// are adjacent, and the position in the input.
+type SortKey<T> = (Point2D<T>, Point2D<T>, Line2D<T>, usize);
+
+fn sort_key<T: Scalar>(index: usize, line: Line2D<T>) -> SortKey<T> {
+    let (start, end) = line.sorted_endpoints();
+    (start.clone(), end.clone(), line, index)
+}
+
+/// Lines with their position in the input, ordered by their left endpoint, see [sort_lines].
+pub struct SortedLines<T: Scalar> {
+    source: Source<T>,
+    /// Files of the sorted runs, removed on drop
+    runs: Vec<PathBuf>,
+}
+
+enum Source<T: Scalar> {
+    Memory(vec::IntoIter<SortKey<T>>),
+    /// Smallest unread line of every run, with the index of the run
+    Merge {
+        readers: Vec<RunReader<T>>,
+        heads: BinaryHeap<Reverse<(SortKey<T>, usize)>>,
+    },
+}
+
+impl<T: Scalar> SortedLines<T> {
+    /// Number of sorted runs written to disk.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn next_line(&mut self) -> Result<Option<(usize, Line2D<T>)>, ReadError> {
+        let key = match &mut self.source {
+            Source::Memory(lines) => lines.next(),
+            Source::Merge { readers, heads } => match heads.pop() {
+                Some(Reverse((key, run))) => {
+                    if let Some(next) = readers[run].next()? {
+                        heads.push(Reverse((next, run)));
+                    }
+                    Some(key)
+                }
+                None => None,
+            },
+        };
+        Ok(key.map(|(_, _, line, index)| (index, line)))
+    }
+}
+
+impl<T: Scalar> Iterator for SortedLines<T> {
+    type Item = Result<(usize, Line2D<T>), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line().transpose()
+    }
+}
+
+impl<T: Scalar> Drop for SortedLines<T> {
+    fn drop(&mut self) {
+        for run in &self.runs {
+            let _ = fs::remove_file(run);
+        }
+    }
+}
+
+/// Reader of a sorted run, one `index x1 y1 x2 y2` row per line with the exact coordinates.
+struct RunReader<T> {
+    rows: io::Lines<BufReader<File>>,
+    path: PathBuf,
+    scalar: std::marker::PhantomData<T>,
+}
+
+impl<T: Scalar> RunReader<T> {
+    fn open(path: &Path) -> Result<Self, ReadError> {
+        let file = File::open(path).map_err(|source| io_error(path, source))?;
+        Ok(RunReader {
+            rows: BufReader::new(file).lines(),
+            path: path.to_path_buf(),
+            scalar: std::marker::PhantomData,
+        })
+    }
+
+    fn next(&mut self) -> Result<Option<SortKey<T>>, ReadError> {
+        let Some(row) = self.rows.next() else {
+            return Ok(None);
+        };
+        let row = row.map_err(|source| io_error(&self.path, source))?;
+        let invalid = || {
+            let source =
+                io::Error::new(io::ErrorKind::InvalidData, format!("corrupt row '{}'", row));
+            io_error(&self.path, source)
+        };
+        let mut tokens = row.split(' ');
+        let index = tokens
+            .next()
+            .and_then(|index| index.parse::<usize>().ok())
+            .ok_or_else(invalid)?;
+        let coordinates = tokens.map(T::parse).collect::<Option<Vec<T>>>();
+        let [x1, y1, x2, y2] = coordinates
+            .and_then(|coordinates| <[T; 4]>::try_from(coordinates).ok())
+            .ok_or_else(invalid)?;
+        let line = Line2D {
+            start: Point2D { x: x1, y: y1 },
+            end: Point2D { x: x2, y: y2 },
+        };
+        Ok(Some(sort_key(index, line)))
+    }
+}
+
+fn io_error(path: &Path, source: io::Error) -> ReadError {
+    ReadError::Io {
+        file: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Sorts the lines by their left endpoint, numbering them by their position in the input.
+/// Inputs larger than the memory budget are sorted in runs of the budget, which are written to the
+/// temporary directory and merged lazily.
+pub fn sort_lines<T: Scalar>(
+    lines: impl IntoIterator<Item = Result<Line2D<T>, ReadError>>,
+    options: &StreamOptions,
+) -> Result<SortedLines<T>, ReadError> {
+    let budget = options.memory_budget.max(1);
+    let id = SORT_ID.fetch_add(1, Ordering::Relaxed);
+    // created first, so that the runs are removed if reading fails
+    let mut sorted = SortedLines {
+        source: Source::Memory(Vec::new().into_iter()),
+        runs: Vec::new(),
+    };
+
+    let run_path = |run: usize| {
+        let name = format!("lab03_run_{}_{}_{}.txt", process::id(), id, run);
+        options.temp_dir.join(name)
+    };
+    let mut chunk: Vec<SortKey<T>> = Vec::new();
+    for (index, line) in lines.into_iter().enumerate() {
+        chunk.push(sort_key(index, line?));
+        if chunk.len() == budget {
+            sorted.runs.push(run_path(sorted.runs.len()));
+            write_run(&sorted.runs[sorted.runs.len() - 1], &mut chunk)?;
+        }
+    }
+
+    if sorted.runs.is_empty() {
+        chunk.sort();
+        sorted.source = Source::Memory(chunk.into_iter());
+        return Ok(sorted);
+    }
+    if !chunk.is_empty() {
+        sorted.runs.push(run_path(sorted.runs.len()));
+        write_run(&sorted.runs[sorted.runs.len() - 1], &mut chunk)?;
+    }
+
+    let mut readers = Vec::with_capacity(sorted.runs.len());
+    let mut heads = BinaryHeap::new();
+    for (run, path) in sorted.runs.iter().enumerate() {
+        let mut reader = RunReader::open(path)?;
+        if let Some(key) = reader.next()? {
+            heads.push(Reverse((key, run)));
+        }
+        readers.push(reader);
+    }
+    sorted.source = Source::Merge { readers, heads };
+    Ok(sorted)
+}
+
+/// Sorts the chunk and writes it to the file, leaving the chunk empty.
+fn write_run<T: Scalar>(path: &Path, chunk: &mut Vec<SortKey<T>>) -> Result<(), ReadError> {
+    chunk.sort();
+    let write = || {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for (_, _, line, index) in chunk.iter() {
+            writeln!(
+                writer,
+                "{} {} {} {} {}",
+                index, line.start.x, line.start.y, line.end.x, line.end.y
+            )?;
+        }
+        writer.flush()
+    };
+    write().map_err(|source| io_error(path, source))?;
+    chunk.clear();
+    Ok(())
+}
+
+/// Intersections already passed to the sink, by the lines involved, forgotten when a line ends.
+struct Reported<T: Scalar> {
+    intersections: HashSet<Intersection<T>>,
+    by_line: HashMap<Line2D<T>, Vec<Intersection<T>>>,
+}
+
+impl<T: Scalar> Reported<T> {
+    /// Passes the intersections which weren't reported yet to the sink, returns their number.
+    fn report(
+        &mut self,
+        found: HashSet<Intersection<T>>,
+        sink: &mut impl IntersectionSink<T>,
+    ) -> io::Result<usize> {
+        let mut count = 0;
+        for intersection in found {
+            if self.intersections.contains(&intersection) {
+                continue;
+            }
+            let (line1, line2) = intersection.lines();
+            for line in [line1, line2] {
+                self.by_line
+                    .entry(line.clone())
+                    .or_default()
+                    .push(intersection.clone());
+            }
+            self.intersections.insert(intersection.clone());
+            sink.report(intersection)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Forgets the intersections of the lines, which can't be found again.
+    fn forget(&mut self, lines: &[Line2D<T>]) {
+        for line in lines {
+            for intersection in self.by_line.remove(line).unwrap_or_default() {
+                self.intersections.remove(&intersection);
+            }
+        }
+    }
+}
+
+/// Sweeps the lines, which have to be ordered by their left endpoint with identical lines next to
+/// each other like the output of [sort_lines], and passes every intersection to the sink once.
+pub fn intersect_sorted<T: Scalar>(
+    lines: impl IntoIterator<Item = Result<(usize, Line2D<T>), ReadError>>,
+    sink: &mut impl IntersectionSink<T>,
+) -> Result<StreamStatistics, StreamError> {
+    let mut lines = lines.into_iter();
+    let mut handler = Handler::new(Vec::new(), SweepLineOptions::degenerate_handling());
+    let mut reported = Reported {
+        intersections: HashSet::new(),
+        by_line: HashMap::new(),
+    };
+    let mut statistics = StreamStatistics::default();
+    let mut last_start: Option<Point2D<T>> = None;
+
+    let mut next = lines.next().transpose()?;
+    loop {
+        // all lines starting up to the next event point have to be in the queue
+        while let Some((first, line)) = next.take() {
+            let start = line.sorted_endpoints().0.clone();
+            if handler
+                .next_point()
+                .is_some_and(|point| *point < start.to_field())
+            {
+                next = Some((first, line));
+                break;
+            }
+            if last_start.as_ref().is_some_and(|last| start < *last) {
+                return Err(StreamError::Unsorted { index: first });
+            }
+            last_start = Some(start);
+
+            let mut last = first;
+            statistics.lines += 1;
+            next = lines.next().transpose()?;
+            while let Some((index, other)) = &next {
+                if *other != line {
+                    break;
+                }
+                last = *index;
+                statistics.lines += 1;
+                next = lines.next().transpose()?;
+            }
+            handler.insert_line(line, first, last);
+        }
+
+        statistics.peak_event_points = statistics.peak_event_points.max(handler.pending_points());
+        let ended = handler.step();
+        statistics.intersections += reported
+            .report(handler.take_intersections(), sink)
+            .map_err(StreamError::Write)?;
+        match ended {
+            Some(ended) => reported.forget(&ended),
+            None => return Ok(statistics),
+        }
+    }
+}
+
+/// Reads, sorts and sweeps the lines of the `.dat` file, see the [module documentation](self).
+pub fn intersect_file<T: Scalar>(
+    path: &Path,
+    dat: &DatOptions,
+    options: &StreamOptions,
+    sink: &mut impl IntersectionSink<T>,
+) -> Result<StreamStatistics, StreamError> {
+    let sorted = sort_lines(dat::open::<T>(path, dat)?, options)?;
+    let runs = sorted.runs();
+    let statistics = intersect_sorted(sorted, sink)?;
+    Ok(StreamStatistics { runs, ..statistics })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::formats::Format;
+    use crate::geometry::generator;
+    use crate::geometry::line_segments::LineSegments2D;
+    use ordered_float::OrderedFloat;
+
+    fn degenerate_lines() -> Vec<Line2D> {
+        let mut lines = generator::degenerate_lines(1000, 11);
+        // identical lines far apart in the input
+        lines.push(lines[3]);
+        lines.push(lines[500]);
+        lines
+    }
+
+    #[test]
+    fn sorted_runs_should_merge_into_the_sorted_lines() {
+        let lines = degenerate_lines();
+        let small = StreamOptions {
+            memory_budget: 97,
+            ..StreamOptions::default()
+        };
+
+        let in_memory: Vec<(usize, Line2D)> =
+            sort_lines(lines.iter().copied().map(Ok), &StreamOptions::default())
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        let sorted = sort_lines(lines.iter().copied().map(Ok), &small).unwrap();
+        let runs = sorted.runs.clone();
+        let merged: Vec<(usize, Line2D)> = sorted.collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(runs.len(), lines.len().div_ceil(97));
+        assert_eq!(merged, in_memory);
+        assert_eq!(merged.len(), lines.len());
+        assert!(merged
+            .windows(2)
+            .all(|pair| { pair[0].1.sorted_endpoints().0 <= pair[1].1.sorted_endpoints().0 }));
+        // the runs are removed when the sorted lines are dropped
+        assert!(runs.iter().all(|run| !run.exists()));
+    }
+
+    #[test]
+    fn streaming_sweep_should_equal_the_brute_force() {
+        let lines = degenerate_lines();
+        let expected = BruteForceHandler::new(lines.clone()).run();
+
+        for memory_budget in [50, 1_000_000] {
+            let options = StreamOptions {
+                memory_budget,
+                ..StreamOptions::default()
+            };
+            let sorted = sort_lines(lines.iter().copied().map(Ok), &options).unwrap();
+            let mut sink = HashSet::new();
+
+            let statistics = intersect_sorted(sorted, &mut sink).unwrap();
+
+            assert_eq!(sink, expected);
+            assert_eq!(statistics.intersections, expected.len());
+            assert_eq!(statistics.lines, lines.len());
+            assert!(statistics.peak_event_points < 2 * lines.len());
+        }
+    }
+
+    #[test]
+    fn intersections_should_be_written_once_to_the_file() {
+        let path = env::temp_dir().join(format!("lab03_stream_{}.dat", process::id()));
+        let lines =
+            LineSegments2D::<OrderedFloat<f64>>::from_dat(Path::new("data/s_1000_10.dat")).unwrap();
+        lines.to_dat(&path).unwrap();
+        let options = StreamOptions {
+            memory_budget: 300,
+            ..StreamOptions::default()
+        };
+        let mut writer = IntersectionWriter::new(Vec::new(), Format::Csv).unwrap();
+
+        let statistics = intersect_file::<OrderedFloat<f64>>(
+            &path,
+            &DatOptions::default(),
+            &options,
+            &mut writer,
+        )
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(statistics.runs, 4);
+        assert_eq!(
+            statistics.intersections,
+            BruteForceHandler::new(lines.lines).run().len()
+        );
+        assert_eq!(output.lines().count(), statistics.intersections + 1);
+        let unsorted = [
+            Line2D::new(1.0, 0.0, 2.0, 0.0),
+            Line2D::new(0.0, 0.0, 1.0, 1.0),
+        ];
+        assert!(matches!(
+            intersect_sorted(
+                unsorted.into_iter().enumerate().map(Ok),
+                &mut HashSet::new()
+            ),
+            Err(StreamError::Unsorted { index: 1 })
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+//! SVG rendering of line segments and their intersections for debugging.
+//!
+//! Segments are drawn in grey, intersections in the colour of their variant: crossing and touching
+//! points as circles, overlaps as thick lines. Lines which the sweep line without degenerate
+//! handling rejects can be highlighted. Every element has the `Display` output of its line or
+//! intersection as tooltip.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::line_segments::LineSegments2D;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::Handler;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+const SEGMENT_COLOUR: &str = "#888888";
+const REJECTED_COLOUR: &str = "#e377c2";
+/// Variants with their colour, in the order of the legend
+const VARIANT_COLOURS: [(&str, &str); 5] = [
+    ("Crossing", "#d62728"),
+    ("Touching", "#ff7f0e"),
+    ("PartialOverlap", "#2ca02c"),
+    ("ContainedOverlap", "#1f77b4"),
+    ("IdenticalOverlap", "#9467bd"),
+];
+
+/// Rectangle of the plane which is rendered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Window {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+}
+
+impl Window {
+    pub fn new(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Self {
+        assert!(
+            x_min < x_max && y_min < y_max,
+            "Window has to be non-empty: ({}, {}) - ({}, {})",
+            x_min,
+            y_min,
+            x_max,
+            y_max
+        );
+        Window {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    /// Bounding box of the lines with a margin of 2%, a unit square around a single point.
+    pub(crate) fn around<T: Scalar>(lines: &[Line2D<T>]) -> Self {
+        if lines.is_empty() {
+            return Window::new(0.0, 0.0, 1.0, 1.0);
+        }
+        let coordinates = |f: fn(&Line2D<T>) -> [f64; 2]| lines.iter().flat_map(f);
+        let xs = || coordinates(|line| [line.start.x.to_f64(), line.end.x.to_f64()]);
+        let ys = || coordinates(|line| [line.start.y.to_f64(), line.end.y.to_f64()]);
+        let (x_min, x_max) = (xs().fold(f64::MAX, f64::min), xs().fold(f64::MIN, f64::max));
+        let (y_min, y_max) = (ys().fold(f64::MAX, f64::min), ys().fold(f64::MIN, f64::max));
+        let margin = ((x_max - x_min).max(y_max - y_min) * 0.02).max(0.5);
+        Window::new(
+            x_min - margin,
+            y_min - margin,
+            x_max + margin,
+            y_max + margin,
+        )
+    }
+
+    fn contains(&self, [x, y]: [f64; 2]) -> bool {
+        (self.x_min..=self.x_max).contains(&x) && (self.y_min..=self.y_max).contains(&y)
+    }
+
+    /// Whether the bounding box of the line overlaps the window.
+    fn touches<T: Scalar>(&self, line: &Line2D<T>) -> bool {
+        let (x1, x2) = (line.start.x.to_f64(), line.end.x.to_f64());
+        let (y1, y2) = (line.start.y.to_f64(), line.end.y.to_f64());
+        x1.max(x2) >= self.x_min
+            && x1.min(x2) <= self.x_max
+            && y1.max(y2) >= self.y_min
+            && y1.min(y2) <= self.y_max
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    /// Parses `x_min,y_min,x_max,y_max`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<f64> = s
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|error| format!("Invalid window '{}': {}", s, error))?;
+        match values.as_slice() {
+            &[x_min, y_min, x_max, y_max] if x_min < x_max && y_min < y_max => {
+                Ok(Window::new(x_min, y_min, x_max, y_max))
+            }
+            _ => Err(format!(
+                "Invalid window '{}', expected x_min,y_min,x_max,y_max",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    /// Width of the image in pixels, the height follows from the aspect ratio of the window
+    pub width: u32,
+    /// Only this part of the plane is rendered, the bounding box of all lines if None
+    pub window: Option<Window>,
+    /// Highlights the lines rejected by the sweep line without degenerate handling
+    pub highlight_rejected: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: 1000,
+            window: None,
+            highlight_rejected: false,
+        }
+    }
+}
+
+/// Maps coordinates of the window onto the pixels of the image, with the y-axis pointing up.
+pub(crate) struct Viewport {
+    window: Window,
+    scale: f64,
+    width: u32,
+}
+
+impl Viewport {
+    pub(crate) fn new(window: Window, width: u32) -> Self {
+        Viewport {
+            window,
+            scale: width as f64 / (window.x_max - window.x_min),
+            width,
+        }
+    }
+
+    /// Opening tag of the document with a white background.
+    pub(crate) fn header(&self) -> String {
+        let height = ((self.window.y_max - self.window.y_min) * self.scale).ceil();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            self.width, height
+        )
+    }
+
+    pub(crate) fn pixel(&self, [x, y]: [f64; 2]) -> (f64, f64) {
+        (
+            (x - self.window.x_min) * self.scale,
+            (self.window.y_max - y) * self.scale,
+        )
+    }
+
+    pub(crate) fn line(
+        &self,
+        positions: &[[f64; 2]],
+        colour: &str,
+        width: f64,
+        extra: &str,
+        title: &str,
+    ) -> String {
+        let (x1, y1) = self.pixel(positions[0]);
+        let (x2, y2) = self.pixel(positions[positions.len() - 1]);
+        format!(
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{}\"{}><title>{}</title></line>\n",
+            x1, y1, x2, y2, colour, width, extra, escape(title)
+        )
+    }
+
+    pub(crate) fn circle(&self, position: [f64; 2], colour: &str, title: &str) -> String {
+        let (x, y) = self.pixel(position);
+        format!(
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"4\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"><title>{}</title></circle>\n",
+            x, y, colour, escape(title)
+        )
+    }
+}
+
+/// Renders the segments and the intersections as SVG document.
+pub fn render<T: Scalar>(
+    segments: &LineSegments2D<T>,
+    intersections: &HashSet<Intersection<T>>,
+    options: &RenderOptions,
+) -> String {
+    let window = options
+        .window
+        .unwrap_or_else(|| Window::around(&segments.lines));
+    let viewport = Viewport::new(window, options.width);
+
+    let mut svg = viewport.header();
+
+    svg.push_str("<g id=\"segments\">\n");
+    for line in segments.lines.iter().filter(|line| window.touches(*line)) {
+        let positions = [line.start.to_f64(), line.end.to_f64()];
+        if line.is_zero_length() {
+            svg.push_str(&viewport.circle(positions[0], SEGMENT_COLOUR, &line.to_string()));
+        } else {
+            svg.push_str(&viewport.line(&positions, SEGMENT_COLOUR, 1.0, "", &line.to_string()));
+        }
+    }
+    svg.push_str("</g>\n");
+
+    let mut rejected_count = 0;
+    if options.highlight_rejected {
+        svg.push_str("<g id=\"rejected\">\n");
+        for (line, rejection) in Handler::rejected_lines(&segments.lines) {
+            rejected_count += 1;
+            if !window.touches(&line) {
+                continue;
+            }
+            let title = format!("{}: {}", rejection, line);
+            let positions = [line.start.to_f64(), line.end.to_f64()];
+            if line.is_zero_length() {
+                svg.push_str(&viewport.circle(positions[0], REJECTED_COLOUR, &title));
+            } else {
+                let dashed = " stroke-dasharray=\"6 3\"";
+                svg.push_str(&viewport.line(&positions, REJECTED_COLOUR, 3.0, dashed, &title));
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+
+    // sorted for a deterministic output
+    let mut sorted: Vec<(String, &Intersection<T>)> = intersections
+        .iter()
+        .map(|intersection| (intersection.to_string(), intersection))
+        .collect();
+    sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    svg.push_str("<g id=\"intersections\">\n");
+    for (title, intersection) in sorted {
+        *counts.entry(intersection.variant()).or_default() += 1;
+        let colour = colour(intersection.variant());
+        match intersection {
+            Intersection::Crossing { point, .. } | Intersection::Touching { point, .. } => {
+                let position = point.to_f64();
+                if window.contains(position) {
+                    svg.push_str(&viewport.circle(position, colour, &title));
+                }
+            }
+            Intersection::PartialOverlap { overlap, .. }
+            | Intersection::ContainedOverlap { overlap, .. }
+            | Intersection::IdenticalOverlap { overlap, .. } => {
+                if !window.touches(overlap) {
+                    continue;
+                }
+                let positions = [overlap.start.to_f64(), overlap.end.to_f64()];
+                if overlap.is_zero_length() {
+                    svg.push_str(&viewport.circle(positions[0], colour, &title));
+                } else {
+                    svg.push_str(&viewport.line(
+                        &positions,
+                        colour,
+                        4.0,
+                        " stroke-opacity=\"0.7\"",
+                        &title,
+                    ));
+                }
+            }
+        }
+    }
+    svg.push_str("</g>\n");
+
+    svg.push_str(&legend(
+        &counts,
+        options.highlight_rejected.then_some(rejected_count),
+    ));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Number of intersections of each variant and of rejected lines, in the top left corner.
+fn legend(counts: &BTreeMap<&str, usize>, rejected: Option<usize>) -> String {
+    let mut entries: Vec<(String, &str)> = VARIANT_COLOURS
+        .iter()
+        .map(|(variant, colour)| {
+            let count = counts.get(variant).copied().unwrap_or(0);
+            (format!("{}: {}", variant, count), *colour)
+        })
+        .collect();
+    if let Some(rejected) = rejected {
+        entries.push((format!("Rejected lines: {}", rejected), REJECTED_COLOUR));
+    }
+
+    let mut legend =
+        String::from("<g id=\"legend\" font-family=\"sans-serif\" font-size=\"12\">\n");
+    for (i, (text, colour)) in entries.iter().enumerate() {
+        let y = 16 + i * 16;
+        writeln!(
+            legend,
+            "<text x=\"8\" y=\"{}\" fill=\"{}\">{}</text>",
+            y, colour, text
+        )
+        .unwrap();
+    }
+    legend.push_str("</g>\n");
+    legend
+}
+
+fn colour(variant: &str) -> &'static str {
+    VARIANT_COLOURS
+        .iter()
+        .find(|(name, _)| *name == variant)
+        .map_or(SEGMENT_COLOUR, |(_, colour)| colour)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+
+    fn render_lines(lines: Vec<Line2D>, options: &RenderOptions) -> String {
+        let intersections = BruteForceHandler::new(lines.clone()).run();
+        render(&LineSegments2D { lines }, &intersections, options)
+    }
+
+    #[test]
+    fn intersections_should_be_coloured_by_variant() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+            Line2D::new(1.0, 1.0, 3.0, 3.0),
+        ];
+
+        let svg = render_lines(lines, &RenderOptions::default());
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<line").count(), 3 + 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains("stroke=\"#d62728\""));
+        assert!(svg.contains("stroke=\"#ff7f0e\""));
+        assert!(svg.contains("stroke=\"#2ca02c\""));
+        assert!(svg.contains(">Crossing: 1</text>"));
+    }
+
+    #[test]
+    fn window_should_drop_lines_outside() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 1.0, 1.0),
+            Line2D::new(0.0, 1.0, 1.0, 0.0),
+            Line2D::new(10.0, 10.0, 11.0, 11.0),
+        ];
+        let options = RenderOptions {
+            window: "-1,-1,2,2".parse().ok(),
+            ..RenderOptions::default()
+        };
+
+        let svg = render_lines(lines, &options);
+
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!("1,2,0,3".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn rejected_lines_should_be_highlighted() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(1.0, 0.0, 1.0, 2.0),
+            Line2D::new(3.0, 3.0, 3.0, 3.0),
+        ];
+        let options = RenderOptions {
+            highlight_rejected: true,
+            ..RenderOptions::default()
+        };
+
+        let svg = render_lines(lines, &options);
+
+        assert_eq!(
+            svg.matches(&format!("stroke=\"{}\"", REJECTED_COLOUR))
+                .count(),
+            2
+        );
+        assert!(svg.contains("<title>Vertical line: "));
+        assert!(svg.contains(">Rejected lines: 2</text>"));
+    }
+}
@@ -1,16 +1,19 @@
 use crate::geometry::line::Line2D;
-use crate::geometry::sweep_line::events::Event;
-use std::collections::BTreeSet;
-
-pub struct EventQueue {
-    events: BTreeSet<Event>,
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::events::{Event, EventPoint};
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct EventQueue<T: Scalar = OrderedFloat<f64>> {
+    events: BTreeSet<Event<T>>,
 }
 
-impl EventQueue {
-    pub fn new(lines: Vec<Line2D>) -> Self {
+impl<T: Scalar> EventQueue<T> {
+    pub fn new(lines: Vec<Line2D<T>>) -> Self {
         let mut events = BTreeSet::new();
         for line in lines {
-            let start = Event::StartEvent { line };
+            let start = Event::StartEvent { line: line.clone() };
             let end = Event::EndEvent { line };
             events.insert(start);
             events.insert(end);
@@ -18,15 +21,15 @@ impl EventQueue {
         Self { events }
     }
 
-    pub fn add(&mut self, event: Event) {
+    pub fn add(&mut self, event: Event<T>) {
         self.events.insert(event);
     }
 
-    pub fn pop(&mut self) -> Option<Event> {
+    pub fn pop(&mut self) -> Option<Event<T>> {
         self.events.pop_first()
     }
 
-    pub fn contains(&self, event: &Event) -> bool {
+    pub fn contains(&self, event: &Event<T>) -> bool {
         self.events.contains(event)
     }
 
@@ -34,15 +37,62 @@ impl EventQueue {
         self.events.is_empty()
     }
 
+    #[cfg(test)]
     pub fn len(&self) -> usize {
         self.events.len()
     }
+}
+
+/// Event queue which groups all events by their point.
+/// Lines are inserted with their endpoints ordered by x and then y, so vertical lines start at the bottom.
+pub struct EventPointQueue<T: Scalar = OrderedFloat<f64>> {
+    points: BTreeMap<Point2D<T::Field>, EventPoint<T>>,
+}
+
+impl<T: Scalar> EventPointQueue<T> {
+    pub fn new(lines: Vec<Line2D<T>>) -> Self {
+        let mut queue = Self {
+            points: BTreeMap::new(),
+        };
+        for line in lines {
+            queue.add_line(line);
+        }
+        queue
+    }
+
+    /// Adds the start and end event of the line.
+    pub fn add_line(&mut self, line: Line2D<T>) {
+        let (start, end) = line.sorted_endpoints();
+        let (start, end) = (start.to_field(), end.to_field());
+        self.points
+            .entry(start)
+            .or_default()
+            .starts
+            .push(line.clone());
+        self.points.entry(end).or_default().ends.push(line);
+    }
 
-    pub fn print_queue(&self) {
-        for event in &self.events {
-            println!("{}", event);
+    pub fn add_crossing(&mut self, point: Point2D<T::Field>, line1: Line2D<T>, line2: Line2D<T>) {
+        let crossings = &mut self.points.entry(point).or_default().crossings;
+        for line in [line1, line2] {
+            if !crossings.contains(&line) {
+                crossings.push(line);
+            }
         }
     }
+
+    pub fn pop(&mut self) -> Option<(Point2D<T::Field>, EventPoint<T>)> {
+        self.points.pop_first()
+    }
+
+    /// Point of the next event, None if the queue is empty.
+    pub fn first_point(&self) -> Option<&Point2D<T::Field>> {
+        self.points.keys().next()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
 }
 
 #[cfg(test)]
@@ -165,9 +215,36 @@ mod tests {
 
     #[test]
     fn test_event_queue_is_empty() {
-        let lines = vec![];
+        let lines: Vec<Line2D> = vec![];
         let event_queue = EventQueue::new(lines);
 
         assert!(event_queue.is_empty());
     }
+
+    #[test]
+    fn test_event_point_queue_should_group_events_by_point() {
+        let line1 = Line2D::new(0.0, 0.0, 1.0, 1.0);
+        let line2 = Line2D::new(1.0, 1.0, 2.0, 0.0);
+        let line3 = Line2D::new(1.0, 3.0, 1.0, 1.0);
+        let mut queue = EventPointQueue::new(vec![line1, line2, line3]);
+        queue.add_crossing(Point2D::new(0.5, 0.5), line1, line2);
+
+        assert_eq!(queue.len(), 5);
+
+        let (point, _) = queue.pop().unwrap();
+        assert_eq!(point, Point2D::new(0.0, 0.0));
+        let (point, event_point) = queue.pop().unwrap();
+        assert_eq!(point, Point2D::new(0.5, 0.5));
+        assert_eq!(event_point.crossings, vec![line1, line2]);
+
+        let (point, event_point) = queue.pop().unwrap();
+        assert_eq!(point, Point2D::new(1.0, 1.0));
+        assert_eq!(event_point.starts, vec![line2, line3]);
+        assert_eq!(event_point.ends, vec![line1]);
+
+        // the vertical line ends at the top
+        let (point, event_point) = queue.pop().unwrap();
+        assert_eq!(point, Point2D::new(1.0, 3.0));
+        assert_eq!(event_point.ends, vec![line3]);
+    }
 }
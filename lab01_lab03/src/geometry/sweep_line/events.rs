@@ -1,26 +1,31 @@
 use crate::geometry::intersection::Intersection;
 use crate::geometry::line::Line2D;
 use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use ordered_float::OrderedFloat;
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Copy, Clone)]
-pub enum Event {
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone)]
+pub enum Event<T: Scalar = OrderedFloat<f64>> {
     StartEvent {
-        line: Line2D,
+        line: Line2D<T>,
     },
     EndEvent {
-        line: Line2D,
+        line: Line2D<T>,
     },
     IntersectionEvent {
-        bigger: Line2D,
-        smaller: Line2D,
-        intersection: Intersection,
+        bigger: Line2D<T>,
+        smaller: Line2D<T>,
+        intersection: Intersection<T>,
     },
 }
 
-impl PartialEq for Event {
+impl<T: Scalar + Copy> Copy for Event<T> where T::Field: Copy {}
+
+impl<T: Scalar> PartialEq for Event<T> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Event::StartEvent { line: line1 }, Event::StartEvent { line: line2 }) => {
@@ -40,15 +45,15 @@ impl PartialEq for Event {
     }
 }
 
-impl Eq for Event {}
+impl<T: Scalar> Eq for Event<T> {}
 
-impl PartialOrd for Event {
+impl<T: Scalar> PartialOrd for Event<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Event {
+impl<T: Scalar> Ord for Event<T> {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
             (Event::StartEvent { line: line1 }, Event::StartEvent { line: line2 }) => {
@@ -62,7 +67,7 @@ impl Ord for Event {
                 Event::IntersectionEvent {
                     intersection: i1, ..
                 },
-            ) => start.start.cmp(get_point_of_intersection(i1)),
+            ) => start.start.to_field().cmp(&get_point_of_intersection(i1)),
             (Event::EndEvent { line: line1 }, Event::EndEvent { line: line2 }) => {
                 line1.end.cmp(&line2.end)
             }
@@ -74,7 +79,7 @@ impl Ord for Event {
                 Event::IntersectionEvent {
                     intersection: i1, ..
                 },
-            ) => end.end.cmp(get_point_of_intersection(i1)),
+            ) => end.end.to_field().cmp(&get_point_of_intersection(i1)),
             (
                 Event::IntersectionEvent {
                     intersection: i1, ..
@@ -82,24 +87,24 @@ impl Ord for Event {
                 Event::IntersectionEvent {
                     intersection: i2, ..
                 },
-            ) => get_point_of_intersection(i1).cmp(get_point_of_intersection(i2)),
+            ) => get_point_of_intersection(i1).cmp(&get_point_of_intersection(i2)),
             (
                 Event::IntersectionEvent {
                     intersection: i1, ..
                 },
                 Event::StartEvent { line: start },
-            ) => get_point_of_intersection(i1).cmp(&start.start),
+            ) => get_point_of_intersection(i1).cmp(&start.start.to_field()),
             (
                 Event::IntersectionEvent {
                     intersection: i1, ..
                 },
                 Event::EndEvent { line: end },
-            ) => get_point_of_intersection(i1).cmp(&end.end),
+            ) => get_point_of_intersection(i1).cmp(&end.end.to_field()),
         }
     }
 }
 
-impl Hash for Event {
+impl<T: Scalar> Hash for Event<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             Event::StartEvent { line } => {
@@ -117,37 +122,59 @@ impl Hash for Event {
     }
 }
 
-fn get_point_of_intersection(intersection: &Intersection) -> &Point2D {
+/// All lines that start, end or cross at a single event point.
+/// Used when degenerate cases are handled, as multiple lines may meet at the same point.
+#[derive(Debug, Clone)]
+pub struct EventPoint<T = OrderedFloat<f64>> {
+    pub starts: Vec<Line2D<T>>,
+    pub ends: Vec<Line2D<T>>,
+    pub crossings: Vec<Line2D<T>>,
+}
+
+impl<T> Default for EventPoint<T> {
+    fn default() -> Self {
+        EventPoint {
+            starts: Vec::new(),
+            ends: Vec::new(),
+            crossings: Vec::new(),
+        }
+    }
+}
+
+/// Point of a crossing or touching, the start of the overlap otherwise.
+pub(crate) fn get_point_of_intersection<T: Scalar>(
+    intersection: &Intersection<T>,
+) -> Point2D<T::Field> {
     match intersection {
         Intersection::Touching {
             line1: _line1,
             line2: _line2,
             point,
-        } => point,
+        } => point.clone(),
         Intersection::Crossing {
             line1: _line1,
             line2: _line2,
             point,
-        } => point,
+        } => point.clone(),
         Intersection::ContainedOverlap {
             line1: _line1,
             line2: _line2,
             overlap,
-        } => &overlap.start,
+        } => overlap.start.to_field(),
         Intersection::IdenticalOverlap {
             line1: _,
             line2: _,
             overlap,
-        } => &overlap.start,
+        } => overlap.start.to_field(),
         Intersection::PartialOverlap {
             line1: _line1,
             line2: _line2,
             overlap,
-        } => &overlap.start,
+        } => overlap.start.to_field(),
     }
 }
 
-impl Display for Event {
+impl<T: Scalar> Display for Event<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Event::StartEvent { line } => {
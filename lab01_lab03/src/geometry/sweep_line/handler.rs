@@ -1,10 +1,18 @@
+use crate::geometry::counting::IntersectionCounts;
 use crate::geometry::intersection::Intersection;
+use crate::geometry::layer::Layers;
 use crate::geometry::line::Line2D;
-use crate::geometry::sweep_line::event_queue::EventQueue;
-use crate::geometry::sweep_line::events::Event;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::event_queue::{EventPointQueue, EventQueue};
+use crate::geometry::sweep_line::events::{get_point_of_intersection, Event, EventPoint};
 use crate::geometry::sweep_line::sweep_line::SweepLine;
+use crate::geometry::sweep_line::trace::{EventRejection, StepKind, Trace};
 use ordered_float::OrderedFloat;
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::ops::Sub;
 
 /// Options for the sweep line algorithm
 /// panic_on_identical_x: if true, the algorithm will panic if two lines have the same end/intersection x coordinate, otherwise it will ignore the line
@@ -12,12 +20,15 @@ use std::collections::HashSet;
 /// panic_on_zero_length: if true, the algorithm will panic if a line has zero length, otherwise it will ignore the line
 /// panic_on_overlap: if true, the algorithm will panic if two collinear lines are overlapping, otherwise it will ignore the intersection
 /// panic_on_touch: if true, the algorithm will panic if two lines are only touching, but not crossing, otherwise it will ignore the intersection
+/// handle_degenerate: if true, all of the above cases are handled and reported like the brute force approach does, the panic options are ignored
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SweepLineOptions {
     pub panic_on_identical_x: bool,
     pub panic_on_vertical: bool,
     pub panic_on_zero_length: bool,
     pub panic_on_overlap: bool,
     pub panic_on_touch: bool,
+    pub handle_degenerate: bool,
     pub x_shift: f64,
 }
 
@@ -30,6 +41,7 @@ impl SweepLineOptions {
             panic_on_zero_length: true,
             panic_on_overlap: true,
             panic_on_touch: true,
+            handle_degenerate: false,
             x_shift: 1e-9,
         }
     }
@@ -43,44 +55,517 @@ impl SweepLineOptions {
             panic_on_zero_length: false,
             panic_on_overlap: true,
             panic_on_touch: true,
+            handle_degenerate: false,
             x_shift: 1e-9,
         }
     }
+
+    /// Handles vertical and zero-length lines, identical x coordinates, multiple lines meeting
+    /// in one point as well as touching and overlapping lines.
+    /// The result equals the result of the brute force approach.
+    pub fn degenerate_handling() -> Self {
+        SweepLineOptions {
+            panic_on_identical_x: false,
+            panic_on_vertical: false,
+            panic_on_zero_length: false,
+            panic_on_overlap: false,
+            panic_on_touch: false,
+            handle_degenerate: true,
+            x_shift: 0.0,
+        }
+    }
 }
 
-pub struct Handler {
-    queue: EventQueue,
-    sweep_line: SweepLine,
-    intersections: HashSet<Intersection>,
+pub struct Handler<T: Scalar = OrderedFloat<f64>> {
+    queue: EventQueue<T>,
+    event_points: EventPointQueue<T>,
+    sweep_line: SweepLine<T>,
+    intersections: HashSet<Intersection<T>>,
     options: SweepLineOptions,
-    x_coords: HashSet<OrderedFloat<f64>>,
+    /// x-coordinates of the endpoints
+    x_coords: HashSet<T::Field>,
+    /// y-coordinates of the intersection events by their x-coordinate, from the last handled one on
+    intersection_points: BTreeMap<T::Field, Vec<T::Field>>,
+    /// first and last position of each line in the input, only used when degenerate cases are handled
+    indices: HashMap<Line2D<T>, (usize, usize)>,
+    /// layers of the lines in the bichromatic mode
+    layers: Option<Layers<T>>,
+    /// number of input lines
+    line_count: usize,
+    /// processed events, only recorded if enabled
+    trace: Option<Trace>,
+    /// intersections are counted instead of collected, see [Handler::count]
+    counting: Option<Counting<T>>,
 }
 
-impl Handler {
-    pub fn new(mut lines: Vec<Line2D>, options: SweepLineOptions) -> Self {
-        let (lines, x_coords) = Self::sanity_checks(&mut lines, &options);
-        let handler = Handler {
-            queue: EventQueue::new(lines.clone()),
+impl<T: Scalar> Handler<T> {
+    pub fn new(lines: Vec<Line2D<T>>, options: SweepLineOptions) -> Self {
+        if options.handle_degenerate {
+            return Self::with_degenerate_handling(lines, options);
+        }
+
+        let line_count = lines.len();
+        let (lines, x_coords) = Self::sanity_checks(&lines, &options);
+        Handler {
+            queue: EventQueue::new(lines),
+            event_points: EventPointQueue::new(Vec::new()),
             sweep_line: SweepLine::new(),
             intersections: HashSet::new(),
             options,
             x_coords,
-        };
+            intersection_points: BTreeMap::new(),
+            indices: HashMap::new(),
+            layers: None,
+            line_count,
+            trace: None,
+            counting: None,
+        }
+    }
+
+    /// Creates a handler which only reports intersections of red with blue lines.
+    /// All lines are swept, as intersections within a layer change the order of the lines.
+    pub fn new_bichromatic(
+        red: Vec<Line2D<T>>,
+        blue: Vec<Line2D<T>>,
+        options: SweepLineOptions,
+    ) -> Self {
+        let layers = Layers::new(&red, &blue);
+        let mut lines = red;
+        lines.extend(blue);
+        let mut handler = Self::new(lines, options);
+        // identical lines are reported while creating the handler
+        handler
+            .intersections
+            .retain(|intersection| layers.reports(intersection));
+        handler.layers = Some(layers);
+        handler
+    }
 
+    /// Identical lines are only inserted once, their overlaps are reported directly.
+    fn with_degenerate_handling(lines: Vec<Line2D<T>>, options: SweepLineOptions) -> Self {
+        let line_count = lines.len();
+        let mut indices: HashMap<Line2D<T>, (usize, usize)> = HashMap::new();
+        let mut unique_lines = Vec::new();
+        for (i, line) in lines.into_iter().enumerate() {
+            indices
+                .entry(line.clone())
+                .and_modify(|(_, last)| *last = i)
+                .or_insert_with(|| {
+                    unique_lines.push(line);
+                    (i, i)
+                });
+        }
+
+        let mut handler = Handler {
+            queue: EventQueue::new(Vec::new()),
+            event_points: EventPointQueue::new(unique_lines.clone()),
+            sweep_line: SweepLine::new(),
+            intersections: HashSet::new(),
+            options,
+            x_coords: HashSet::new(),
+            intersection_points: BTreeMap::new(),
+            indices,
+            layers: None,
+            line_count,
+            trace: None,
+            counting: None,
+        };
+        for line in unique_lines {
+            let (first, last) = handler.indices[&line];
+            if first != last {
+                handler.report(&line, &line);
+            }
+        }
         handler
     }
 
-    pub fn run(&mut self) -> HashSet<Intersection> {
+    pub fn run(&mut self) -> HashSet<Intersection<T>> {
+        self.sweep();
+        if let Some(layers) = &self.layers {
+            self.intersections
+                .retain(|intersection| layers.reports(intersection));
+        }
+        self.intersections.clone()
+    }
+
+    /// Counts the intersections [run](Self::run) would return by variant without storing them.
+    /// Every intersection is counted at the event point where it's found, overlaps at the left end
+    /// of the overlap, so apart from the pending events only the intersections near the sweep line
+    /// are remembered.
+    pub fn count(&mut self) -> IntersectionCounts {
+        self.count_within(None, None)
+    }
+
+    /// Counts the intersections with a representative x-coordinate in [left, right), unbounded if
+    /// None, see [representative_point].
+    pub(crate) fn count_within(
+        &mut self,
+        left: Option<T::Field>,
+        right: Option<T::Field>,
+    ) -> IntersectionCounts {
+        let mut counting = Counting {
+            counts: IntersectionCounts::default(),
+            left,
+            right,
+            point: None,
+            recent: BTreeMap::new(),
+        };
+        // copies of lines are reported when the handler is created
+        for intersection in self.take_intersections() {
+            counting.point = Some(representative_point(&intersection));
+            counting.add(intersection);
+        }
+        counting.point = None;
+
+        self.counting = Some(counting);
+        self.sweep();
+        self.counting
+            .take()
+            .map_or_else(Default::default, |counting| counting.counts)
+    }
+
+    fn sweep(&mut self) {
+        while let Some((point, event_point)) = self.event_points.pop() {
+            self.handle_event_point(point, event_point);
+        }
         while !self.queue.is_empty() {
             let event = self.queue.pop();
             if let Some(event) = event {
                 self.handle_event(event);
             }
         }
-        self.intersections.clone()
     }
 
-    fn handle_event(&mut self, event: Event) {
+    /// Adds the intersection to the result, or counts it in the counting mode.
+    fn add(&mut self, intersection: Intersection<T>) {
+        match &mut self.counting {
+            Some(counting) => {
+                if self
+                    .layers
+                    .as_ref()
+                    .is_none_or(|layers| layers.reports(&intersection))
+                {
+                    counting.add(intersection);
+                }
+            }
+            None => {
+                self.intersections.insert(intersection);
+            }
+        }
+    }
+
+    /// Sets the event point whose intersections are counted, None while only new events are searched.
+    fn count_at(&mut self, point: Option<&Point2D<T::Field>>) {
+        if let Some(counting) = &mut self.counting {
+            counting.enter(point);
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_count
+    }
+
+    /// Records every processed event from now on, see [Trace].
+    /// Copying the sweep line after each event makes the sweep O(n) per event.
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Trace::new());
+    }
+
+    /// Trace of the processed events, None if it isn't enabled.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Checks whether any pair of lines intersects (Shamos-Hoey) and returns the leftmost
+    /// intersection by its representative point, None if the lines are intersection-free.
+    /// Neighbours report their crossing when they are found, which may lie right of other
+    /// intersections, so the leftmost intersection found so far is kept and the sweep continues
+    /// until the next event point lies right of it. The crossing events handled until then are
+    /// intersections themselves, so the check still runs in O(n log n).
+    /// Requires the degenerate handling options.
+    pub fn find_any(&mut self) -> Option<Intersection<T>> {
+        assert!(
+            self.options.handle_degenerate,
+            "Finding any intersection requires degenerate handling"
+        );
+        let mut leftmost: Option<(Point2D<T::Field>, Intersection<T>)> = None;
+        loop {
+            // only the intersections of the last event point are new
+            for intersection in std::mem::take(&mut self.intersections) {
+                let point = representative_point(&intersection);
+                let is_left = leftmost.as_ref().is_none_or(|(leftmost_point, leftmost)| {
+                    (&point, &intersection) < (leftmost_point, leftmost)
+                });
+                if is_left {
+                    leftmost = Some((point, intersection));
+                }
+            }
+            match (&leftmost, self.event_points.first_point()) {
+                (_, None) => break,
+                (Some((point, _)), Some(next)) if next > point => break,
+                _ => {}
+            }
+            let (point, event_point) = self.event_points.pop()?;
+            self.handle_event_point(point, event_point);
+        }
+        leftmost.map(|(_, intersection)| intersection)
+    }
+
+    /// Adds a line of a sorted stream, which occurs at the positions first to last of the input.
+    /// Lines have to be added before the sweep reaches their start, see [Handler::step].
+    /// Requires the degenerate handling options.
+    pub(crate) fn insert_line(&mut self, line: Line2D<T>, first: usize, last: usize) {
+        self.indices.insert(line.clone(), (first, last));
+        if first != last {
+            self.report(&line, &line);
+        }
+        self.event_points.add_line(line);
+    }
+
+    /// Point of the next event point of the sweep.
+    pub(crate) fn next_point(&self) -> Option<&Point2D<T::Field>> {
+        self.event_points.first_point()
+    }
+
+    /// Number of pending event points.
+    pub(crate) fn pending_points(&self) -> usize {
+        self.event_points.len()
+    }
+
+    /// Handles the next event point and forgets the lines ending there, which are returned.
+    /// None if there are no more event points.
+    pub(crate) fn step(&mut self) -> Option<Vec<Line2D<T>>> {
+        let (point, event_point) = self.event_points.pop()?;
+        let ends = event_point.ends.clone();
+        self.handle_event_point(point, event_point);
+        for line in &ends {
+            self.indices.remove(line);
+        }
+        Some(ends)
+    }
+
+    /// Intersections found since the last call, an intersection might be found again later.
+    pub(crate) fn take_intersections(&mut self) -> HashSet<Intersection<T>> {
+        std::mem::take(&mut self.intersections)
+    }
+
+    /// Handles all lines meeting at the event point at once:
+    /// reports every pair of them and reinserts the ones continuing behind the point in the order
+    /// right of the point, which reverses the order of crossing lines.
+    fn handle_event_point(&mut self, point: Point2D<T::Field>, event_point: EventPoint<T>) {
+        self.sweep_line.set_x(point.x.to_f64());
+
+        let mut passing = self.lines_through(&point);
+        for line in event_point.ends.iter().chain(&event_point.crossings) {
+            if self.sweep_line.contains(line) && !passing.contains(line) {
+                passing.push(line.clone());
+            }
+        }
+
+        let meeting: Vec<Line2D<T>> = passing.iter().chain(&event_point.starts).cloned().collect();
+        let traced_lines = self.trace.is_some().then(|| meeting.clone());
+        // every intersection is found by the pairs of lines meeting at an event point
+        self.count_at(Some(&point));
+        for i in 0..meeting.len() {
+            for j in i + 1..meeting.len() {
+                self.report(&meeting[i], &meeting[j]);
+            }
+        }
+        self.count_at(None);
+
+        for line in &passing {
+            self.sweep_line.remove(line);
+        }
+
+        let mut continuing: Vec<Line2D<T>> = meeting
+            .into_iter()
+            .filter(|line| {
+                !event_point.ends.contains(line) && line.sorted_endpoints().1.to_field() > point
+            })
+            .collect();
+        continuing.sort_by(|a, b| Self::compare_at(a, b, &point));
+        for line in &continuing {
+            self.sweep_line
+                .add_by(line.clone(), |a, b| Self::compare_at(a, b, &point));
+        }
+
+        match (continuing.first(), continuing.last()) {
+            (Some(lowest), Some(highest)) => {
+                if let Some(below) = self.sweep_line.get_neighbors(lowest).smaller {
+                    self.find_new_event(below, lowest.clone(), &point);
+                }
+                if let Some(above) = self.sweep_line.get_neighbors(highest).bigger {
+                    self.find_new_event(highest.clone(), above, &point);
+                }
+            }
+            _ => {
+                let above = self
+                    .sweep_line
+                    .first_where(|line| Self::y_at_point(line, &point) >= point.y);
+                let below = match &above {
+                    Some(above) => self.sweep_line.get_neighbors(above).smaller,
+                    None => self.sweep_line.last(),
+                };
+                if let (Some(below), Some(above)) = (below, above) {
+                    self.find_new_event(below, above, &point);
+                }
+            }
+        }
+
+        if let (Some(trace), Some(mut lines)) = (&mut self.trace, traced_lines) {
+            for line in &event_point.ends {
+                if !lines.contains(line) {
+                    lines.push(line.clone());
+                }
+            }
+            let status = self.sweep_line.get_sorted_lines();
+            trace.step(StepKind::EventPoint, &point, &lines, &status);
+        }
+    }
+
+    /// Returns the lines of the sweep line passing through the point in ascending order.
+    fn lines_through(&self, point: &Point2D<T::Field>) -> Vec<Line2D<T>> {
+        let tolerance = T::Field::tolerance(&point.y);
+        let lower = point.y.clone() - tolerance.clone();
+        let upper = point.y.clone() + tolerance;
+        let mut lines = Vec::new();
+        let mut current = self
+            .sweep_line
+            .first_where(|line| Self::y_at_point(line, point) >= lower);
+        while let Some(line) = current {
+            if Self::y_at_point(&line, point) > upper {
+                break;
+            }
+            current = self.sweep_line.get_neighbors(&line).bigger;
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Reports the intersection of both lines and adds an event if they cross behind the point.
+    fn find_new_event(&mut self, below: Line2D<T>, above: Line2D<T>, point: &Point2D<T::Field>) {
+        if let Some(Intersection::Crossing {
+            point: intersection_point,
+            ..
+        }) = self.report(&below, &above)
+        {
+            let rejection =
+                (intersection_point <= *point).then_some(EventRejection::AlreadyProcessed);
+            if let Some(trace) = &mut self.trace {
+                trace.queue(&intersection_point, &below, &above, rejection);
+            }
+            if rejection.is_none() {
+                self.event_points
+                    .add_crossing(intersection_point, below, above);
+            }
+        }
+    }
+
+    /// Adds the intersection of both lines to the result.
+    /// The lines are intersected in the order of their position in the input, exactly as the brute
+    /// force approach does, so that both results are identical. Identical lines which occur at
+    /// several positions might be intersected in both orders.
+    /// In the bichromatic mode only intersections of lines of different layers are added.
+    fn report(&mut self, line1: &Line2D<T>, line2: &Line2D<T>) -> Option<Intersection<T>> {
+        let (first1, last1) = self.indices[line1];
+        let (first2, last2) = self.indices[line2];
+
+        let mut result = None;
+        for (line1, line2, needed) in [
+            (line1, line2, first1 < last2),
+            (line2, line1, first2 < last1),
+        ] {
+            if !needed {
+                continue;
+            }
+            if let Some(intersection) = Self::intersect(line1, line2) {
+                if result.is_none() {
+                    result = Some(intersection.clone());
+                }
+                // intersections within a layer are still returned, as they become events
+                let reported = match &self.layers {
+                    Some(layers) => layers.is_bichromatic(line1, line2),
+                    None => true,
+                };
+                if reported {
+                    self.add(intersection);
+                }
+            }
+        }
+        result
+    }
+
+    fn intersect(line1: &Line2D<T>, line2: &Line2D<T>) -> Option<Intersection<T>> {
+        if line1.intersects(line2.clone()) {
+            line1.find_intersection(line2.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Order of the lines directly right of the point.
+    /// Lines passing through the point are ordered by their slope, vertical lines are the biggest.
+    fn compare_at(line1: &Line2D<T>, line2: &Line2D<T>, point: &Point2D<T::Field>) -> Ordering {
+        let y1 = Self::y_at_point(line1, point);
+        let y2 = Self::y_at_point(line2, point);
+        let tolerance = T::Field::tolerance(&point.y);
+
+        if abs_diff(&y1, &y2) > tolerance {
+            return y1.cmp(&y2);
+        }
+        let slope = match (Self::slope(line1), Self::slope(line2)) {
+            (Some(slope1), Some(slope2)) => slope1.cmp(&slope2),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        slope.then_with(|| line1.cmp(line2))
+    }
+
+    /// y-coordinate of the line at the x-coordinate of the point.
+    /// Vertical lines are evaluated at the y-coordinate of the point, clamped to the line.
+    fn y_at_point(line: &Line2D<T>, point: &Point2D<T::Field>) -> T::Field {
+        let (start, end) = line.sorted_endpoints();
+        let (start, end) = (start.to_field(), end.to_field());
+        if start.x == end.x {
+            return point.y.clone().clamp(start.y, end.y);
+        }
+        if point.x == start.x {
+            return start.y;
+        }
+        if point.x == end.x {
+            return end.y;
+        }
+        start.y.clone()
+            + (point.x.clone() - start.x.clone()) * (end.y - start.y) / (end.x - start.x)
+    }
+
+    /// Slope of the line, None if it is vertical.
+    fn slope(line: &Line2D<T>) -> Option<T::Field> {
+        if line.is_vertical() {
+            None
+        } else {
+            let (start, end) = (line.start.to_field(), line.end.to_field());
+            Some((end.y - start.y) / (end.x - start.x))
+        }
+    }
+
+    fn handle_event(&mut self, event: Event<T>) {
+        let traced = self.trace.is_some().then(|| match &event {
+            Event::StartEvent { line } => {
+                (StepKind::Start, line.start.to_field(), vec![line.clone()])
+            }
+            Event::EndEvent { line } => (StepKind::End, line.end.to_field(), vec![line.clone()]),
+            Event::IntersectionEvent {
+                intersection,
+                smaller,
+                bigger,
+            } => (
+                StepKind::Intersection,
+                get_point_of_intersection(intersection),
+                vec![smaller.clone(), bigger.clone()],
+            ),
+        });
         match event {
             Event::StartEvent { line } => self.handle_start_event(line),
             Event::EndEvent { line } => self.handle_end_event(line),
@@ -90,103 +575,203 @@ impl Handler {
                 bigger,
             } => self.handle_intersection_event(intersection, smaller, bigger),
         }
+        if let (Some(trace), Some((kind, point, lines))) = (&mut self.trace, traced) {
+            let status = self.sweep_line.get_sorted_lines();
+            trace.step(kind, &point, &lines, &status);
+        }
     }
 
-    fn handle_start_event(&mut self, line: Line2D) {
-        self.sweep_line.set_x(*line.start.x);
+    fn handle_start_event(&mut self, line: Line2D<T>) {
+        self.sweep_line.set_x(line.start.x.to_f64());
 
-        self.sweep_line.add(line);
+        self.sweep_line.add(line.clone());
         let neighbors = self.sweep_line.get_neighbors(&line);
 
         if let Some(small_neighbor) = neighbors.smaller {
-            let intersection_point = line.find_intersection(small_neighbor);
+            let intersection_point = line.find_intersection(small_neighbor.clone());
             if let Some(intersection) = intersection_point {
-                self.add_intersection_event(intersection, small_neighbor, line);
+                self.add_intersection_event(intersection, small_neighbor, line.clone());
             }
         }
         if let Some(big_neighbor) = neighbors.bigger {
-            let intersection_point = line.find_intersection(big_neighbor);
+            let intersection_point = line.find_intersection(big_neighbor.clone());
             if let Some(intersection) = intersection_point {
                 self.add_intersection_event(intersection, line, big_neighbor);
             }
         }
     }
 
-    fn handle_end_event(&mut self, line: Line2D) {
-        self.sweep_line.set_x(*line.end.x);
+    fn handle_end_event(&mut self, line: Line2D<T>) {
+        self.sweep_line.set_x(line.end.x.to_f64());
 
         let neighbors = self.sweep_line.get_neighbors(&line);
         self.sweep_line.remove(&line);
 
         if let (Some(small_neighbor), Some(big_neighbor)) = (neighbors.smaller, neighbors.bigger) {
-            let intersection_point = small_neighbor.find_intersection(big_neighbor);
+            let intersection_point = small_neighbor.find_intersection(big_neighbor.clone());
             if let Some(intersection) = intersection_point {
                 self.add_intersection_event(intersection, small_neighbor, big_neighbor);
             }
         }
     }
 
+    /// Handles all lines crossing at the point of the intersection (C(p)) at once:
+    /// their order is reversed and every pair of them is reported.
     fn handle_intersection_event(
         &mut self,
-        intersection: Intersection,
-        smaller: Line2D,
-        bigger: Line2D,
+        intersection: Intersection<T>,
+        smaller: Line2D<T>,
+        bigger: Line2D<T>,
     ) {
-        // small shift to the right to calculate order behind intersection
-        let point = match intersection {
-            Intersection::Crossing { point, .. } => point,
-            Intersection::Touching { point, .. } => point,
-            Intersection::PartialOverlap { overlap, .. } => overlap.start,
-            Intersection::ContainedOverlap { overlap, .. } => overlap.start,
-            Intersection::IdenticalOverlap { overlap, .. } => overlap.start,
-        };
-
-        self.sweep_line.set_x(point.x.0 + self.options.x_shift);
+        let point = get_point_of_intersection(&intersection);
+        // points left of the event can't be compared with new events anymore
+        self.intersection_points = self.intersection_points.split_off(&point.x);
 
         // add intersection to the list
-        self.intersections.insert(intersection);
+        self.count_at(Some(&point));
+        self.add(intersection);
+
+        let crossing = self.lines_crossing_at(&point, &smaller, &bigger);
+        if crossing.len() < 2 {
+            self.count_at(None);
+            return;
+        }
 
-        let above = self.sweep_line.get_neighbors(&smaller).bigger;
-        let below = self.sweep_line.get_neighbors(&bigger).smaller;
+        // the lines reverse their order behind the intersection
+        self.sweep_line.reverse(&crossing);
+        // small shift to the right to calculate order behind intersection
+        self.sweep_line
+            .set_x(point.x.to_f64() + self.options.x_shift);
+
+        // the pair of the event is already reported
+        for i in 0..crossing.len() {
+            for j in i + 1..crossing.len() {
+                if crossing[i] == smaller && crossing[j] == bigger {
+                    continue;
+                }
+                if let Some(intersection @ Intersection::Crossing { .. }) =
+                    crossing[i].find_intersection(crossing[j].clone())
+                {
+                    self.add(intersection);
+                }
+            }
+        }
+        self.count_at(None);
 
-        // if intersection segE2 with segA
-        if let Some(above) = above {
-            let intersection_point = above.find_intersection(smaller);
+        let lowest = &crossing[crossing.len() - 1];
+        let highest = &crossing[0];
+
+        if let Some(above) = self.sweep_line.get_neighbors(highest).bigger {
+            let intersection_point = above.find_intersection(highest.clone());
             if let Some(intersection) = intersection_point {
-                self.add_intersection_event(intersection, smaller, above);
+                self.add_intersection_event(intersection, highest.clone(), above);
             }
         }
-        // if intersection segE1 with segB
-        if let Some(below) = below {
-            let intersection_point = below.find_intersection(bigger);
+        if let Some(below) = self.sweep_line.get_neighbors(lowest).smaller {
+            let intersection_point = below.find_intersection(lowest.clone());
             if let Some(intersection) = intersection_point {
-                self.add_intersection_event(intersection, below, bigger);
+                self.add_intersection_event(intersection, below, lowest.clone());
+            }
+        }
+    }
+
+    /// Returns the consecutive lines of the sweep line passing through the point in ascending order,
+    /// which includes the two given lines.
+    /// The sweep line is ordered with floating point arithmetic, so the floating point tolerance is used
+    /// for all scalars.
+    fn lines_crossing_at(
+        &self,
+        point: &Point2D<T::Field>,
+        smaller: &Line2D<T>,
+        bigger: &Line2D<T>,
+    ) -> Vec<Line2D<T>> {
+        if !self.sweep_line.contains(smaller) || !self.sweep_line.contains(bigger) {
+            return Vec::new();
+        }
+
+        let (x, y) = (point.x.to_f64(), point.y.to_f64());
+        let tolerance = OrderedFloat::tolerance(&OrderedFloat(y)).0;
+        let passes_through = |line: &Line2D<T>| {
+            line.y_at(x)
+                .is_some_and(|y_at| (y_at - y).abs() <= tolerance)
+        };
+
+        let mut lowest = smaller.clone();
+        while let Some(below) = self.sweep_line.get_neighbors(&lowest).smaller {
+            if !passes_through(&below) {
+                break;
             }
+            lowest = below;
         }
+
+        let mut reached_bigger = lowest == *bigger;
+        let mut lines = vec![lowest];
+        while let Some(above) = self.sweep_line.get_neighbors(lines.last().unwrap()).bigger {
+            if reached_bigger && !passes_through(&above) {
+                break;
+            }
+            reached_bigger |= above == *bigger;
+            lines.push(above);
+        }
+        lines
     }
 
     fn add_intersection_event(
         &mut self,
-        intersection: Intersection,
-        smaller: Line2D,
-        bigger: Line2D,
+        intersection: Intersection<T>,
+        smaller: Line2D<T>,
+        bigger: Line2D<T>,
     ) {
+        let point = get_point_of_intersection(&intersection);
+        let rejection = self.check_intersection_event(&intersection, &smaller, &bigger);
+        if let Some(trace) = &mut self.trace {
+            trace.queue(&point, &smaller, &bigger, rejection);
+        }
+        if rejection.is_some() {
+            return;
+        }
+
+        self.intersection_points
+            .entry(point.x)
+            .or_default()
+            .push(point.y);
+        self.queue.add(Event::IntersectionEvent {
+            intersection,
+            smaller,
+            bigger,
+        });
+    }
+
+    /// Returns why no event is added for the intersection, None if it has to be added.
+    fn check_intersection_event(
+        &self,
+        intersection: &Intersection<T>,
+        smaller: &Line2D<T>,
+        bigger: &Line2D<T>,
+    ) -> Option<EventRejection> {
         match intersection {
             Intersection::Crossing { point, .. } => {
+                // already processed
+                if *self.sweep_line.get_x() > point.x.to_f64() {
+                    return Some(EventRejection::AlreadyProcessed);
+                }
                 let new_event = Event::IntersectionEvent {
-                    intersection,
-                    smaller,
-                    bigger,
+                    intersection: intersection.clone(),
+                    smaller: smaller.clone(),
+                    bigger: bigger.clone(),
                 };
-
-                // already processed
-                if self.sweep_line.get_x() > point.x || self.queue.contains(&new_event) {
-                    return;
+                if self.queue.contains(&new_event) {
+                    return Some(EventRejection::AlreadyQueued);
                 }
 
-                // self.intersections.iter().for_each(|i| { println!("{:?}", i)});
+                // another pair of lines crossing at the same point, handled by the same event
+                if self.is_intersection_point(point) {
+                    return Some(EventRejection::SamePoint);
+                }
 
-                if self.x_coords.contains(&point.x) {
+                if self.x_coords.contains(&point.x)
+                    || self.intersection_points.contains_key(&point.x)
+                {
                     if self.options.panic_on_identical_x {
                         panic!("Duplicate x-Coordinate: {:?}", intersection)
                     } else {
@@ -194,12 +779,10 @@ impl Handler {
                             "Duplicate x-Coordinate. Skip adding intersection event of {:?}",
                             intersection
                         );
-                        return;
+                        return Some(EventRejection::DuplicateX);
                     }
                 }
-
-                self.x_coords.insert(point.x);
-                self.queue.add(new_event);
+                None
             }
             // Error Handling Below
             Intersection::Touching { .. } => {
@@ -208,7 +791,7 @@ impl Handler {
                 }
                 eprintln!("Touching intersection detected {:?}", intersection);
                 // todo: add it to the output, but not queue, or skip entirely?
-                return;
+                Some(EventRejection::Touching)
             }
             _ => {
                 if self.options.panic_on_overlap {
@@ -216,76 +799,199 @@ impl Handler {
                 }
                 eprintln!("Overlapping intersection detected {:?}", intersection);
                 // todo: add it to the output, but not queue, or skip entirely?
-                return;
+                Some(EventRejection::Overlap)
             }
         }
     }
 
+    /// Checks whether an intersection event was already added for the point.
+    /// Points are compared exactly, a crossing close to another one gets its own event.
+    fn is_intersection_point(&self, point: &Point2D<T::Field>) -> bool {
+        self.intersection_points
+            .get(&point.x)
+            .is_some_and(|ys| ys.contains(&point.y))
+    }
+
+    /// Lines the sweep line without degenerate handling can't process, with the reason they are rejected.
+    pub fn rejected_lines(lines: &[Line2D<T>]) -> Vec<(Line2D<T>, Rejection)> {
+        let mut x_coords = HashSet::new();
+        lines
+            .iter()
+            .filter_map(|line| {
+                Self::check_line(line, &mut x_coords)
+                    .err()
+                    .map(|rejection| (line.clone(), rejection))
+            })
+            .collect()
+    }
+
     /// Performs sanity checks on the input lines
     /// Covers the following cases:
     ///   - lines with zero length -> remove them
     ///   - lines with identical x coordinates -> panic
     ///   - vertical lines -> panic
+    ///
     /// Complexity: O(n)
     fn sanity_checks(
-        lines: &mut Vec<Line2D>,
+        lines: &[Line2D<T>],
         options: &SweepLineOptions,
-    ) -> (Vec<Line2D>, HashSet<OrderedFloat<f64>>) {
+    ) -> (Vec<Line2D<T>>, HashSet<T::Field>) {
         let mut x_coords = HashSet::new();
 
         let res = lines
-            .clone()
-            .into_iter()
-            .filter_map(|mut line| {
-                if line.is_zero_length() {
-                    if options.panic_on_zero_length {
-                        panic!("Zero length line detected: {}", line);
-                    } else {
-                        eprintln!("Zero length line detected, ignoring line: {}", line);
-                        return None;
-                    }
+            .iter()
+            .filter_map(|line| match Self::check_line(line, &mut x_coords) {
+                Ok(line) => Some(line),
+                Err(Rejection::ZeroLength) if options.panic_on_zero_length => {
+                    panic!("Zero length line detected: {}", line);
                 }
-                if line.is_vertical() {
-                    if options.panic_on_vertical {
-                        panic!("Vertical line detected");
-                    } else {
-                        // eprintln!("Vertical line detected, shifting x coordinate slightly");
-                        // line.end.x = line.end.x + options.x_shift;
-                        eprintln!("Vertical line detected, ignoring line: {}", line);
-                        return None;
-                    }
+                Err(Rejection::Vertical) if options.panic_on_vertical => {
+                    panic!("Vertical line detected");
                 }
-
-                if line.start.x > line.end.x {
-                    line = Line2D::new(*line.end.x, *line.end.y, *line.start.x, *line.start.y);
+                Err(Rejection::IdenticalX) if options.panic_on_identical_x => {
+                    let end_x = std::cmp::max(&line.start.x, &line.end.x);
+                    panic!("Lines have identical x coordinates: {}", end_x);
                 }
-
-                x_coords.insert(line.start.x);
-
-                if x_coords.contains(&(line.end.x)) {
-                    if options.panic_on_identical_x {
-                        panic!("Lines have identical x coordinates: {}", line.end.x);
-                    } else {
-                        // eprintln!("Duplicate x value detected, shifting end x coordinate  of {} slightly", line);
-                        // line.end.x = line.end.x + options.x_shift;
-                        eprintln!("Duplicate x value detected, ignoring line: {}", line);
-                        return None;
-                    }
+                Err(rejection) => {
+                    eprintln!("{} detected, ignoring line: {}", rejection, line);
+                    None
                 }
-
-                Some(line)
             })
             .collect();
 
         (res, x_coords)
     }
+
+    /// Checks a line and orders its endpoints by x, the x-coordinates seen so far are updated.
+    fn check_line(
+        line: &Line2D<T>,
+        x_coords: &mut HashSet<T::Field>,
+    ) -> Result<Line2D<T>, Rejection> {
+        if line.is_zero_length() {
+            return Err(Rejection::ZeroLength);
+        }
+        if line.is_vertical() {
+            return Err(Rejection::Vertical);
+        }
+
+        let mut line = line.clone();
+        if line.start.x > line.end.x {
+            std::mem::swap(&mut line.start, &mut line.end);
+        }
+
+        x_coords.insert(line.start.x.to_field());
+
+        if x_coords.contains(&line.end.x.to_field()) {
+            return Err(Rejection::IdenticalX);
+        }
+        Ok(line)
+    }
+}
+
+/// Intersections counted by [Handler::count].
+struct Counting<T: Scalar> {
+    counts: IntersectionCounts,
+    /// Bounds of the representative x-coordinates counted
+    left: Option<T::Field>,
+    right: Option<T::Field>,
+    /// Event point whose intersections are counted, None while only new events are searched, which
+    /// may find intersections of past event points again
+    point: Option<Point2D<T::Field>>,
+    /// Counted intersections by their representative x-coordinate, kept until the sweep has passed
+    /// them, as a pair of lines may meet at several event points within the tolerance
+    recent: BTreeMap<T::Field, HashSet<Intersection<T>>>,
+}
+
+impl<T: Scalar> Counting<T> {
+    fn enter(&mut self, point: Option<&Point2D<T::Field>>) {
+        if let Some(point) = point {
+            let passed = point.x.clone() - T::Field::tolerance(&point.x);
+            self.recent = self.recent.split_off(&passed);
+        }
+        self.point = point.cloned();
+    }
+
+    fn add(&mut self, intersection: Intersection<T>) {
+        let Some(point) = &self.point else {
+            return;
+        };
+        let representative = representative_point(&intersection);
+        let owned = self
+            .left
+            .as_ref()
+            .is_none_or(|left| representative.x >= *left)
+            && self
+                .right
+                .as_ref()
+                .is_none_or(|right| representative.x < *right);
+        // overlapping lines meet at every event point within the overlap
+        let overlap = !matches!(
+            intersection,
+            Intersection::Crossing { .. } | Intersection::Touching { .. }
+        );
+        if !owned || (overlap && representative != *point) {
+            return;
+        }
+        if self
+            .recent
+            .entry(representative.x)
+            .or_default()
+            .insert(intersection.clone())
+        {
+            self.counts.add(&intersection);
+        }
+    }
+}
+
+/// Point an intersection is assigned to: the point of a crossing or touching, the left end of an
+/// overlap.
+pub(crate) fn representative_point<T: Scalar>(intersection: &Intersection<T>) -> Point2D<T::Field> {
+    match intersection {
+        Intersection::Crossing { point, .. } | Intersection::Touching { point, .. } => {
+            point.clone()
+        }
+        Intersection::PartialOverlap { overlap, .. }
+        | Intersection::ContainedOverlap { overlap, .. }
+        | Intersection::IdenticalOverlap { overlap, .. } => overlap.sorted_endpoints().0.to_field(),
+    }
+}
+
+/// Reason why the sweep line without degenerate handling ignores a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    ZeroLength,
+    Vertical,
+    /// The end of the line shares its x-coordinate with a previous endpoint
+    IdenticalX,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::ZeroLength => write!(f, "Zero length line"),
+            Rejection::Vertical => write!(f, "Vertical line"),
+            Rejection::IdenticalX => write!(f, "Duplicate x value"),
+        }
+    }
+}
+
+fn abs_diff<F: Scalar + Sub<Output = F>>(a: &F, b: &F) -> F {
+    if a > b {
+        a.clone() - b.clone()
+    } else {
+        b.clone() - a.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::line_segments::LineSegments2D;
     use crate::geometry::point::Point2D;
+    use num_rational::BigRational;
     use ordered_float::OrderedFloat;
+    use std::path::Path;
 
     fn get_options_to_panic() -> SweepLineOptions {
         SweepLineOptions::panic_enabled()
@@ -394,10 +1100,7 @@ mod tests {
         let line2 = Line2D::new(20.823, 47.127, 20.8124, 47.3939);
         let line3 = Line2D::new(20.616, 47.165, 21.2751, 47.4518);
 
-        let mut handler = Handler::new(
-            vec![line1, line2, line3],
-            get_options_to_panic(),
-        );
+        let mut handler = Handler::new(vec![line1, line2, line3], get_options_to_panic());
 
         let intersections = handler.run();
 
@@ -422,6 +1125,22 @@ mod tests {
         assert_eq!(intersections.len(), 5);
     }
 
+    #[test]
+    fn crossings_closer_than_the_float_tolerance_should_be_separate_events() {
+        // the second pair crosses 2e-9 right of and 1e-9 above the first one
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 10.0, 10.0),
+            Line2D::new(0.5, 9.5, 9.5, 0.5),
+            Line2D::new(1.000000002, -2.999999999, 9.000000002, 13.000000001),
+            Line2D::new(1.000000002, 7.000000001, 9.000000002, 3.000000001),
+        ];
+        let expected = BruteForceHandler::new(lines.clone()).run();
+
+        let actual = Handler::new(lines, SweepLineOptions::panic_disabled()).run();
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_multiple_intersections_same_start_point() {
         // Visualization: https://www.geogebra.org/calculator/mtakwyb8
@@ -492,4 +1211,382 @@ mod tests {
         })
         .is_err());
     }
+
+    fn assert_equals_brute_force(lines: Vec<Line2D>) -> HashSet<Intersection> {
+        let expected = BruteForceHandler::new(lines.clone()).run();
+        let mut handler = Handler::new(lines, SweepLineOptions::degenerate_handling());
+
+        let intersections = handler.run();
+
+        assert_eq!(intersections, expected);
+        intersections
+    }
+
+    #[test]
+    fn degenerate_handling_should_report_vertical_lines() {
+        let line1 = Line2D::new(1.0, 0.0, 1.0, 4.0);
+        let line2 = Line2D::new(0.0, 1.0, 2.0, 3.0);
+        let line3 = Line2D::new(0.0, 3.0, 3.0, 3.0);
+        let line4 = Line2D::new(1.0, 4.0, 1.0, 5.0);
+
+        let intersections = assert_equals_brute_force(vec![line1, line2, line3, line4]);
+
+        assert_eq!(intersections.len(), 4);
+    }
+
+    #[test]
+    fn degenerate_handling_should_report_zero_length_lines() {
+        let line1 = Line2D::new(0.0, 0.0, 4.0, 4.0);
+        let line2 = Line2D::new(2.0, 2.0, 2.0, 2.0);
+        let line3 = Line2D::new(3.0, 0.0, 3.0, 0.0);
+        let line4 = Line2D::new(2.0, 2.0, 2.0, 2.0);
+
+        let intersections = assert_equals_brute_force(vec![line1, line2, line3, line4]);
+
+        assert_eq!(intersections.len(), 2);
+    }
+
+    #[test]
+    fn degenerate_handling_should_report_touching_and_overlapping_lines() {
+        let line1 = Line2D::new(0.0, 0.0, 4.0, 0.0);
+        let line2 = Line2D::new(2.0, 0.0, 3.0, 2.0);
+        let line3 = Line2D::new(3.0, 0.0, 6.0, 0.0);
+        let line4 = Line2D::new(1.0, 0.0, 2.0, 0.0);
+        let line5 = Line2D::new(0.0, 0.0, 4.0, 0.0);
+        let line6 = Line2D::new(4.0, 0.0, 5.0, -1.0);
+
+        let intersections =
+            assert_equals_brute_force(vec![line1, line2, line3, line4, line5, line6]);
+
+        assert!(intersections
+            .iter()
+            .any(|i| matches!(i, Intersection::Touching { .. })));
+        assert!(intersections
+            .iter()
+            .any(|i| matches!(i, Intersection::PartialOverlap { .. })));
+        assert!(intersections
+            .iter()
+            .any(|i| matches!(i, Intersection::ContainedOverlap { .. })));
+        assert!(intersections
+            .iter()
+            .any(|i| matches!(i, Intersection::IdenticalOverlap { .. })));
+    }
+
+    #[test]
+    fn degenerate_handling_should_report_lines_with_identical_x_coordinates() {
+        let line1 = Line2D::new(0.0, 0.0, 2.0, 2.0);
+        let line2 = Line2D::new(0.0, 2.0, 2.0, 0.0);
+        let line3 = Line2D::new(1.0, 0.0, 3.0, 3.0);
+        let line4 = Line2D::new(1.0, 3.0, 2.0, 1.0);
+        let line5 = Line2D::new(2.0, 3.0, 4.0, 0.0);
+
+        assert_equals_brute_force(vec![line1, line2, line3, line4, line5]);
+    }
+
+    #[test]
+    fn degenerate_handling_should_report_all_lines_through_one_point() {
+        let line1 = Line2D::new(0.0, 0.0, 2.0, 2.0);
+        let line2 = Line2D::new(0.0, 2.0, 2.0, 0.0);
+        let line3 = Line2D::new(0.0, 1.0, 2.0, 1.0);
+        let line4 = Line2D::new(1.0, 0.0, 1.0, 2.0);
+        let line5 = Line2D::new(1.0, 1.0, 3.0, 1.5);
+
+        let intersections = assert_equals_brute_force(vec![line1, line2, line3, line4, line5]);
+
+        assert_eq!(intersections.len(), 10);
+    }
+
+    #[test]
+    fn degenerate_handling_should_equal_brute_force_for_data_files() {
+        for file in ["data/s_1000_1.dat", "data/s_1000_10.dat"] {
+            let lines = LineSegments2D::from_dat(Path::new(file)).unwrap().lines;
+            assert_equals_brute_force(lines);
+        }
+    }
+
+    #[test]
+    fn should_report_all_lines_crossing_at_same_point() {
+        let line1 = Line2D::new(0.0, 0.0, 4.0, 4.0);
+        let line2 = Line2D::new(0.5, 3.5, 3.5, 0.5);
+        let line3 = Line2D::new(1.0, 2.0, 3.0, 2.0);
+        let line4 = Line2D::new(2.5, 0.25, 3.75, 4.5);
+
+        let expected = BruteForceHandler::new(vec![line1, line2, line3, line4]).run();
+        let mut handler = Handler::new(vec![line1, line2, line3, line4], get_options_to_panic());
+
+        let intersections = handler.run();
+
+        assert_eq!(intersections.len(), 5);
+        assert_eq!(intersections, expected);
+    }
+
+    /// Segments between the points of a small grid, with many lines meeting in one point,
+    /// crossings at fractional coordinates and collinear overlaps.
+    fn grid_lines() -> Vec<Line2D<i64>> {
+        let mut lines = Vec::new();
+        for i in 0..6i64 {
+            for j in 0..6i64 {
+                let start = Point2D { x: i, y: j };
+                let end = Point2D {
+                    x: (i * 7 + j * 3) % 6,
+                    y: (i * 5 + j) % 6,
+                };
+                lines.push(Line2D { start, end });
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn degenerate_handling_should_equal_brute_force_for_integer_grid() {
+        let lines = grid_lines();
+        let expected = BruteForceHandler::new(lines.clone()).run();
+
+        let intersections = Handler::new(lines, SweepLineOptions::degenerate_handling()).run();
+
+        assert!(expected.len() > 100);
+        assert_eq!(intersections, expected);
+    }
+
+    #[test]
+    fn degenerate_handling_should_equal_brute_force_for_rationals() {
+        let lines: Vec<Line2D<BigRational>> = grid_lines()
+            .iter()
+            .map(|line| line.convert().unwrap())
+            .collect();
+        let expected = BruteForceHandler::new(lines.clone()).run();
+
+        let intersections = Handler::new(lines, SweepLineOptions::degenerate_handling()).run();
+
+        assert_eq!(intersections, expected);
+        assert_eq!(
+            intersections.len(),
+            BruteForceHandler::new(grid_lines()).run().len()
+        );
+    }
+
+    #[test]
+    fn exact_rational_result_should_equal_float_result_for_data_file() {
+        let path = Path::new("data/s_1000_1.dat");
+        let float_lines = LineSegments2D::<OrderedFloat<f64>>::from_dat(path)
+            .unwrap()
+            .lines;
+        let rational_lines = LineSegments2D::<BigRational>::from_dat(path).unwrap().lines;
+
+        let float = Handler::new(float_lines, SweepLineOptions::degenerate_handling()).run();
+        let rational = Handler::new(rational_lines, SweepLineOptions::degenerate_handling()).run();
+
+        assert_eq!(rational.len(), float.len());
+    }
+
+    #[test]
+    fn find_any_should_return_none_for_intersection_free_lines() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 4.0, 0.0),
+            Line2D::new(0.0, 1.0, 4.0, 2.0),
+            Line2D::new(5.0, 0.0, 5.0, 3.0),
+            Line2D::new(1.0, 3.0, 1.0, 3.0),
+        ];
+
+        let intersection = Handler::new(lines, SweepLineOptions::degenerate_handling()).find_any();
+
+        assert_eq!(intersection, None);
+    }
+
+    #[test]
+    fn find_any_should_return_leftmost_intersection() {
+        let line1 = Line2D::new(0.0, 0.0, 10.0, 10.0);
+        let line2 = Line2D::new(0.0, 4.0, 4.0, 0.0);
+        let line3 = Line2D::new(6.0, 10.0, 10.0, 6.0);
+        let mut handler = Handler::new(
+            vec![line1, line2, line3],
+            SweepLineOptions::degenerate_handling(),
+        );
+
+        let intersection = handler.find_any();
+
+        assert_eq!(
+            intersection,
+            Some(Intersection::Crossing {
+                line1,
+                line2,
+                point: Point2D::new(2.0, 2.0),
+            })
+        );
+        // the sweep stopped before the second crossing
+        assert!(handler.pending_points() > 0);
+    }
+
+    #[test]
+    fn find_any_should_not_return_a_crossing_found_before_a_left_one() {
+        // the first two lines are neighbours at x = 0 and report their crossing at (5, 5) first
+        let line1 = Line2D::new(0.0, 0.0, 10.0, 10.0);
+        let line2 = Line2D::new(0.0, 10.0, 10.0, 0.0);
+        let line3 = Line2D::new(1.0, 2.0, 3.0, 0.0);
+        let mut handler = Handler::new(
+            vec![line1, line2, line3],
+            SweepLineOptions::degenerate_handling(),
+        );
+
+        let intersection = handler.find_any();
+
+        assert_eq!(
+            intersection,
+            Some(Intersection::Crossing {
+                line1,
+                line2: line3,
+                point: Point2D::new(1.5, 1.5),
+            })
+        );
+    }
+
+    #[test]
+    fn find_any_should_find_intersection_in_data_file() {
+        let lines = LineSegments2D::<OrderedFloat<f64>>::from_dat(Path::new("data/s_1000_1.dat"))
+            .unwrap()
+            .lines;
+        let all = BruteForceHandler::new(lines.clone()).run();
+        let leftmost = all.iter().map(representative_point).min();
+
+        let intersection = Handler::new(lines, SweepLineOptions::degenerate_handling()).find_any();
+
+        let intersection = intersection.unwrap();
+        assert!(all.contains(&intersection));
+        assert_eq!(Some(representative_point(&intersection)), leftmost);
+    }
+
+    #[test]
+    fn bichromatic_mode_should_only_report_intersections_between_layers() {
+        let red1 = Line2D::new(0.0, 0.0, 4.0, 4.0);
+        let red2 = Line2D::new(0.0, 4.0, 4.0, 0.0);
+        let blue = Line2D::new(0.0, 1.0, 4.0, 1.0);
+
+        for options in [
+            SweepLineOptions::panic_disabled(),
+            SweepLineOptions::degenerate_handling(),
+        ] {
+            let intersections =
+                Handler::new_bichromatic(vec![red1, red2], vec![blue], options).run();
+
+            assert_eq!(intersections.len(), 2);
+            assert!(!intersections.iter().any(|i| matches!(
+                i,
+                Intersection::Crossing { line1, line2, .. } if *line1 != blue && *line2 != blue
+            )));
+        }
+    }
+
+    #[test]
+    fn bichromatic_mode_should_equal_brute_force_for_data_file() {
+        let lines = LineSegments2D::<OrderedFloat<f64>>::from_dat(Path::new("data/s_1000_10.dat"))
+            .unwrap()
+            .lines;
+        let (red, blue) = lines.split_at(lines.len() / 2);
+        let layers = Layers::new(red, blue);
+
+        let expected: HashSet<Intersection> = BruteForceHandler::new(lines.clone())
+            .run()
+            .into_iter()
+            .filter(|intersection| layers.reports(intersection))
+            .collect();
+        let brute_force = BruteForceHandler::new_bichromatic(red.to_vec(), blue.to_vec()).run();
+        let sweep_line = Handler::new_bichromatic(
+            red.to_vec(),
+            blue.to_vec(),
+            SweepLineOptions::degenerate_handling(),
+        )
+        .run();
+
+        assert!(!expected.is_empty());
+        assert_eq!(brute_force, expected);
+        assert_eq!(sweep_line, expected);
+    }
+
+    #[test]
+    fn count_should_equal_the_intersections_of_run() {
+        let lines = LineSegments2D::<OrderedFloat<f64>>::from_dat(Path::new("data/s_1000_10.dat"))
+            .unwrap()
+            .lines;
+        let (red, blue) = lines.split_at(lines.len() / 2);
+        let degenerate = SweepLineOptions::degenerate_handling();
+        let handlers = || {
+            [
+                Handler::new(lines.clone(), degenerate),
+                Handler::new_bichromatic(red.to_vec(), blue.to_vec(), degenerate),
+                Handler::new(
+                    vec![
+                        Line2D::new(0.0, 0.0, 4.0, 4.0),
+                        Line2D::new(0.5, 3.5, 3.5, 0.5),
+                        Line2D::new(1.0, 2.0, 3.25, 1.25),
+                    ],
+                    SweepLineOptions::panic_disabled(),
+                ),
+            ]
+        };
+
+        for (mut handler, mut counting) in handlers().into_iter().zip(handlers()) {
+            let intersections = handler.run();
+
+            assert_eq!(counting.count(), IntersectionCounts::of(&intersections));
+        }
+    }
+
+    #[test]
+    fn trace_should_record_every_event_point() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 4.0, 4.0),
+            Line2D::new(0.0, 4.0, 4.0, 0.0),
+        ];
+        let mut handler = Handler::new(lines, SweepLineOptions::degenerate_handling());
+        handler.enable_trace();
+        handler.run();
+
+        let steps = &handler.trace().unwrap().steps;
+        let points: Vec<[f64; 2]> = steps.iter().map(|step| step.point).collect();
+        assert_eq!(
+            points,
+            vec![[0.0, 0.0], [0.0, 4.0], [2.0, 2.0], [4.0, 0.0], [4.0, 4.0]]
+        );
+        assert_eq!(steps[1].events.len(), 1);
+        assert_eq!(steps[1].events[0].rejection, None);
+        assert_eq!(steps[1].status[0], [[0.0, 0.0], [4.0, 4.0]]);
+        assert_eq!(steps[2].lines.len(), 2);
+        assert_eq!(steps[2].status[0], [[0.0, 4.0], [4.0, 0.0]]);
+        assert!(steps[4].status.is_empty());
+    }
+
+    #[test]
+    fn trace_should_record_rejected_intersection_events() {
+        // all lines cross at (2.5, 2.5), which is handled by a single event
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 4.0, 4.0),
+            Line2D::new(1.0, 4.0, 5.0, 0.0),
+            Line2D::new(2.0, 2.5, 4.5, 2.5),
+        ];
+        let mut handler = Handler::new(lines, SweepLineOptions::panic_disabled());
+        handler.enable_trace();
+        handler.run();
+
+        let steps = &handler.trace().unwrap().steps;
+        let kinds: Vec<StepKind> = steps.iter().map(|step| step.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StepKind::Start,
+                StepKind::Start,
+                StepKind::Start,
+                StepKind::Intersection,
+                StepKind::End,
+                StepKind::End,
+                StepKind::End,
+            ]
+        );
+        let rejections: Vec<EventRejection> = steps
+            .iter()
+            .flat_map(|step| &step.events)
+            .filter_map(|event| event.rejection)
+            .collect();
+        assert_eq!(steps[1].events[0].rejection, None);
+        assert_eq!(rejections, vec![EventRejection::AlreadyQueued; 2]);
+    }
 }
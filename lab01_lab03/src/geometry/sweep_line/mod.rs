@@ -1,5 +1,7 @@
-#![allow(dead_code)]
 mod event_queue;
 mod events;
 pub mod handler;
+pub mod strips;
+#[allow(clippy::module_inception)]
 mod sweep_line;
+pub mod trace;
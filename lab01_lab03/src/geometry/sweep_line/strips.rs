@@ -0,0 +1,227 @@
+//! Parallel sweep with degenerate handling: the plane is split along x into vertical strips, which are
+//! swept independently.
+//!
+//! The strip boundaries are quantiles of the left x-coordinates of the lines, so the strips contain
+//! about as many lines each. A line crossing a boundary is added to all strips it overlaps, so every
+//! intersection is found by each strip containing it. An intersection is only kept by the strip owning
+//! its representative x-coordinate, the x-coordinate of its point or the left end of its overlap. Each
+//! strip owns the half-open interval from its left boundary to the next one, so the result equals the
+//! sequential sweep.
+//!
+//! The classic sweep rejects lines and events depending on the whole input, so it always runs sequentially.
+use crate::geometry::counting::IntersectionCounts;
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::parallel;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::sweep_line::handler::{self, Handler, SweepLineOptions};
+use ordered_float::OrderedFloat;
+use std::collections::HashSet;
+
+pub struct StripHandler<T: Scalar = OrderedFloat<f64>> {
+    lines: Vec<Line2D<T>>,
+    /// number of red lines at the start of the lines in the bichromatic mode
+    red_count: Option<usize>,
+    options: SweepLineOptions,
+    threads: usize,
+}
+
+impl<T: Scalar> StripHandler<T> {
+    pub fn new(lines: Vec<Line2D<T>>, options: SweepLineOptions) -> Self {
+        StripHandler {
+            lines,
+            red_count: None,
+            options,
+            threads: 1,
+        }
+    }
+
+    /// Creates a handler which only reports intersections of red with blue lines.
+    pub fn new_bichromatic(
+        red: Vec<Line2D<T>>,
+        blue: Vec<Line2D<T>>,
+        options: SweepLineOptions,
+    ) -> Self {
+        let red_count = red.len();
+        let mut lines = red;
+        lines.extend(blue);
+        StripHandler {
+            red_count: Some(red_count),
+            ..Self::new(lines, options)
+        }
+    }
+
+    /// Sweeps one strip per thread, only with degenerate handling.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn run(&mut self) -> HashSet<Intersection<T>> {
+        if self.threads <= 1 || !self.options.handle_degenerate {
+            return self.handler(self.lines.clone(), self.red_count).run();
+        }
+
+        let boundaries = self.boundaries();
+        parallel::collect_blocks(
+            boundaries.len() + 1,
+            1,
+            self.threads,
+            |strips, found: &mut HashSet<_>| {
+                for strip in strips {
+                    let left = strip.checked_sub(1).map(|s| &boundaries[s]);
+                    let right = boundaries.get(strip);
+                    found.extend(self.sweep_strip(left, right));
+                }
+            },
+        )
+    }
+
+    /// Counts the intersections [run](Self::run) would return without storing them, each strip
+    /// counts the intersections it owns, see [Handler::count].
+    pub fn count(&mut self) -> IntersectionCounts {
+        if self.threads <= 1 || !self.options.handle_degenerate {
+            return self.handler(self.lines.clone(), self.red_count).count();
+        }
+
+        let boundaries = self.boundaries();
+        parallel::collect_blocks::<T, _>(
+            boundaries.len() + 1,
+            1,
+            self.threads,
+            |strips, counts: &mut IntersectionCounts| {
+                for strip in strips {
+                    let left = strip.checked_sub(1).map(|s| &boundaries[s]);
+                    let right = boundaries.get(strip);
+                    let (lines, red_count) = self.strip_lines(left, right);
+                    counts.merge(
+                        self.handler(lines, red_count)
+                            .count_within(left.map(Scalar::to_field), right.map(Scalar::to_field)),
+                    );
+                }
+            },
+        )
+    }
+
+    /// Left boundaries of all strips but the first, ascending and distinct.
+    fn boundaries(&self) -> Vec<T> {
+        let mut x_min: Vec<&T> = self
+            .lines
+            .iter()
+            .map(|line| std::cmp::min(&line.start.x, &line.end.x))
+            .collect();
+        x_min.sort();
+        let mut boundaries: Vec<T> = (1..self.threads)
+            .filter_map(|strip| x_min.get(strip * x_min.len() / self.threads))
+            .map(|&x| x.clone())
+            .collect();
+        boundaries.dedup();
+        boundaries
+    }
+
+    /// Intersections with a representative x-coordinate in [left, right), unbounded if None.
+    fn sweep_strip(&self, left: Option<&T>, right: Option<&T>) -> HashSet<Intersection<T>> {
+        let (lines, red_count) = self.strip_lines(left, right);
+        let left = left.map(Scalar::to_field);
+        let right = right.map(Scalar::to_field);
+        let mut intersections = self.handler(lines, red_count).run();
+        intersections.retain(|intersection| {
+            let x = handler::representative_point(intersection).x;
+            left.as_ref().is_none_or(|left| &x >= left)
+                && right.as_ref().is_none_or(|right| &x < right)
+        });
+        intersections
+    }
+
+    /// Lines overlapping the strip from left to right with the number of red lines among them.
+    fn strip_lines(&self, left: Option<&T>, right: Option<&T>) -> (Vec<Line2D<T>>, Option<usize>) {
+        let overlaps = |line: &Line2D<T>| {
+            let (x_min, x_max) = match line.start.x <= line.end.x {
+                true => (&line.start.x, &line.end.x),
+                false => (&line.end.x, &line.start.x),
+            };
+            left.is_none_or(|left| x_max >= left) && right.is_none_or(|right| x_min < right)
+        };
+        let mut lines = Vec::new();
+        let mut red_count = self.red_count.map(|_| 0);
+        for (i, line) in self.lines.iter().enumerate() {
+            if overlaps(line) {
+                lines.push(line.clone());
+                if self.red_count.is_some_and(|red| i < red) {
+                    red_count = red_count.map(|count| count + 1);
+                }
+            }
+        }
+        (lines, red_count)
+    }
+
+    fn handler(&self, mut lines: Vec<Line2D<T>>, red_count: Option<usize>) -> Handler<T> {
+        match red_count {
+            Some(red_count) => {
+                let blue = lines.split_off(red_count);
+                Handler::new_bichromatic(lines, blue, self.options)
+            }
+            None => Handler::new(lines, self.options),
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::brute_force::handler::BruteForceHandler;
+    use crate::geometry::generator::degenerate_lines;
+
+    #[test]
+    fn strips_should_find_the_intersections_of_the_sequential_sweep() {
+        let lines = degenerate_lines(1500, 7);
+        let options = SweepLineOptions::degenerate_handling();
+        let expected = Handler::new(lines.clone(), options).run();
+
+        for threads in [2, 3, 8] {
+            let actual = StripHandler::new(lines.clone(), options)
+                .with_threads(threads)
+                .run();
+
+            assert_eq!(actual, expected, "{} threads", threads);
+        }
+        assert_eq!(expected, BruteForceHandler::new(lines).run());
+    }
+
+    #[test]
+    fn bichromatic_strips_should_only_report_red_blue_intersections() {
+        let mut red = degenerate_lines(1500, 7);
+        let blue = red.split_off(700);
+        let expected = BruteForceHandler::new_bichromatic(red.clone(), blue.clone()).run();
+
+        let actual =
+            StripHandler::new_bichromatic(red, blue, SweepLineOptions::degenerate_handling())
+                .with_threads(4)
+                .run();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn lines_on_a_boundary_should_be_reported_once() {
+        // all lines start at x = 1, so there is a single boundary there
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 1.0, 1.0),
+            Line2D::new(1.0, 1.0, 2.0, 0.0),
+            Line2D::new(1.0, 0.0, 1.0, 2.0),
+            Line2D::new(1.0, 1.0, 3.0, 1.0),
+            Line2D::new(1.0, 1.0, 3.0, 1.0),
+        ];
+        let options = SweepLineOptions::degenerate_handling();
+
+        let actual = StripHandler::new(lines.clone(), options)
+            .with_threads(4)
+            .run();
+
+        assert_eq!(actual, BruteForceHandler::new(lines).run());
+    }
+}
@@ -1,39 +1,41 @@
 use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
 use ordered_float::OrderedFloat;
 use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
-struct OrderedLine {
-    value: Line2D,
+struct OrderedLine<T> {
+    value: Line2D<T>,
     x: Rc<RefCell<f64>>,
 }
 
-impl OrderedLine {
-    pub fn new(value: Line2D, x: Rc<RefCell<f64>>) -> Self {
+impl<T> OrderedLine<T> {
+    pub fn new(value: Line2D<T>, x: Rc<RefCell<f64>>) -> Self {
         OrderedLine { value, x }
     }
 }
 
-impl PartialEq for OrderedLine {
+impl<T: Scalar> PartialEq for OrderedLine<T> {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
     }
 }
 
-impl Eq for OrderedLine {}
+impl<T: Scalar> Eq for OrderedLine<T> {}
 
-impl PartialOrd for OrderedLine {
+impl<T: Scalar> PartialOrd for OrderedLine<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for OrderedLine {
+impl<T: Scalar> Ord for OrderedLine<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        let y_self = self.value.y_at(self.x.borrow().clone());
-        let y_other = other.value.y_at(other.x.borrow().clone());
+        let y_self = self.value.y_at(*self.x.borrow());
+        let y_other = other.value.y_at(*other.x.borrow());
 
         if y_self.is_none() {
             panic!(
@@ -55,104 +57,335 @@ impl Ord for OrderedLine {
 }
 
 #[derive(Debug)]
-pub struct Neighbors {
-    pub bigger: Option<Line2D>,
-    pub smaller: Option<Line2D>,
+pub struct Neighbors<T = OrderedFloat<f64>> {
+    pub bigger: Option<Line2D<T>>,
+    pub smaller: Option<Line2D<T>>,
 }
 
+/// Node of the treap backing the [SweepLine].
+/// Nodes live in an arena and reference each other by index, the parent link allows
+/// neighbor lookups without comparing lines again.
 #[derive(Debug)]
-pub struct SweepLine {
-    lines: Vec<OrderedLine>,
-    x: Rc<RefCell<f64>>,
+struct Node<T> {
+    line: OrderedLine<T>,
+    priority: u64,
+    left: Option<usize>,
+    right: Option<usize>,
+    parent: Option<usize>,
 }
 
 /// Implementation of SweepLine structure
-/// Current implementation is not optimal as it sorts the lines every time x is updated
-impl SweepLine {
+/// The lines are kept in a treap (randomized balanced binary search tree) ordered by their
+/// y-coordinate at the current x of the sweep line.
+/// Complexity: insert, remove, neighbor lookup and swap are O(log n) expected, set_x is O(1)
+///
+/// Changing x does not reorder the tree. Instead, lines that cross each other have to be
+/// exchanged explicitly with [SweepLine::swap] when the sweep line passes their intersection.
+/// The order at x is approximated with floating point arithmetic regardless of the scalar,
+/// use [SweepLine::add_by] to insert lines by an exact order.
+#[derive(Debug)]
+pub struct SweepLine<T = OrderedFloat<f64>> {
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    index: HashMap<Line2D<T>, usize>,
+    x: Rc<RefCell<f64>>,
+    seed: u64,
+}
+
+impl<T: Scalar> SweepLine<T> {
     pub fn new() -> Self {
         SweepLine {
-            lines: Vec::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            index: HashMap::new(),
             x: Rc::new(RefCell::new(f64::NEG_INFINITY)),
+            seed: 0x2545_F491_4F6C_DD1D,
         }
     }
 
     pub fn set_x(&mut self, x: f64) {
         *self.x.borrow_mut() = x;
-        self.lines.sort();
     }
 
     pub fn get_x(&self) -> OrderedFloat<f64> {
         OrderedFloat::from(*self.x.borrow())
     }
 
-    pub fn add(&mut self, line: Line2D) {
-        self.lines.push(OrderedLine::new(line, self.x.clone()));
-        self.lines.sort();
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.index.len()
     }
 
-    pub fn remove(&mut self, line: &Line2D) -> bool {
-        if let Some(ordered_line) = self.find_ordered_line(line).cloned() {
-            // get index of ordered line
-            let index = self
-                .lines
-                .iter()
-                .position(|ol| ol == &ordered_line)
-                .unwrap();
-            self.lines.remove(index);
-            self.lines.sort();
-            true
-        } else {
-            false
+    /// Inserts the line according to its y-coordinate at the current x.
+    /// Lines that are already part of the sweep line are ignored.
+    pub fn add(&mut self, line: Line2D<T>) {
+        let ordered_line = OrderedLine::new(line.clone(), Rc::clone(&self.x));
+        self.insert_with(line, |other| ordered_line < *other);
+    }
+
+    /// Inserts the line according to the given comparison instead of the y-coordinate at x.
+    /// The comparison has to be consistent with the order of the lines that are already inserted.
+    pub fn add_by<F>(&mut self, line: Line2D<T>, compare: F)
+    where
+        F: Fn(&Line2D<T>, &Line2D<T>) -> Ordering,
+    {
+        let inserted = line.clone();
+        self.insert_with(line, |other| {
+            compare(&inserted, &other.value) == Ordering::Less
+        });
+    }
+
+    pub fn remove(&mut self, line: &Line2D<T>) -> bool {
+        let id = match self.index.remove(line) {
+            Some(id) => id,
+            None => return false,
+        };
+
+        // rotate the node down until it has at most one child
+        while let (Some(left), Some(right)) = (self.nodes[id].left, self.nodes[id].right) {
+            if self.nodes[left].priority > self.nodes[right].priority {
+                self.rotate_up(left);
+            } else {
+                self.rotate_up(right);
+            }
+        }
+
+        let child = self.nodes[id].left.or(self.nodes[id].right);
+        let parent = self.nodes[id].parent;
+        if let Some(c) = child {
+            self.nodes[c].parent = parent;
         }
+        self.replace_child(parent, id, child);
+        self.free.push(id);
+        true
     }
 
-    pub fn get_neighbors(&self, line: &Line2D) -> Neighbors {
-        let ordered_line = OrderedLine::new(line.clone(), Rc::clone(&self.x));
+    /// Exchanges the positions of two lines, which is required after passing their intersection.
+    /// Returns false if one of the lines is not part of the sweep line.
+    #[cfg(test)]
+    pub fn swap(&mut self, line1: &Line2D<T>, line2: &Line2D<T>) -> bool {
+        self.reverse(&[line1.clone(), line2.clone()])
+    }
+
+    /// Reverses the order of consecutive lines, which is required after passing their common intersection.
+    /// Returns false if one of the lines is not part of the sweep line.
+    pub fn reverse(&mut self, lines: &[Line2D<T>]) -> bool {
+        let ids: Option<Vec<usize>> = lines
+            .iter()
+            .map(|line| self.index.get(line).copied())
+            .collect();
+        let ids = match ids {
+            Some(ids) => ids,
+            None => return false,
+        };
+
+        for (&id, line) in ids.iter().zip(lines.iter().rev()) {
+            self.nodes[id].line.value = line.clone();
+            self.index.insert(line.clone(), id);
+        }
+        true
+    }
+
+    pub fn get_neighbors(&self, line: &Line2D<T>) -> Neighbors<T> {
+        match self.index.get(line) {
+            Some(&id) => Neighbors {
+                smaller: self
+                    .predecessor(id)
+                    .map(|p| self.nodes[p].line.value.clone()),
+                bigger: self.successor(id).map(|s| self.nodes[s].line.value.clone()),
+            },
+            None => Neighbors {
+                smaller: None,
+                bigger: None,
+            },
+        }
+    }
+
+    pub fn contains(&self, line: &Line2D<T>) -> bool {
+        self.index.contains_key(line)
+    }
 
-        let mut smaller = None;
-        let mut bigger = None;
-        let mut found = false;
+    /// Returns the lines in ascending order. Complexity: O(n)
+    pub fn get_sorted_lines(&self) -> Vec<Line2D<T>> {
+        let mut lines = Vec::with_capacity(self.index.len());
+        let mut current = self.root.map(|root| self.leftmost(root));
+        while let Some(id) = current {
+            lines.push(self.nodes[id].line.value.clone());
+            current = self.successor(id);
+        }
+        lines
+    }
 
-        for ol in self.lines.iter() {
-            if ol == &ordered_line {
-                found = true;
-                continue;
+    /// Returns the first line for which the predicate holds.
+    /// The predicate has to be monotone regarding the order of the lines (false, ..., false, true, ..., true).
+    pub fn first_where<P>(&self, predicate: P) -> Option<Line2D<T>>
+    where
+        P: Fn(&Line2D<T>) -> bool,
+    {
+        let mut result = None;
+        let mut current = self.root;
+        while let Some(id) = current {
+            if predicate(&self.nodes[id].line.value) {
+                result = Some(id);
+                current = self.nodes[id].left;
+            } else {
+                current = self.nodes[id].right;
             }
+        }
+        result.map(|id| self.nodes[id].line.value.clone())
+    }
 
-            if found {
-                bigger = Some(ol.clone().value);
-                break;
+    pub fn last(&self) -> Option<Line2D<T>> {
+        self.root
+            .map(|root| self.nodes[self.rightmost(root)].line.value.clone())
+    }
+
+    fn insert_with<L>(&mut self, line: Line2D<T>, is_less: L)
+    where
+        L: Fn(&OrderedLine<T>) -> bool,
+    {
+        if self.index.contains_key(&line) {
+            return;
+        }
+
+        let mut parent = None;
+        let mut is_left = false;
+        let mut current = self.root;
+        while let Some(id) = current {
+            parent = Some(id);
+            is_left = is_less(&self.nodes[id].line);
+            current = if is_left {
+                self.nodes[id].left
             } else {
-                smaller = Some(ol.clone().value);
+                self.nodes[id].right
+            };
+        }
+
+        let priority = self.next_priority();
+        let id = self.allocate(Node {
+            line: OrderedLine::new(line.clone(), Rc::clone(&self.x)),
+            priority,
+            left: None,
+            right: None,
+            parent,
+        });
+        match parent {
+            None => self.root = Some(id),
+            Some(p) if is_left => self.nodes[p].left = Some(id),
+            Some(p) => self.nodes[p].right = Some(id),
+        }
+        self.index.insert(line, id);
+
+        // restore the heap property of the priorities
+        while let Some(p) = self.nodes[id].parent {
+            if self.nodes[p].priority >= priority {
+                break;
             }
+            self.rotate_up(id);
         }
+    }
 
-        Neighbors { smaller, bigger }
+    fn allocate(&mut self, node: Node<T>) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
     }
 
-    pub fn contains(&self, line: &Line2D) -> bool {
-        let ordered_line = OrderedLine::new(line.clone(), self.x.clone());
-        self.lines.contains(&ordered_line)
+    /// xorshift64 is sufficient to keep the treap balanced in expectation
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
     }
 
-    pub fn print_lines(&self) {
-        println!("Line Order @ {}", self.x.borrow());
-        for ol in &self.lines {
-            println!(
-                "\t{} @ {} = {}",
-                ol.value,
-                self.x.borrow(),
-                ol.value.y_at(self.x.borrow().clone()).unwrap()
-            );
+    /// Rotates the node above its parent while keeping the in-order sequence intact.
+    fn rotate_up(&mut self, id: usize) {
+        let parent = self.nodes[id].parent.expect("Root can't be rotated up");
+        let grand_parent = self.nodes[parent].parent;
+
+        if self.nodes[parent].left == Some(id) {
+            let inner = self.nodes[id].right;
+            self.nodes[parent].left = inner;
+            self.nodes[id].right = Some(parent);
+            if let Some(inner) = inner {
+                self.nodes[inner].parent = Some(parent);
+            }
+        } else {
+            let inner = self.nodes[id].left;
+            self.nodes[parent].right = inner;
+            self.nodes[id].left = Some(parent);
+            if let Some(inner) = inner {
+                self.nodes[inner].parent = Some(parent);
+            }
+        }
+
+        self.nodes[parent].parent = Some(id);
+        self.nodes[id].parent = grand_parent;
+        self.replace_child(grand_parent, parent, Some(id));
+    }
+
+    fn replace_child(&mut self, parent: Option<usize>, old: usize, new: Option<usize>) {
+        match parent {
+            None => self.root = new,
+            Some(p) if self.nodes[p].left == Some(old) => self.nodes[p].left = new,
+            Some(p) => self.nodes[p].right = new,
         }
     }
 
-    pub fn get_sorted_lines(&self) -> Vec<Line2D> {
-        self.lines.iter().cloned().map(|line| line.value).collect()
+    fn leftmost(&self, mut id: usize) -> usize {
+        while let Some(left) = self.nodes[id].left {
+            id = left;
+        }
+        id
     }
 
-    fn find_ordered_line(&self, line: &Line2D) -> Option<&OrderedLine> {
-        self.lines.iter().find(|ol| ol.value == *line)
+    fn rightmost(&self, mut id: usize) -> usize {
+        while let Some(right) = self.nodes[id].right {
+            id = right;
+        }
+        id
+    }
+
+    fn successor(&self, mut id: usize) -> Option<usize> {
+        if let Some(right) = self.nodes[id].right {
+            return Some(self.leftmost(right));
+        }
+        while let Some(parent) = self.nodes[id].parent {
+            if self.nodes[parent].left == Some(id) {
+                return Some(parent);
+            }
+            id = parent;
+        }
+        None
+    }
+
+    fn predecessor(&self, mut id: usize) -> Option<usize> {
+        if let Some(left) = self.nodes[id].left {
+            return Some(self.rightmost(left));
+        }
+        while let Some(parent) = self.nodes[id].parent {
+            if self.nodes[parent].right == Some(id) {
+                return Some(parent);
+            }
+            id = parent;
+        }
+        None
+    }
+}
+
+impl<T: Scalar> Default for SweepLine<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -218,9 +451,9 @@ mod tests {
         let mut sweep_line = SweepLine::new();
         sweep_line.set_x(0.0);
 
-        sweep_line.add(line1.clone());
-        sweep_line.add(line2.clone());
-        sweep_line.add(line3.clone());
+        sweep_line.add(line1);
+        sweep_line.add(line2);
+        sweep_line.add(line3);
 
         let sorted_lines = sweep_line.get_sorted_lines();
         assert_eq!(sorted_lines[0], line1);
@@ -228,18 +461,21 @@ mod tests {
         assert_eq!(sorted_lines[2], line3);
 
         sweep_line.set_x(4.1);
+        assert!(sweep_line.swap(&line1, &line2));
         let sorted_lines = sweep_line.get_sorted_lines();
         assert_eq!(sorted_lines[0], line2);
         assert_eq!(sorted_lines[1], line1);
         assert_eq!(sorted_lines[2], line3);
 
         sweep_line.set_x(5.1);
+        assert!(sweep_line.swap(&line1, &line3));
         let sorted_lines = sweep_line.get_sorted_lines();
         assert_eq!(sorted_lines[0], line2);
         assert_eq!(sorted_lines[1], line3);
         assert_eq!(sorted_lines[2], line1);
 
         sweep_line.set_x(6.1);
+        assert!(sweep_line.swap(&line2, &line3));
         let sorted_lines = sweep_line.get_sorted_lines();
         assert_eq!(sorted_lines[0], line3);
         assert_eq!(sorted_lines[1], line2);
@@ -295,4 +531,73 @@ mod tests {
         let neighbors3 = sweepline.get_neighbors(&line3);
         assert_eq!(neighbors3.smaller, Some(line1));
     }
+
+    #[test]
+    fn swap_should_exchange_neighbors() {
+        let line1 = Line2D::new(0.0, 0.0, 2.0, 0.0);
+        let line2 = Line2D::new(0.0, 1.0, 2.0, 1.0);
+        let line3 = Line2D::new(0.0, 2.0, 2.0, 2.0);
+        let unknown = Line2D::new(0.0, 3.0, 2.0, 3.0);
+        let mut sweepline = SweepLine::new();
+        sweepline.set_x(0.0);
+
+        sweepline.add(line1);
+        sweepline.add(line2);
+        sweepline.add(line3);
+
+        assert!(sweepline.swap(&line1, &line2));
+        assert!(!sweepline.swap(&line1, &unknown));
+        assert_eq!(sweepline.get_sorted_lines(), vec![line2, line1, line3]);
+
+        let neighbors = sweepline.get_neighbors(&line1);
+        assert_eq!(neighbors.smaller, Some(line2));
+        assert_eq!(neighbors.bigger, Some(line3));
+
+        assert!(sweepline.remove(&line1));
+        assert_eq!(sweepline.get_neighbors(&line2).bigger, Some(line3));
+    }
+
+    #[test]
+    fn should_stay_sorted_for_many_lines() {
+        let mut sweepline = SweepLine::new();
+        sweepline.set_x(0.0);
+
+        // insert in a scrambled order
+        let lines: Vec<Line2D> = (0..1000)
+            .map(|i| (i * 7919) % 1000)
+            .map(|i| Line2D::new(0.0, i as f64, 1.0, i as f64))
+            .collect();
+        for line in &lines {
+            sweepline.add(*line);
+        }
+        for line in lines.iter().filter(|line| *line.start.y % 3.0 == 0.0) {
+            assert!(sweepline.remove(line));
+        }
+
+        let sorted_lines = sweepline.get_sorted_lines();
+        assert_eq!(sorted_lines.len(), sweepline.len());
+        assert_eq!(sorted_lines.len(), 666);
+        assert!(sorted_lines.windows(2).all(|w| w[0].start.y < w[1].start.y));
+    }
+
+    #[test]
+    fn reverse_should_reverse_consecutive_lines() {
+        let lines: Vec<Line2D> = (0..5)
+            .map(|i| Line2D::new(0.0, i as f64, 2.0, i as f64))
+            .collect();
+        let mut sweepline = SweepLine::new();
+        sweepline.set_x(0.0);
+        for line in &lines {
+            sweepline.add(*line);
+        }
+
+        assert!(sweepline.reverse(&lines[1..4]));
+
+        assert_eq!(
+            sweepline.get_sorted_lines(),
+            vec![lines[0], lines[3], lines[2], lines[1], lines[4]]
+        );
+        assert_eq!(sweepline.get_neighbors(&lines[1]).bigger, Some(lines[4]));
+        assert_eq!(sweepline.get_neighbors(&lines[3]).smaller, Some(lines[0]));
+    }
 }
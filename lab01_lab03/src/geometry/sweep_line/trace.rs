@@ -0,0 +1,430 @@
+//! Trace of the sweep line: one step per processed event with the order of the sweep line after
+//! the event and the intersection events added or rejected while handling it.
+//!
+//! Lines and points are recorded as floats, so traces of all scalars can be stored as JSON lines
+//! (one step per row) and replayed as SVG frames to find the step where the sweep line diverges
+//! from the brute force result.
+use crate::geometry::dat::{ParseError, ParseErrorKind, ReadError};
+use crate::geometry::line::Line2D;
+use crate::geometry::point::Point2D;
+use crate::geometry::scalar::Scalar;
+use crate::geometry::svg::{RenderOptions, Viewport, Window};
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Endpoints of a line
+pub type Segment = [[f64; 2]; 2];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    Start,
+    End,
+    Intersection,
+    /// All lines meeting at a point, handled at once with degenerate handling
+    EventPoint,
+}
+
+impl StepKind {
+    fn name(&self) -> &'static str {
+        match self {
+            StepKind::Start => "Start",
+            StepKind::End => "End",
+            StepKind::Intersection => "Intersection",
+            StepKind::EventPoint => "EventPoint",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            StepKind::Start,
+            StepKind::End,
+            StepKind::Intersection,
+            StepKind::EventPoint,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == name)
+    }
+}
+
+/// Reason why no intersection event is added for a pair of neighboring lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventRejection {
+    /// The point lies left of the sweep line
+    AlreadyProcessed,
+    AlreadyQueued,
+    /// Another pair of lines crosses at the same point, handled by the same event
+    SamePoint,
+    /// Another event or endpoint has the same x-coordinate
+    DuplicateX,
+    Touching,
+    Overlap,
+}
+
+impl EventRejection {
+    fn name(&self) -> &'static str {
+        match self {
+            EventRejection::AlreadyProcessed => "AlreadyProcessed",
+            EventRejection::AlreadyQueued => "AlreadyQueued",
+            EventRejection::SamePoint => "SamePoint",
+            EventRejection::DuplicateX => "DuplicateX",
+            EventRejection::Touching => "Touching",
+            EventRejection::Overlap => "Overlap",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            EventRejection::AlreadyProcessed,
+            EventRejection::AlreadyQueued,
+            EventRejection::SamePoint,
+            EventRejection::DuplicateX,
+            EventRejection::Touching,
+            EventRejection::Overlap,
+        ]
+        .into_iter()
+        .find(|rejection| rejection.name() == name)
+    }
+}
+
+/// Intersection of two neighboring lines found while handling an event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedEvent {
+    pub point: [f64; 2],
+    pub lines: [Segment; 2],
+    /// None if the event was added to the queue
+    pub rejection: Option<EventRejection>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceStep {
+    /// 0-based number of the step
+    pub step: usize,
+    pub kind: StepKind,
+    pub point: [f64; 2],
+    /// Lines of the event: the starting or ending line, the crossing pair or all lines meeting at the point
+    pub lines: Vec<Segment>,
+    /// Lines of the sweep line after the event in ascending order
+    pub status: Vec<Segment>,
+    pub events: Vec<QueuedEvent>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    /// events found since the last step
+    pending: Vec<QueuedEvent>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace::default()
+    }
+
+    /// Records an intersection event found while handling the current event.
+    pub(crate) fn queue<T: Scalar>(
+        &mut self,
+        point: &Point2D<T::Field>,
+        line1: &Line2D<T>,
+        line2: &Line2D<T>,
+        rejection: Option<EventRejection>,
+    ) {
+        self.pending.push(QueuedEvent {
+            point: position(point),
+            lines: [segment(line1), segment(line2)],
+            rejection,
+        });
+    }
+
+    /// Records the handled event with the events found while handling it.
+    pub(crate) fn step<T: Scalar>(
+        &mut self,
+        kind: StepKind,
+        point: &Point2D<T::Field>,
+        lines: &[Line2D<T>],
+        status: &[Line2D<T>],
+    ) {
+        self.steps.push(TraceStep {
+            step: self.steps.len(),
+            kind,
+            point: position(point),
+            lines: lines.iter().map(segment).collect(),
+            status: status.iter().map(segment).collect(),
+            events: std::mem::take(&mut self.pending),
+        });
+    }
+
+    /// Writes one JSON object per step and row.
+    pub fn write_json_lines<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for step in &self.steps {
+            let events: Vec<Value> = step
+                .events
+                .iter()
+                .map(|event| {
+                    json!({
+                        "point": event.point,
+                        "lines": event.lines,
+                        "rejection": event.rejection.map(|rejection| rejection.name()),
+                    })
+                })
+                .collect();
+            let row = json!({
+                "step": step.step,
+                "kind": step.kind.name(),
+                "point": step.point,
+                "lines": step.lines,
+                "status": step.status,
+                "events": events,
+            });
+            writeln!(writer, "{}", row)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a trace written by [Trace::write_json_lines], the path is only used for the errors.
+    pub fn read_json_lines<R: BufRead>(reader: R, path: &Path) -> Result<Trace, ReadError> {
+        let mut steps = Vec::new();
+        for (index, row) in reader.lines().enumerate() {
+            let row = row.map_err(|source| ReadError::Io {
+                file: path.to_path_buf(),
+                source,
+            })?;
+            if row.trim().is_empty() {
+                continue;
+            }
+            let error = |column: usize, message: String| {
+                ReadError::Parse(ParseError {
+                    file: path.to_path_buf(),
+                    line: index + 1,
+                    column,
+                    pointer: None,
+                    token: String::new(),
+                    kind: ParseErrorKind::Json(message),
+                })
+            };
+            let value: Value =
+                serde_json::from_str(&row).map_err(|e| error(e.column(), e.to_string()))?;
+            let step =
+                parse_step(&value).ok_or_else(|| error(1, "not a trace step".to_string()))?;
+            steps.push(step);
+        }
+        Ok(Trace {
+            steps,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Renders the step as SVG: all lines of the trace in light grey, the sweep line at the event
+    /// point, the lines of the sweep line numbered in their order, the lines of the event in black
+    /// and the intersection events added (green) or rejected (red) while handling it.
+    pub fn render_step(&self, step: usize, options: &RenderOptions) -> Option<String> {
+        let current = self.steps.get(step)?;
+        let mut all_lines: Vec<Segment> = self
+            .steps
+            .iter()
+            .flat_map(|step| step.lines.iter().chain(&step.status))
+            .copied()
+            .collect();
+        all_lines.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        all_lines.dedup();
+
+        let window = options.window.unwrap_or_else(|| {
+            let lines: Vec<Line2D> = all_lines
+                .iter()
+                .map(|[[x1, y1], [x2, y2]]| Line2D::new(*x1, *y1, *x2, *y2))
+                .collect();
+            Window::around(&lines)
+        });
+        let viewport = Viewport::new(window, options.width);
+
+        let mut svg = viewport.header();
+        for line in &all_lines {
+            svg.push_str(&viewport.line(line, "#dddddd", 1.0, "", ""));
+        }
+        let [x, y] = current.point;
+        let sweep = [[x, window.y_min], [x, window.y_max]];
+        svg.push_str(&viewport.line(
+            &sweep,
+            "#d62728",
+            1.0,
+            " stroke-dasharray=\"4 4\"",
+            "sweep line",
+        ));
+        for (i, line) in current.status.iter().enumerate() {
+            let title = format!("{}: {:?}", i, line);
+            svg.push_str(&viewport.line(line, "#1f77b4", 1.5, "", &title));
+            let (px, py) = viewport.pixel(line_at(line, x));
+            writeln!(
+                svg,
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"10\" fill=\"#1f77b4\">{}</text>",
+                px + 3.0,
+                py - 3.0,
+                i
+            )
+            .unwrap();
+        }
+        for line in &current.lines {
+            svg.push_str(&viewport.line(line, "#000000", 3.0, "", &format!("{:?}", line)));
+        }
+        svg.push_str(&viewport.circle([x, y], "#000000", "event point"));
+        for event in &current.events {
+            let (colour, title) = match event.rejection {
+                None => ("#2ca02c", "added".to_string()),
+                Some(rejection) => ("#d62728", format!("rejected: {}", rejection.name())),
+            };
+            svg.push_str(&viewport.circle(event.point, colour, &title));
+        }
+
+        let added = current
+            .events
+            .iter()
+            .filter(|event| event.rejection.is_none())
+            .count();
+        writeln!(
+            svg,
+            "<text x=\"8\" y=\"16\" font-family=\"sans-serif\" font-size=\"12\">Step {} of {}: {} at ({}, {}), {} lines on the sweep line, {} events added, {} rejected</text>",
+            current.step,
+            self.steps.len(),
+            current.kind.name(),
+            x,
+            y,
+            current.status.len(),
+            added,
+            current.events.len() - added
+        )
+        .unwrap();
+        svg.push_str("</svg>\n");
+        Some(svg)
+    }
+}
+
+fn position<F: Scalar>(point: &Point2D<F>) -> [f64; 2] {
+    [point.x.to_f64(), point.y.to_f64()]
+}
+
+fn segment<T: Scalar>(line: &Line2D<T>) -> Segment {
+    [position(&line.start), position(&line.end)]
+}
+
+/// Point of the line at x, its lower endpoint if it is vertical.
+fn line_at([[x1, y1], [x2, y2]]: &Segment, x: f64) -> [f64; 2] {
+    if x1 == x2 {
+        return [*x1, y1.min(*y2)];
+    }
+    let x = x.clamp(x1.min(*x2), x1.max(*x2));
+    [x, y1 + (x - x1) * (y2 - y1) / (x2 - x1)]
+}
+
+fn parse_step(value: &Value) -> Option<TraceStep> {
+    let events = value
+        .get("events")?
+        .as_array()?
+        .iter()
+        .map(|event| {
+            let rejection = match event.get("rejection")? {
+                Value::Null => None,
+                rejection => Some(EventRejection::from_name(rejection.as_str()?)?),
+            };
+            let lines = parse_segments(event.get("lines")?)?;
+            Some(QueuedEvent {
+                point: parse_position(event.get("point")?)?,
+                lines: [*lines.first()?, *lines.get(1)?],
+                rejection,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(TraceStep {
+        step: value.get("step")?.as_u64()? as usize,
+        kind: StepKind::from_name(value.get("kind")?.as_str()?)?,
+        point: parse_position(value.get("point")?)?,
+        lines: parse_segments(value.get("lines")?)?,
+        status: parse_segments(value.get("status")?)?,
+        events,
+    })
+}
+
+fn parse_segments(value: &Value) -> Option<Vec<Segment>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|segment| match segment.as_array()?.as_slice() {
+            [start, end] => Some([parse_position(start)?, parse_position(end)?]),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Non-finite coordinates are written as null.
+fn parse_position(value: &Value) -> Option<[f64; 2]> {
+    let coordinate = |value: &Value| match value {
+        Value::Null => Some(f64::NAN),
+        value => value.as_f64(),
+    };
+    match value.as_array()?.as_slice() {
+        [x, y] => Some([coordinate(x)?, coordinate(y)?]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::sweep_line::handler::{Handler, SweepLineOptions};
+
+    fn trace() -> Trace {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 4.0, 4.0),
+            Line2D::new(0.0, 4.0, 4.0, 0.0),
+            Line2D::new(1.0, 0.0, 1.0, 4.0),
+        ];
+        let mut handler = Handler::new(lines, SweepLineOptions::degenerate_handling());
+        handler.enable_trace();
+        handler.run();
+        handler.trace().unwrap().clone()
+    }
+
+    #[test]
+    fn json_lines_should_round_trip() {
+        let trace = trace();
+
+        let mut json = Vec::new();
+        trace.write_json_lines(&mut json).unwrap();
+        let read = Trace::read_json_lines(json.as_slice(), Path::new("trace.jsonl")).unwrap();
+
+        assert_eq!(
+            json.iter().filter(|&&byte| byte == b'\n').count(),
+            trace.steps.len()
+        );
+        assert_eq!(read, trace);
+    }
+
+    #[test]
+    fn malformed_rows_should_be_reported() {
+        let content = "{\"step\": 0}\n";
+
+        let result = Trace::read_json_lines(content.as_bytes(), Path::new("trace.jsonl"));
+
+        assert!(matches!(
+            result,
+            Err(ReadError::Parse(ParseError { line: 1, .. }))
+        ));
+    }
+
+    #[test]
+    fn steps_should_render_to_svg() {
+        let trace = trace();
+
+        let frame = trace.render_step(3, &RenderOptions::default()).unwrap();
+
+        assert!(frame.starts_with("<svg"));
+        assert!(frame.contains(&format!("Step 3 of {}: EventPoint", trace.steps.len())));
+        assert_eq!(
+            frame.matches("<text").count(),
+            trace.steps[3].status.len() + 1
+        );
+        assert!(trace
+            .render_step(trace.steps.len(), &RenderOptions::default())
+            .is_none());
+    }
+}
@@ -0,0 +1,215 @@
+//! Comparison of the intersections found by different algorithms.
+//!
+//! The results are normalized before they are compared: an intersection is identified by the kind
+//! and the unordered pair of its lines, independent of the order the algorithm intersected the
+//! lines in, of the direction of the lines and of rounding differences of the intersection point. The kinds of overlaps aren't
+//! distinguished, as the geo library reports all overlaps as partial overlaps.
+use crate::geometry::intersection::Intersection;
+use crate::geometry::line::Line2D;
+use crate::geometry::scalar::Scalar;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind {
+    Crossing,
+    Touching,
+    /// Partial, contained and identical overlaps
+    Overlap,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Crossing => write!(f, "Crossing"),
+            Kind::Touching => write!(f, "Touching"),
+            Kind::Overlap => write!(f, "Overlap"),
+        }
+    }
+}
+
+/// Intersection of a pair of lines with sorted endpoints, the smaller line first.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NormalizedIntersection<T: Scalar> {
+    pub kind: Kind,
+    pub line1: Line2D<T>,
+    pub line2: Line2D<T>,
+}
+
+impl<T: Scalar> NormalizedIntersection<T> {
+    pub fn new(intersection: &Intersection<T>) -> Self {
+        let kind = match intersection {
+            Intersection::Crossing { .. } => Kind::Crossing,
+            Intersection::Touching { .. } => Kind::Touching,
+            Intersection::PartialOverlap { .. }
+            | Intersection::ContainedOverlap { .. }
+            | Intersection::IdenticalOverlap { .. } => Kind::Overlap,
+        };
+        let (line1, line2) = intersection.lines();
+        let (line1, line2) = (oriented(line1), oriented(line2));
+        let (line1, line2) = if line1 <= line2 {
+            (line1, line2)
+        } else {
+            (line2, line1)
+        };
+        NormalizedIntersection { kind, line1, line2 }
+    }
+}
+
+/// The line from its smaller to its larger endpoint.
+fn oriented<T: Scalar>(line: &Line2D<T>) -> Line2D<T> {
+    let (start, end) = line.sorted_endpoints();
+    Line2D {
+        start: start.clone(),
+        end: end.clone(),
+    }
+}
+
+impl<T: Scalar> Display for NormalizedIntersection<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} and {}", self.kind, self.line1, self.line2)
+    }
+}
+
+pub fn normalize<T: Scalar>(
+    intersections: &HashSet<Intersection<T>>,
+) -> BTreeSet<NormalizedIntersection<T>> {
+    intersections
+        .iter()
+        .map(NormalizedIntersection::new)
+        .collect()
+}
+
+/// Symmetric difference of two normalized results.
+#[derive(Clone, Debug)]
+pub struct Difference<T: Scalar> {
+    /// Intersections of the expected result which are missing in the actual result
+    pub missing: Vec<NormalizedIntersection<T>>,
+    /// Intersections of the actual result which aren't expected
+    pub extra: Vec<NormalizedIntersection<T>>,
+}
+
+impl<T: Scalar> Difference<T> {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+
+    /// Number of missing and extra intersections of each kind with differences.
+    pub fn by_kind(&self) -> BTreeMap<Kind, (usize, usize)> {
+        let mut counts: BTreeMap<Kind, (usize, usize)> = BTreeMap::new();
+        for intersection in &self.missing {
+            counts.entry(intersection.kind).or_default().0 += 1;
+        }
+        for intersection in &self.extra {
+            counts.entry(intersection.kind).or_default().1 += 1;
+        }
+        counts
+    }
+}
+
+/// Compares the actual result with the expected one.
+pub fn compare<T: Scalar>(
+    expected: &HashSet<Intersection<T>>,
+    actual: &HashSet<Intersection<T>>,
+) -> Difference<T> {
+    let expected = normalize(expected);
+    let actual = normalize(actual);
+    Difference {
+        missing: expected.difference(&actual).cloned().collect(),
+        extra: actual.difference(&expected).cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::algorithm::{AlgorithmOptions, Registry};
+    use crate::geometry::line_segments::LineSegments2D;
+    use std::path::Path;
+
+    #[test]
+    fn normalization_should_ignore_order_and_direction_of_lines_and_overlap_kind() {
+        let line1 = Line2D::new(0.0, 0.0, 2.0, 2.0);
+        let line2 = Line2D::new(1.0, 1.0, 3.0, 3.0);
+        let reversed = Line2D::new(3.0, 3.0, 1.0, 1.0);
+        let partial = Intersection::PartialOverlap {
+            line1,
+            line2,
+            overlap: Line2D::new(1.0, 1.0, 2.0, 2.0),
+        };
+        let contained = Intersection::ContainedOverlap {
+            line1: reversed,
+            line2: line1,
+            overlap: Line2D::new(1.0, 1.0, 2.0, 2.0),
+        };
+
+        assert_eq!(
+            NormalizedIntersection::new(&partial),
+            NormalizedIntersection::new(&contained)
+        );
+    }
+
+    #[test]
+    fn difference_should_list_missing_and_extra_intersections_by_kind() {
+        let lines = vec![
+            Line2D::new(0.0, 0.0, 2.0, 2.0),
+            Line2D::new(0.0, 2.0, 2.0, 0.0),
+            Line2D::new(2.0, 2.0, 3.0, 0.0),
+        ];
+        let expected = Registry::default()
+            .get("brute-force")
+            .unwrap()
+            .create(lines, &AlgorithmOptions::default())
+            .unwrap()
+            .intersect()
+            .intersections;
+        let mut actual = expected.clone();
+        actual.retain(|intersection| !matches!(intersection, Intersection::Touching { .. }));
+        actual.insert(Intersection::Crossing {
+            line1: Line2D::new(5.0, 5.0, 6.0, 6.0),
+            line2: Line2D::new(5.0, 6.0, 6.0, 5.0),
+            point: crate::geometry::point::Point2D::new(5.5, 5.5),
+        });
+
+        let difference = compare(&expected, &actual);
+
+        assert!(!difference.is_empty());
+        assert_eq!(
+            difference.by_kind(),
+            BTreeMap::from([(Kind::Crossing, (0, 1)), (Kind::Touching, (1, 0))])
+        );
+        assert!(compare(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn complete_algorithms_should_agree_on_data_files() {
+        let registry = Registry::default();
+        for file in ["data/s_1000_1.dat", "data/s_1000_10.dat"] {
+            let lines =
+                LineSegments2D::<ordered_float::OrderedFloat<f64>>::from_dat(Path::new(file))
+                    .unwrap()
+                    .lines;
+            let results: Vec<HashSet<Intersection>> =
+                ["brute-force", "sweep-line-degenerate", "external"]
+                    .iter()
+                    .map(|flag| {
+                        let algorithm = registry.get(flag).unwrap();
+                        let mut algorithm = algorithm
+                            .create(lines.clone(), &AlgorithmOptions::default())
+                            .unwrap();
+                        algorithm.intersect().intersections
+                    })
+                    .collect();
+
+            for result in &results[1..] {
+                let difference = compare(&results[0], result);
+                assert!(
+                    difference.is_empty(),
+                    "{}: {:?}",
+                    file,
+                    difference.by_kind()
+                );
+            }
+        }
+    }
+}
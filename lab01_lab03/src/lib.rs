@@ -0,0 +1,25 @@
+//! Intersection of line segments in the plane: brute force, sweep line (Bentley-Ottmann) and the
+//! geo library, generic over exact and floating point coordinates, with snap rounding and planar
+//! arrangements on top of the intersections.
+//!
+//! The types needed to read lines and intersect them are re-exported here, the modules of
+//! [geometry] contain the rest of the public API.
+pub mod geometry;
+
+pub use geometry::algorithm::{
+    AlgorithmError, AlgorithmOptions, CountResult, IntersectionAlgorithm, IntersectionResult,
+    RegisteredAlgorithm, Registry, Statistics,
+};
+pub use geometry::brute_force::handler::BruteForceHandler;
+pub use geometry::counting::IntersectionCounts;
+pub use geometry::dat::{DatOptions, ReadError};
+pub use geometry::dynamic::{DynamicSegments, SegmentId};
+pub use geometry::external::handler::GeoHandler;
+pub use geometry::grid::handler::GridHandler;
+pub use geometry::intersection::Intersection;
+pub use geometry::line::Line2D;
+pub use geometry::line_segments::LineSegments2D;
+pub use geometry::point::Point2D;
+pub use geometry::rtree::handler::RTreeHandler;
+pub use geometry::scalar::Scalar;
+pub use geometry::sweep_line::handler::{Handler as SweepLineHandler, SweepLineOptions};
@@ -1,17 +1,34 @@
-use crate::geometry::brute_force;
-use crate::geometry::sweep_line::handler::SweepLineOptions;
 use cpu_time::ProcessTime;
-use geometry::line_segments::LineSegments2D;
-use geometry::sweep_line::handler::Handler;
-use geometry::external::handler::GeoHandler;
-use memory_stats::memory_stats;
+use lab03::geometry::analysis::{AnalysisOptions, InputReport};
+use lab03::geometry::benchmark::{
+    self, BenchmarkOptions, BenchmarkRecord, Measurement, MemoryTracker, Summary, Timing,
+};
+use lab03::geometry::counting;
+use lab03::geometry::dat::{DatOptions, ParseError};
+use lab03::geometry::formats::{self, IntersectionWriter};
+use lab03::geometry::generator::{self, GeneratorOptions};
+use lab03::geometry::parallel;
+use lab03::geometry::repair::{self, Perturbation, RepairOptions};
+use lab03::geometry::snap_rounding::SnapRounding;
+use lab03::geometry::stream::{self, StreamError, StreamOptions};
+use lab03::geometry::svg::{self, RenderOptions, Window};
+use lab03::geometry::sweep_line::handler::Handler;
+use lab03::geometry::sweep_line::handler::SweepLineOptions;
+use lab03::geometry::sweep_line::trace::Trace;
+use lab03::geometry::verify;
+use lab03::Line2D;
+use lab03::LineSegments2D;
+use lab03::Scalar;
+use lab03::{AlgorithmOptions, RegisteredAlgorithm, Registry};
+use num_rational::BigRational;
+use ordered_float::OrderedFloat;
 
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::{env, panic};
-use std::fmt::Display;
-use std::path::Path;
-use std::time::Duration;
-
-mod geometry;
 
 const ALL_LINES: [&str; 4] = [
     "data/s_1000_1.dat",
@@ -24,35 +41,189 @@ fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 3 {
-        eprintln!("Usage: {} <benchmark|analyze> <filename|all> <--brute-force|--sweep-line>", args[0]);
-        return;
+        eprintln!(
+            "Usage: {} <benchmark|analyze> <filename|all> [--brute-force|--sweep-line|--sweep-line-degenerate|--external|--grid|--rtree] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} analyze <filename> [<algorithm>] [--min-length <length>] [--near-distance <distance>] [--output <report.txt|report.json>]",
+            args[0]
+        );
+        eprintln!(
+            "       {} benchmark <filename|all> [<algorithm>] [--repeats <runs>] [--warmup <runs>] [--output <results.csv|results.json>]",
+            args[0]
+        );
+        eprintln!(
+            "       {} benchmark <red filename> [<algorithm>] --red-blue <blue filename> [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} snap-round <filename> <grid> <output> [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} check <filename> [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} export <filename> <output> [<algorithm>] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} convert <filename> <output> [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} render <filename> <output.svg> [<algorithm>] [--window x_min,y_min,x_max,y_max] [--rejected] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} trace <filename> <output.jsonl> [--sweep-line|--sweep-line-degenerate] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} replay <trace.jsonl> <steps> <output prefix> [--window x_min,y_min,x_max,y_max]",
+            args[0]
+        );
+        eprintln!(
+            "       {} verify <filename> [<algorithm>...] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} stream <filename> <output.csv|output.wkt> [--memory-budget <lines>] [--temp-dir <dir>] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} count <filename> [<algorithm>] [--slab <x_left>,<x_right>] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} repair <filename> <output> [--perturb symbolic|<epsilon>] [--log <changes.log>] [--f64|--i64|--rational]",
+            args[0]
+        );
+        eprintln!(
+            "       {} generate <output> <count> [--seed <seed>] [--bounds x_min,y_min,x_max,y_max] [--density <density>] [--length fixed:L|uniform:MIN,MAX|exponential:MEAN] [--grid <spacing>] [--degenerate vertical=N,shared-endpoints=N,overlaps=N,shared-x=N,crossings=N,degree=N]",
+            args[0]
+        );
+        std::process::exit(1);
+    }
+
+    let scalar = match args
+        .iter()
+        .skip(3)
+        .find(|arg| arg.parse::<ScalarType>().is_ok())
+    {
+        Some(arg) => arg.parse().unwrap(),
+        None => ScalarType::Float,
+    };
+
+    // second layer of the bichromatic mode
+    let blue_file = args
+        .iter()
+        .position(|arg| arg == "--red-blue")
+        .map(|i| args.get(i + 1).map(String::as_str));
+    if blue_file == Some(None) {
+        eprintln!("--red-blue requires a second file");
+        std::process::exit(1);
     }
+    let blue_file = blue_file.flatten();
+
+    let dat = DatOptions {
+        lenient: args.iter().any(|arg| arg == "--lenient"),
+        ..DatOptions::default()
+    };
+
+    let window = match option_value(&args, "--window").map(str::parse::<Window>) {
+        Some(Ok(window)) => Some(window),
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let render_options = RenderOptions {
+        window,
+        highlight_rejected: args.iter().any(|arg| arg == "--rejected"),
+        ..RenderOptions::default()
+    };
+
+    let algorithm_options = match algorithm_options(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
-    match args[1].as_str() {
+    let succeeded = match args[1].as_str() {
         "benchmark" => {
             let file = args[2].as_str();
-            let algorithm = match args.get(3) {
-                Some(algorithm) => match algorithm.as_str() {
-                    "--brute-force" => Algorithm::BruteForce,
-                    "--sweep-line" => Algorithm::SweepLine,
-                    "--external" => Algorithm::External,
-                    _ => {
-                        eprintln!("Unknown parameter: {}", algorithm);
-                        return;
-                    }
-                },
-                None => {
-                    println!("Using default algorithm: Brute Force");
-                    Algorithm::BruteForce
-                },
+            let algorithm = algorithm_flag(&args, 3);
+            let options = match benchmark_options(&args) {
+                Ok(options) => options,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
             };
+            let output = option_value(&args, "--output");
             match file {
                 "" => {
                     eprintln!("No file specified");
-                    return;
+                    false
+                }
+                "all" if blue_file.is_some() => {
+                    eprintln!("--red-blue only supports single files");
+                    false
                 }
-                "all" => benchmark_all(algorithm),
-                _ => benchmark_single(file, algorithm),
+                "all" => match scalar {
+                    ScalarType::Float => benchmark_all::<OrderedFloat<f64>>(
+                        algorithm,
+                        &dat,
+                        &algorithm_options,
+                        &options,
+                        output,
+                    ),
+                    ScalarType::Integer => {
+                        benchmark_all::<i64>(algorithm, &dat, &algorithm_options, &options, output)
+                    }
+                    ScalarType::Rational => benchmark_all::<BigRational>(
+                        algorithm,
+                        &dat,
+                        &algorithm_options,
+                        &options,
+                        output,
+                    ),
+                },
+                _ => match scalar {
+                    ScalarType::Float => benchmark_single::<OrderedFloat<f64>>(
+                        file,
+                        blue_file,
+                        algorithm,
+                        &dat,
+                        &algorithm_options,
+                        &options,
+                        output,
+                    ),
+                    ScalarType::Integer => benchmark_single::<i64>(
+                        file,
+                        blue_file,
+                        algorithm,
+                        &dat,
+                        &algorithm_options,
+                        &options,
+                        output,
+                    ),
+                    ScalarType::Rational => benchmark_single::<BigRational>(
+                        file,
+                        blue_file,
+                        algorithm,
+                        &dat,
+                        &algorithm_options,
+                        &options,
+                        output,
+                    ),
+                },
             }
         }
         "analyze" => {
@@ -60,175 +231,1319 @@ fn main() {
             match file {
                 "" => {
                     eprintln!("No file specified");
-                    return;
+                    false
                 }
                 "all" => {
                     eprintln!("Analyze only supports single files");
-                    return;
+                    false
+                }
+                _ => {
+                    let algorithm = algorithm_flag(&args, 3);
+                    let options = match analysis_options(&args) {
+                        Ok(options) => options,
+                        Err(error) => {
+                            eprintln!("{}", error);
+                            std::process::exit(1);
+                        }
+                    };
+                    let output = option_value(&args, "--output");
+                    match scalar {
+                        ScalarType::Float => analyze::<OrderedFloat<f64>>(
+                            file,
+                            algorithm,
+                            &algorithm_options,
+                            &options,
+                            output,
+                            &dat,
+                        ),
+                        ScalarType::Integer => analyze::<i64>(
+                            file,
+                            algorithm,
+                            &algorithm_options,
+                            &options,
+                            output,
+                            &dat,
+                        ),
+                        ScalarType::Rational => analyze::<BigRational>(
+                            file,
+                            algorithm,
+                            &algorithm_options,
+                            &options,
+                            output,
+                            &dat,
+                        ),
+                    }
+                }
+            }
+        }
+        "snap-round" => {
+            let file = args[2].as_str();
+            let grid = args.get(3).and_then(|grid| grid.parse::<f64>().ok());
+            match (grid, args.get(4)) {
+                (Some(grid), Some(_)) if !(grid.is_finite() && grid > 0.0) => {
+                    eprintln!("Grid spacing has to be positive: {}", grid);
+                    false
+                }
+                (Some(grid), Some(output)) => match scalar {
+                    ScalarType::Float => snap_round::<OrderedFloat<f64>>(file, grid, output, &dat),
+                    ScalarType::Integer => snap_round::<i64>(file, grid, output, &dat),
+                    ScalarType::Rational => snap_round::<BigRational>(file, grid, output, &dat),
+                },
+                _ => {
+                    eprintln!("snap-round requires a grid spacing and an output file");
+                    false
+                }
+            }
+        }
+        "check" => {
+            let file = args[2].as_str();
+            match scalar {
+                ScalarType::Float => check::<OrderedFloat<f64>>(file, &dat),
+                ScalarType::Integer => check::<i64>(file, &dat),
+                ScalarType::Rational => check::<BigRational>(file, &dat),
+            }
+        }
+        "export" => {
+            let file = args[2].as_str();
+            let algorithm = algorithm_flag(&args, 4);
+            match args.get(3) {
+                Some(output) => match scalar {
+                    ScalarType::Float => export::<OrderedFloat<f64>>(
+                        file,
+                        output,
+                        algorithm,
+                        &algorithm_options,
+                        &dat,
+                    ),
+                    ScalarType::Integer => {
+                        export::<i64>(file, output, algorithm, &algorithm_options, &dat)
+                    }
+                    ScalarType::Rational => {
+                        export::<BigRational>(file, output, algorithm, &algorithm_options, &dat)
+                    }
+                },
+                None => {
+                    eprintln!("export requires an output file");
+                    false
+                }
+            }
+        }
+        "convert" => {
+            let file = args[2].as_str();
+            match args.get(3) {
+                Some(output) => match scalar {
+                    ScalarType::Float => convert::<OrderedFloat<f64>>(file, output, &dat),
+                    ScalarType::Integer => convert::<i64>(file, output, &dat),
+                    ScalarType::Rational => convert::<BigRational>(file, output, &dat),
+                },
+                None => {
+                    eprintln!("convert requires an output file");
+                    false
+                }
+            }
+        }
+        "render" => {
+            let file = args[2].as_str();
+            let algorithm = algorithm_flag(&args, 4);
+            match args.get(3) {
+                Some(output) => match scalar {
+                    ScalarType::Float => render::<OrderedFloat<f64>>(
+                        file,
+                        output,
+                        algorithm,
+                        &algorithm_options,
+                        &render_options,
+                        &dat,
+                    ),
+                    ScalarType::Integer => render::<i64>(
+                        file,
+                        output,
+                        algorithm,
+                        &algorithm_options,
+                        &render_options,
+                        &dat,
+                    ),
+                    ScalarType::Rational => render::<BigRational>(
+                        file,
+                        output,
+                        algorithm,
+                        &algorithm_options,
+                        &render_options,
+                        &dat,
+                    ),
+                },
+                None => {
+                    eprintln!("render requires an output file");
+                    false
+                }
+            }
+        }
+        "trace" => {
+            let file = args[2].as_str();
+            let options = match algorithm_flag(&args, 4) {
+                None | Some("sweep-line-degenerate") => SweepLineOptions::degenerate_handling(),
+                Some("sweep-line") => SweepLineOptions::panic_disabled(),
+                Some(flag) => {
+                    eprintln!(
+                        "Tracing requires --sweep-line or --sweep-line-degenerate, found --{}",
+                        flag
+                    );
+                    std::process::exit(1);
+                }
+            };
+            match args.get(3) {
+                Some(output) => match scalar {
+                    ScalarType::Float => trace::<OrderedFloat<f64>>(file, output, options, &dat),
+                    ScalarType::Integer => trace::<i64>(file, output, options, &dat),
+                    ScalarType::Rational => trace::<BigRational>(file, output, options, &dat),
+                },
+                None => {
+                    eprintln!("trace requires an output file");
+                    false
                 }
+            }
+        }
+        "replay" => {
+            let file = args[2].as_str();
+            match (args.get(3).map(|steps| parse_steps(steps)), args.get(4)) {
+                (Some(Some(steps)), Some(prefix)) => replay(file, &steps, prefix, &render_options),
                 _ => {
-                    analyze(file);
+                    eprintln!("replay requires steps (e.g. 3,10-12) and an output prefix");
+                    false
+                }
+            }
+        }
+        "verify" => {
+            let file = args[2].as_str();
+            let algorithms: Vec<&str> = (3..args.len())
+                .filter(|&i| args[i].starts_with("--"))
+                .filter_map(|i| algorithm_flag(&args, i))
+                .collect();
+            match scalar {
+                ScalarType::Float => {
+                    verify::<OrderedFloat<f64>>(file, &algorithms, &algorithm_options, &dat)
+                }
+                ScalarType::Integer => verify::<i64>(file, &algorithms, &algorithm_options, &dat),
+                ScalarType::Rational => {
+                    verify::<BigRational>(file, &algorithms, &algorithm_options, &dat)
+                }
+            }
+        }
+        "generate" => {
+            let output = args[2].as_str();
+            match args.get(3).and_then(|count| count.parse::<usize>().ok()) {
+                Some(count) => match generator_options(&args, count) {
+                    Ok(options) => generate(output, &options),
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        false
+                    }
+                },
+                None => {
+                    eprintln!("generate requires the number of segments");
+                    false
+                }
+            }
+        }
+        "stream" => {
+            let file = args[2].as_str();
+            let options = match stream_options(&args) {
+                Ok(options) => options,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            };
+            match args.get(3) {
+                Some(output) => match scalar {
+                    ScalarType::Float => stream::<OrderedFloat<f64>>(file, output, &dat, &options),
+                    ScalarType::Integer => stream::<i64>(file, output, &dat, &options),
+                    ScalarType::Rational => stream::<BigRational>(file, output, &dat, &options),
+                },
+                None => {
+                    eprintln!("stream requires an output file");
+                    false
+                }
+            }
+        }
+        "count" => {
+            let file = args[2].as_str();
+            let algorithm = algorithm_flag(&args, 3);
+            let slab = option_value(&args, "--slab");
+            match scalar {
+                ScalarType::Float => {
+                    count::<OrderedFloat<f64>>(file, algorithm, slab, &algorithm_options, &dat)
+                }
+                ScalarType::Integer => {
+                    count::<i64>(file, algorithm, slab, &algorithm_options, &dat)
+                }
+                ScalarType::Rational => {
+                    count::<BigRational>(file, algorithm, slab, &algorithm_options, &dat)
+                }
+            }
+        }
+        "repair" => {
+            let file = args[2].as_str();
+            let perturbation = match option_value(&args, "--perturb") {
+                None => Perturbation::None,
+                Some("symbolic") => Perturbation::Symbolic,
+                Some(value) => match value.parse::<f64>() {
+                    Ok(epsilon) if epsilon.is_finite() && epsilon > 0.0 => {
+                        Perturbation::Controlled { epsilon }
+                    }
+                    _ => {
+                        eprintln!("Invalid value of --perturb: '{}' (expected symbolic or a positive epsilon)", value);
+                        std::process::exit(1);
+                    }
+                },
+            };
+            let options = RepairOptions {
+                perturbation,
+                ..RepairOptions::default()
+            };
+            match args.get(3) {
+                Some(output) => {
+                    let log = option_value(&args, "--log")
+                        .map_or_else(|| Path::new(output).with_extension("log"), PathBuf::from);
+                    match scalar {
+                        ScalarType::Float => {
+                            repair::<OrderedFloat<f64>>(file, output, &log, &options, &dat)
+                        }
+                        ScalarType::Integer => repair::<i64>(file, output, &log, &options, &dat),
+                        ScalarType::Rational => {
+                            repair::<BigRational>(file, output, &log, &options, &dat)
+                        }
+                    }
+                }
+                None => {
+                    eprintln!("repair requires an output file");
+                    false
                 }
             }
         }
         _ => {
             eprintln!("Unknown command: {}", args[1]);
+            false
+        }
+    };
+    if !succeeded {
+        std::process::exit(1);
+    }
+}
+
+/// Flags of options which aren't algorithms
+const OPTION_FLAGS: [&str; 15] = [
+    "--red-blue",
+    "--lenient",
+    "--window",
+    "--rejected",
+    "--repeats",
+    "--warmup",
+    "--output",
+    "--threads",
+    "--memory-budget",
+    "--temp-dir",
+    "--slab",
+    "--min-length",
+    "--near-distance",
+    "--perturb",
+    "--log",
+];
+
+/// Returns the flag of the algorithm given at the index, None if no algorithm is given.
+fn algorithm_flag(args: &[String], index: usize) -> Option<&str> {
+    args.get(index)
+        .filter(|arg| arg.parse::<ScalarType>().is_err() && !OPTION_FLAGS.contains(&arg.as_str()))
+        .map(|arg| arg.trim_start_matches("--"))
+}
+
+/// Returns the value following the option flag.
+fn option_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Reads the number of warmup and measured runs, the defaults of `BenchmarkOptions` for missing options.
+fn benchmark_options(args: &[String]) -> Result<BenchmarkOptions, String> {
+    let count = |flag: &str, default: usize| match option_value(args, flag) {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid value of {}: '{}'", flag, value)),
+        None => Ok(default),
+    };
+    let defaults = BenchmarkOptions::default();
+    let options = BenchmarkOptions {
+        warmup: count("--warmup", defaults.warmup)?,
+        repeats: count("--repeats", defaults.repeats)?,
+    };
+    if options.repeats == 0 {
+        return Err("--repeats has to be at least 1".to_string());
+    }
+    Ok(options)
+}
+
+/// Reads the number of threads, `auto` for all available threads, 1 if the option is missing.
+fn algorithm_options(args: &[String]) -> Result<AlgorithmOptions, String> {
+    let threads = match option_value(args, "--threads") {
+        Some("auto") => parallel::available_threads(),
+        Some(value) => match value.parse::<usize>() {
+            Ok(threads) if threads > 0 => threads,
+            _ => return Err(format!("Invalid value of --threads: '{}'", value)),
+        },
+        None => 1,
+    };
+    Ok(AlgorithmOptions {
+        threads,
+        ..AlgorithmOptions::default()
+    })
+}
+
+/// Reads the thresholds of the report, the defaults of `AnalysisOptions` for missing options.
+fn analysis_options(args: &[String]) -> Result<AnalysisOptions, String> {
+    let threshold = |flag: &str, default: f64| match option_value(args, flag) {
+        Some(value) => match value.parse::<f64>() {
+            Ok(threshold) if threshold >= 0.0 => Ok(threshold),
+            _ => Err(format!("Invalid value of {}: '{}'", flag, value)),
+        },
+        None => Ok(default),
+    };
+    let defaults = AnalysisOptions::default();
+    Ok(AnalysisOptions {
+        min_length: threshold("--min-length", defaults.min_length)?,
+        near_distance: threshold("--near-distance", defaults.near_distance)?,
+        ..defaults
+    })
+}
+
+/// Reads the memory budget and the directory of the sorted runs, the defaults of `StreamOptions` for
+/// missing options.
+fn stream_options(args: &[String]) -> Result<StreamOptions, String> {
+    let defaults = StreamOptions::default();
+    let memory_budget = match option_value(args, "--memory-budget") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(budget) if budget > 0 => budget,
+            _ => return Err(format!("Invalid value of --memory-budget: '{}'", value)),
+        },
+        None => defaults.memory_budget,
+    };
+    Ok(StreamOptions {
+        memory_budget,
+        temp_dir: option_value(args, "--temp-dir").map_or(defaults.temp_dir, PathBuf::from),
+    })
+}
+
+/// Looks up the algorithm of the flag, the first registered algorithm is the default.
+fn get_algorithm<'a, T: Scalar>(
+    registry: &'a Registry<T>,
+    flag: Option<&str>,
+) -> Option<&'a RegisteredAlgorithm<T>> {
+    match flag {
+        Some(flag) => {
+            let algorithm = registry.get(flag);
+            if algorithm.is_none() {
+                let flags: Vec<String> = registry
+                    .algorithms()
+                    .iter()
+                    .map(|algorithm| format!("--{}", algorithm.flag))
+                    .collect();
+                eprintln!(
+                    "Unknown parameter: --{} (expected one of {})",
+                    flag,
+                    flags.join(", ")
+                );
+            }
+            algorithm
+        }
+        None => {
+            let algorithm = registry.algorithms().first();
+            if let Some(algorithm) = algorithm {
+                println!("Using default algorithm: {}", algorithm.name);
+            }
+            algorithm
+        }
+    }
+}
+
+/// Prints the report of the input and writes it to the output file, as JSON for `.json` files and
+/// text otherwise.
+fn analyze<T: Scalar>(
+    file: &str,
+    algorithm: Option<&str>,
+    algorithm_options: &AlgorithmOptions,
+    options: &AnalysisOptions,
+    output: Option<&str>,
+    dat: &DatOptions,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let result = match algorithm.create(lines.lines.clone(), algorithm_options) {
+        Ok(mut algorithm) => algorithm.intersect(),
+        Err(error) => {
+            eprintln!("{} can't analyze {}: {}", algorithm.name, file, error);
+            return false;
+        }
+    };
+    let report = InputReport::new(&lines.lines, &result.intersections, options);
+    report
+        .write_text(&mut std::io::stdout())
+        .expect("Writing the report failed");
+
+    if let Some(output) = output {
+        let result = File::create(output).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            match Path::new(output)
+                .extension()
+                .and_then(|extension| extension.to_str())
+            {
+                Some("json") => report.write_json(&mut writer)?,
+                _ => report.write_text(&mut writer)?,
+            }
+            writer.flush()
+        });
+        match result {
+            Ok(()) => println!("Report written to {}", output),
+            Err(error) => {
+                eprintln!("Error writing {}: {}", output, error);
+                return false;
+            }
         }
     }
+    true
 }
 
-fn analyze(file: &str) {
-    let lines = get_lines(file).expect(format!("Error reading file {}", file).as_str());
+/// Counts the intersections by variant with the algorithm, or the crossings within the slab
+/// `x_left < x < x_right` by counting inversions.
+fn count<T: Scalar>(
+    file: &str,
+    algorithm: Option<&str>,
+    slab: Option<&str>,
+    algorithm_options: &AlgorithmOptions,
+    dat: &DatOptions,
+) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    if let Some(slab) = slab {
+        let bounds: Vec<Option<T>> = slab
+            .split(',')
+            .map(|value| T::parse(value.trim()))
+            .collect();
+        let [Some(left), Some(right)] = bounds.as_slice() else {
+            eprintln!(
+                "Invalid value of --slab: '{}' (expected x_left,x_right)",
+                slab
+            );
+            return false;
+        };
+        let start = ProcessTime::try_now().expect("Getting process time failed");
+        return match counting::count_slab_crossings(&lines.lines, left, right) {
+            Ok(crossings) => {
+                println!(
+                    "Found {} crossings in the slab {} < x < {} in {} ms",
+                    crossings,
+                    left,
+                    right,
+                    start.elapsed().as_millis()
+                );
+                true
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                false
+            }
+        };
+    }
 
-    let mut brute_force_handler = brute_force::handler::BruteForceHandler::new(lines.lines);
-    brute_force_handler.run();
-    brute_force_handler.analyze();
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let result = match algorithm.create(lines.lines, algorithm_options) {
+        Ok(mut algorithm) => algorithm.count(),
+        Err(error) => {
+            eprintln!("{} can't count {}: {}", algorithm.name, file, error);
+            return false;
+        }
+    };
+    println!(
+        "{} counted {} intersections of {} lines in {} ms",
+        algorithm.name,
+        result.counts.total(),
+        result.statistics.lines,
+        result.statistics.time.as_millis()
+    );
+    for (variant, count) in result.counts.by_variant() {
+        println!("  {}: {}", variant, count);
+    }
+    true
 }
 
+/// Coordinate type the lines are read and intersected with
 #[derive(Clone, Copy)]
-enum Algorithm {
-    BruteForce,
-    SweepLine,
-    External,
+enum ScalarType {
+    Float,
+    Integer,
+    Rational,
 }
 
-impl Display for Algorithm {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Algorithm::BruteForce => write!(f, "Brute Force"),
-            Algorithm::SweepLine => write!(f, "Sweep Line"),
-            Algorithm::External => write!(f, "External (Geo Library)"),
+impl FromStr for ScalarType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("--") {
+            Some(name) if name == OrderedFloat::<f64>::NAME => Ok(ScalarType::Float),
+            Some(name) if name == i64::NAME => Ok(ScalarType::Integer),
+            Some(name) if name == BigRational::NAME => Ok(ScalarType::Rational),
+            _ => Err(()),
         }
     }
 }
 
-struct BenchmarkResult {
-    file: String,
-    lines: usize,
-    intersections: Option<usize>,
-    time: Option<Duration>,
-    memory: Option<u64>,
+/// Runs the sweep line on the lines of the file and writes the trace of the processed events as JSON lines.
+fn trace<T: Scalar>(file: &str, output: &str, options: SweepLineOptions, dat: &DatOptions) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let mut handler = Handler::new(lines.lines, options);
+    handler.enable_trace();
+    let intersections = handler.run();
+    let trace = handler.trace().expect("Trace is enabled");
+
+    let result = File::create(output).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        trace.write_json_lines(&mut writer)?;
+        writer.flush()
+    });
+    if let Err(error) = result {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    println!(
+        "Traced {} events with {} intersections to {}",
+        trace.steps.len(),
+        intersections.len(),
+        output
+    );
+    true
+}
+
+/// Parses steps like `3,10-12`.
+fn parse_steps(steps: &str) -> Option<Vec<usize>> {
+    let mut result = Vec::new();
+    for part in steps.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => result.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => result.push(part.parse().ok()?),
+        }
+    }
+    Some(result)
 }
 
-fn benchmark_single(file: &str, algorithm: Algorithm) {
-    let result = benchmark(file, algorithm);
+/// Renders the steps of the trace as SVG frames `<prefix>_<step>.svg`.
+/// Returns whether all steps were rendered.
+fn replay(file: &str, steps: &[usize], prefix: &str, options: &RenderOptions) -> bool {
+    let reader = match File::open(file) {
+        Ok(file) => BufReader::new(file),
+        Err(error) => {
+            eprintln!("Error reading {}: {}", file, error);
+            return false;
+        }
+    };
+    let trace = match Trace::read_json_lines(reader, Path::new(file)) {
+        Ok(trace) => trace,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
 
-    print_benchmark_results(vec![result]);
+    let mut rendered = true;
+    for &step in steps {
+        let Some(frame) = trace.render_step(step, options) else {
+            eprintln!(
+                "Step {} doesn't exist, the trace has {} steps",
+                step,
+                trace.steps.len()
+            );
+            rendered = false;
+            continue;
+        };
+        let output = format!("{}_{}.svg", prefix, step);
+        if let Err(error) = std::fs::write(&output, frame) {
+            eprintln!("Error writing {}: {}", output, error);
+            return false;
+        }
+        println!("Rendered step {} to {}", step, output);
+    }
+    rendered
 }
 
-fn benchmark_all(algorithm: Algorithm) {
-    let mut benchmark_results: Vec<BenchmarkResult> = Vec::new();
-    for file in ALL_LINES.iter() {
-        benchmark_results.push(benchmark(file, algorithm));
+/// Reads the options of the generator, the defaults of `GeneratorOptions` for missing options.
+fn generator_options(args: &[String], count: usize) -> Result<GeneratorOptions, String> {
+    fn parse<T: FromStr>(args: &[String], flag: &str) -> Result<Option<T>, String> {
+        option_value(args, flag)
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .map_err(|_| format!("Invalid value of {}: '{}'", flag, value))
+            })
+            .transpose()
+    }
+    fn parse_with<T: FromStr<Err = String>>(
+        args: &[String],
+        flag: &str,
+    ) -> Result<Option<T>, String> {
+        option_value(args, flag).map(str::parse::<T>).transpose()
     }
 
-    print_benchmark_results(benchmark_results);
+    let defaults = GeneratorOptions::default();
+    let options = GeneratorOptions {
+        count,
+        bounds: parse_with(args, "--bounds")?.unwrap_or(defaults.bounds),
+        density: parse(args, "--density")?,
+        lengths: parse_with(args, "--length")?.unwrap_or(defaults.lengths),
+        grid: parse(args, "--grid")?.unwrap_or(defaults.grid),
+        seed: parse(args, "--seed")?.unwrap_or(defaults.seed),
+        degeneracies: parse_with(args, "--degenerate")?.unwrap_or(defaults.degeneracies),
+    };
+    if !(options.grid.is_finite() && options.grid > 0.0) {
+        return Err(format!("Grid spacing has to be positive: {}", options.grid));
+    }
+    if options
+        .density
+        .is_some_and(|density| !(density.is_finite() && density > 0.0))
+    {
+        return Err("Density has to be positive".to_string());
+    }
+    if options.density.is_some() && count == 0 {
+        return Err("A density requires at least one segment".to_string());
+    }
+    if options.degeneracies.segments() > count {
+        return Err(format!(
+            "{} degenerate segments requested, but only {} segments in total",
+            options.degeneracies.segments(),
+            count
+        ));
+    }
+    Ok(options)
 }
 
-fn benchmark(file: &str, algorithm: Algorithm) -> BenchmarkResult {
-    println!("Starting benchmark for file {} with {} Algorithm...", file, algorithm.to_string());
-    let lines = get_lines(file).expect(format!("Error reading file {}", file).as_str());
+fn generate(output: &str, options: &GeneratorOptions) -> bool {
+    let segments = generator::generate(options);
+    let bounds = options.effective_bounds();
+    match segments.write(Path::new(output)) {
+        Ok(()) => {
+            println!(
+                "Generated {} segments ({} degenerate) in ({}, {}) - ({}, {}) with seed {} to {}",
+                segments.lines.len(),
+                options.degeneracies.segments(),
+                bounds.x_min,
+                bounds.y_min,
+                bounds.x_max,
+                bounds.y_max,
+                options.seed,
+                output
+            );
+            true
+        }
+        Err(error) => {
+            eprintln!("Error writing {}: {}", output, error);
+            false
+        }
+    }
+}
 
-    let start = ProcessTime::try_now().expect("Getting process time failed");
-    let memory_start = get_memory_usage();
+/// Number of offending pairs printed per difference
+const VERIFY_LIMIT: usize = 10;
 
-    let result = panic::catch_unwind(|| {
-        match algorithm {
-            Algorithm::BruteForce => {
-                let mut brute_force_handler = brute_force::handler::BruteForceHandler::new(lines.clone().lines);
-                brute_force_handler.run()
+/// Compares the intersections of the algorithms with the first one, all registered algorithms if none
+/// are given. Returns whether all algorithms succeeded and agree.
+fn verify<T: Scalar>(
+    file: &str,
+    flags: &[&str],
+    algorithm_options: &AlgorithmOptions,
+    dat: &DatOptions,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let algorithms: Vec<&RegisteredAlgorithm<T>> = if flags.is_empty() {
+        registry.algorithms().iter().collect()
+    } else {
+        match flags
+            .iter()
+            .map(|&flag| get_algorithm(&registry, Some(flag)))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(algorithms) => algorithms,
+            None => return false,
+        }
+    };
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let mut results = Vec::new();
+    for algorithm in &algorithms {
+        // the lines are only read, so a panic can't leave them in an inconsistent state
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            algorithm
+                .create(lines.lines.clone(), algorithm_options)
+                .map(|mut algorithm| algorithm.intersect().intersections)
+        }));
+        match result {
+            Ok(Ok(intersections)) => {
+                println!("{}: {} intersections", algorithm.name, intersections.len());
+                results.push((algorithm, intersections));
+            }
+            Ok(Err(error)) => println!("{}: {}", algorithm.name, error),
+            Err(_) => println!("{}: panicked", algorithm.name),
+        }
+    }
+
+    let mut verified = results.len() == algorithms.len();
+    let Some(((reference, expected), others)) = results.split_first() else {
+        return false;
+    };
+    for (algorithm, actual) in others {
+        let difference = verify::compare(expected, actual);
+        if difference.is_empty() {
+            continue;
+        }
+        verified = false;
+        println!();
+        println!("{} differs from {}:", algorithm.name, reference.name);
+        for (kind, (missing, extra)) in difference.by_kind() {
+            println!("  {}: {} missing, {} extra", kind, missing, extra);
+        }
+        for (label, intersections) in [
+            ("Missing", &difference.missing),
+            ("Extra", &difference.extra),
+        ] {
+            for intersection in intersections.iter().take(VERIFY_LIMIT) {
+                println!("  {} {}", label, intersection);
             }
-            Algorithm::SweepLine => {
-                let mut sweep_line_handler = Handler::new(lines.clone().lines, SweepLineOptions::panic_disabled());
-                sweep_line_handler.run()
-            },
-            Algorithm::External => {
-                let geo_handler = GeoHandler::new(lines.clone().lines);
-                geo_handler.run()
+            if intersections.len() > VERIFY_LIMIT {
+                println!("  ... and {} more", intersections.len() - VERIFY_LIMIT);
             }
         }
-    });
+    }
 
+    println!();
+    if verified {
+        println!("All algorithms agree");
+    } else {
+        println!("Verification failed");
+    }
+    verified
+}
+
+/// Computes the intersections with the algorithm and renders them with the lines as SVG.
+fn render<T: Scalar>(
+    file: &str,
+    output: &str,
+    algorithm: Option<&str>,
+    algorithm_options: &AlgorithmOptions,
+    options: &RenderOptions,
+    dat: &DatOptions,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let result = match algorithm.create(lines.lines.clone(), algorithm_options) {
+        Ok(mut algorithm) => algorithm.intersect(),
+        Err(error) => {
+            eprintln!("{} can't intersect {}: {}", algorithm.name, file, error);
+            return false;
+        }
+    };
+    if let Err(error) = std::fs::write(output, svg::render(&lines, &result.intersections, options))
+    {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    println!(
+        "Rendered {} lines with {} intersections to {}",
+        result.statistics.lines, result.statistics.intersections, output
+    );
+    true
+}
+
+/// Computes the intersections with the algorithm and writes them to the output file in the format of its extension.
+fn export<T: Scalar>(
+    file: &str,
+    output: &str,
+    algorithm: Option<&str>,
+    algorithm_options: &AlgorithmOptions,
+    dat: &DatOptions,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let result = match algorithm.create(lines.lines, algorithm_options) {
+        Ok(mut algorithm) => algorithm.intersect(),
+        Err(error) => {
+            eprintln!("{} can't intersect {}: {}", algorithm.name, file, error);
+            return false;
+        }
+    };
+    if let Err(error) = formats::write_intersections(Path::new(output), &result.intersections) {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    println!(
+        "Wrote {} intersections of {} lines to {}",
+        result.statistics.intersections, result.statistics.lines, output
+    );
+    true
+}
+
+/// Intersects the lines of the `.dat` file without loading them into memory and writes every
+/// intersection to the output file as soon as it is found.
+fn stream<T: Scalar>(file: &str, output: &str, dat: &DatOptions, options: &StreamOptions) -> bool {
+    let mut writer = match IntersectionWriter::create(Path::new(output)) {
+        Ok(writer) => writer,
+        Err(error) => {
+            eprintln!("Error writing {}: {}", output, error);
+            return false;
+        }
+    };
+    let result = stream::intersect_file::<T>(Path::new(file), dat, options, &mut writer).and_then(
+        |statistics| {
+            writer.finish().map_err(StreamError::Write)?;
+            Ok(statistics)
+        },
+    );
     match result {
-        Ok(intersections) => BenchmarkResult {
-            file: file.to_string(),
-            lines: lines.lines.len(),
-            intersections: Some(intersections.len()),
-            time: Some(start.elapsed()),
-            memory: Some(get_memory_usage() - memory_start),
+        Ok(statistics) => {
+            println!(
+                "Wrote {} intersections of {} lines to {} ({} sorted runs, at most {} pending event points)",
+                statistics.intersections,
+                statistics.lines,
+                output,
+                statistics.runs,
+                statistics.peak_event_points
+            );
+            true
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            false
+        }
+    }
+}
+
+/// Writes the lines of the file to the output file in the format of its extension.
+fn convert<T: Scalar>(file: &str, output: &str, dat: &DatOptions) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    if let Err(error) = lines.write(Path::new(output)) {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    println!("Wrote {} lines to {}", lines.lines.len(), output);
+    true
+}
+
+/// Rounds the lines and their intersections onto the grid and writes the resulting segments to the output file.
+fn snap_round<T: Scalar>(file: &str, grid: f64, output: &str, dat: &DatOptions) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let intersections =
+        Handler::new(lines.lines.clone(), SweepLineOptions::degenerate_handling()).run();
+    let rounded = SnapRounding::new(grid).round(&lines, &intersections);
+
+    if let Err(error) = rounded.write(Path::new(output)) {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    println!(
+        "Rounded {} lines with {} intersections onto a grid of {} into {} segments, written to {}",
+        lines.lines.len(),
+        intersections.len(),
+        grid,
+        rounded.lines.len(),
+        output
+    );
+    true
+}
+
+/// Cleans the lines of the file, writes them to the output and every change to the log.
+fn repair<T: Scalar>(
+    file: &str,
+    output: &str,
+    log: &Path,
+    options: &RepairOptions,
+    dat: &DatOptions,
+) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let repaired = match repair::repair(&lines.lines, options) {
+        Ok(repaired) => repaired,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let segments = LineSegments2D {
+        lines: repaired.lines.clone(),
+    };
+    if let Err(error) = segments.write(Path::new(output)) {
+        eprintln!("Error writing {}: {}", output, error);
+        return false;
+    }
+    let result = File::create(log).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        repaired.write_log(&mut writer)?;
+        writer.flush()
+    });
+    if let Err(error) = result {
+        eprintln!("Error writing {}: {}", log.display(), error);
+        return false;
+    }
+
+    let (zero_length, copies, merged, moved) = repaired.summary();
+    println!(
+        "Repaired {} lines into {}: removed {} zero length lines and {} copies, merged {} overlapping lines, moved {} endpoints",
+        lines.lines.len(),
+        repaired.lines.len(),
+        zero_length,
+        copies,
+        merged,
+        moved
+    );
+    println!("Written to {}, changes logged to {}", output, log.display());
+    true
+}
+
+/// Checks whether any pair of lines intersects and prints the leftmost intersection.
+fn check<T: Scalar>(file: &str, dat: &DatOptions) -> bool {
+    let Some(lines) = get_lines::<T>(file, dat) else {
+        return false;
+    };
+
+    let start = ProcessTime::try_now().expect("Getting process time failed");
+    let intersection =
+        Handler::new(lines.lines.clone(), SweepLineOptions::degenerate_handling()).find_any();
+    let time = start.elapsed().as_millis();
+
+    match intersection {
+        Some(intersection) => println!(
+            "{} lines intersect, e.g. {} (checked in {} ms)",
+            file, intersection, time
+        ),
+        None => println!(
+            "{} lines of {} are intersection-free (checked in {} ms)",
+            lines.lines.len(),
+            file,
+            time
+        ),
+    }
+    true
+}
+
+fn benchmark_single<T: Scalar>(
+    file: &str,
+    blue_file: Option<&str>,
+    algorithm: Option<&str>,
+    dat: &DatOptions,
+    algorithm_options: &AlgorithmOptions,
+    options: &BenchmarkOptions,
+    output: Option<&str>,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let record = benchmark::<T>(file, blue_file, algorithm, dat, algorithm_options, options);
+
+    report_benchmark_results(&[record], output)
+}
+
+fn benchmark_all<T: Scalar>(
+    algorithm: Option<&str>,
+    dat: &DatOptions,
+    algorithm_options: &AlgorithmOptions,
+    options: &BenchmarkOptions,
+    output: Option<&str>,
+) -> bool {
+    let registry = Registry::<T>::default();
+    let Some(algorithm) = get_algorithm(&registry, algorithm) else {
+        return false;
+    };
+    let mut records: Vec<BenchmarkRecord> = Vec::new();
+    for file in ALL_LINES.iter() {
+        records.push(benchmark::<T>(
+            file,
+            None,
+            algorithm,
+            dat,
+            algorithm_options,
+            options,
+        ));
+    }
+
+    report_benchmark_results(&records, output)
+}
+
+/// Reads the lines of the file and the blue file, with the options extended by the bichromatic mode if
+/// a blue file is given. Malformed rows skipped in the lenient mode are only printed if `verbose` is set.
+fn read_benchmark_lines<T: Scalar>(
+    file: &str,
+    blue_file: Option<&str>,
+    dat: &DatOptions,
+    options: &AlgorithmOptions,
+    verbose: bool,
+) -> Result<(Vec<Line2D<T>>, AlgorithmOptions), String> {
+    let read = |file: &str| match LineSegments2D::<T>::read(Path::new(file), dat) {
+        Ok(report) => {
+            if verbose {
+                print_skipped(&report.errors);
+            }
+            Ok(report.segments.lines)
+        }
+        Err(error) => Err(error.to_string()),
+    };
+    let mut lines = read(file)?;
+    let mut options = *options;
+    if let Some(blue_file) = blue_file {
+        options.red_count = Some(lines.len());
+        lines.extend(read(blue_file)?);
+    }
+    Ok((lines, options))
+}
+
+/// Benchmarks the algorithm on the lines of the file. If a blue file is given, only the intersections
+/// between the lines of both files are computed.
+///
+/// Each run reads the file (parse time), then creates the algorithm and computes the intersections
+/// (compute time, peak memory). The warmup runs are discarded, the measured runs are summarized.
+fn benchmark<T: Scalar>(
+    file: &str,
+    blue_file: Option<&str>,
+    algorithm: &RegisteredAlgorithm<T>,
+    dat: &DatOptions,
+    algorithm_options: &AlgorithmOptions,
+    options: &BenchmarkOptions,
+) -> BenchmarkRecord {
+    println!(
+        "Starting benchmark for file {} with {} Algorithm ({}, {} threads, {} warmup and {} measured runs)...",
+        file,
+        algorithm.name,
+        T::NAME,
+        algorithm_options.threads,
+        options.warmup,
+        options.repeats
+    );
+    let mut record = BenchmarkRecord {
+        file: match blue_file {
+            Some(blue_file) => format!("{} x {}", file, blue_file),
+            None => file.to_string(),
         },
-        Err(_) => {
-            BenchmarkResult {
-                file: file.to_string(),
-                lines: lines.lines.len(),
-                intersections: None,
-                time: None,
-                memory: None,
+        algorithm: algorithm.name.to_string(),
+        scalar: T::NAME,
+        lines: 0,
+        warmup: options.warmup,
+        repeats: options.repeats,
+        intersections: None,
+        parse: None,
+        compute: None,
+        peak_memory: None,
+        error: None,
+    };
+
+    let mut parse_times = Vec::new();
+    let mut compute_times = Vec::new();
+    let mut peak_memory = 0;
+    for run in 0..options.warmup + options.repeats {
+        let (lines, parse_time) = Measurement::measure(|| {
+            read_benchmark_lines::<T>(file, blue_file, dat, algorithm_options, run == 0)
+        });
+        let (lines, algorithm_options) = match lines {
+            Ok(lines) => lines,
+            Err(error) => {
+                eprintln!("{}", error);
+                record.error = Some(error);
+                return record;
+            }
+        };
+        record.lines = lines.len();
+
+        let memory = MemoryTracker::start();
+        // the lines are moved into the algorithm, so a panic can't leave them in an inconsistent state
+        let (result, compute_time) = Measurement::measure(|| {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                algorithm
+                    .create(lines, &algorithm_options)
+                    .map(|mut algorithm| algorithm.intersect().statistics.intersections)
+            }))
+        });
+        let memory = memory.peak();
+
+        let error = match result {
+            Ok(Ok(intersections)) => {
+                record.intersections = Some(intersections);
+                None
             }
+            Ok(Err(error)) => {
+                eprintln!("{}", error);
+                Some(error.to_string())
+            }
+            Err(_) => Some("panicked".to_string()),
+        };
+        if error.is_some() {
+            record.intersections = None;
+            record.error = error;
+            return record;
+        }
+        if run >= options.warmup {
+            parse_times.push(parse_time);
+            compute_times.push(compute_time);
+            peak_memory = peak_memory.max(memory);
         }
     }
+
+    record.parse = Timing::new(&parse_times);
+    record.compute = Timing::new(&compute_times);
+    record.peak_memory = Some(peak_memory);
+    record
 }
 
-fn print_benchmark_results(results: Vec<BenchmarkResult>) {
+/// Prints the results and writes them to the output file, as JSON for `.json` files and CSV otherwise.
+/// Failed runs are part of the results, only an output file that can't be written is an error.
+fn report_benchmark_results(records: &[BenchmarkRecord], output: Option<&str>) -> bool {
+    print_benchmark_results(records);
+
+    if let Some(output) = output {
+        let result = File::create(output).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            match Path::new(output)
+                .extension()
+                .and_then(|extension| extension.to_str())
+            {
+                Some("json") => benchmark::write_json(&mut writer, records)?,
+                _ => benchmark::write_csv(&mut writer, records)?,
+            }
+            writer.flush()
+        });
+        match result {
+            Ok(()) => println!("Results written to {}", output),
+            Err(error) => {
+                eprintln!("Error writing {}: {}", output, error);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Median, with the standard deviation for repeated runs, of the durations in ms.
+fn format_summary(summary: Option<Summary>, repeats: usize) -> String {
+    match summary {
+        Some(summary) if repeats > 1 => format!(
+            "{} ± {}",
+            summary.median.as_millis(),
+            summary.stddev.as_millis()
+        ),
+        Some(summary) => summary.median.as_millis().to_string(),
+        None => "Error".to_string(),
+    }
+}
+
+fn print_benchmark_results(records: &[BenchmarkRecord]) {
     println!();
     // Header
     println!(
-        "| {0: <20} | {1: <10} | {2: <15} | {3: <15} | {4: <15} |",
-        "File", "# Lines", "# Intersections", "CPU Time (ms)", "Memory (kB)"
+        "| {0: <20} | {1: <10} | {2: <15} | {3: <15} | {4: <15} | {5: <15} | {6: <16} |",
+        "File",
+        "# Lines",
+        "# Intersections",
+        "CPU Time (ms)",
+        "Wall Time (ms)",
+        "Parse Time (ms)",
+        "Peak Memory (kB)"
     );
     println!(
-        "| {} | {} | {} | {} | {} |",
+        "| {} | {} | {} | {} | {} | {} | {} |",
         "-".repeat(20),
         "-".repeat(10),
         "-".repeat(15),
         "-".repeat(15),
-        "-".repeat(15)
+        "-".repeat(15),
+        "-".repeat(15),
+        "-".repeat(16)
     );
 
-    for result in results {
-        let intersections = match result.intersections {
+    for record in records {
+        let intersections = match record.intersections {
             Some(i) => i.to_string(),
             None => "Error".to_string(),
         };
 
-        let time = match result.time {
-            Some(t) => t.as_millis().to_string(),
-            None => "Error".to_string(),
-        };
+        let cpu = format_summary(record.compute.map(|t| t.cpu), record.repeats);
+        let wall = format_summary(record.compute.map(|t| t.wall), record.repeats);
+        let parse = format_summary(record.parse.map(|t| t.cpu), record.repeats);
 
-        let memory = match result.memory {
-            Some(m) => (m/1024).to_string(), // Convert to kB
+        let memory = match record.peak_memory {
+            Some(m) => (m / 1024).to_string(), // Convert to kB
             None => "Error".to_string(),
         };
 
         println!(
-            "| {0: <20} | {1: <10} | {2: <15} | {3: <15} | {4: <15} |",
-            result.file,
-            result.lines,
-            intersections,
-            time,
-            memory
+            "| {0: <20} | {1: <10} | {2: <15} | {3: <15} | {4: <15} | {5: <15} | {6: <16} |",
+            record.file, record.lines, intersections, cpu, wall, parse, memory
         );
     }
 
     println!("\nFor more detailed results, use analyze <filename>");
 }
 
-fn get_lines(file: &str) -> Option<LineSegments2D> {
-    let path = Path::new(file);
-
-    let lines = LineSegments2D::from_dat(path);
-    if lines.is_err() {
-        return None;
+/// Prints the malformed rows skipped in the lenient mode.
+fn print_skipped(errors: &[ParseError]) {
+    if !errors.is_empty() {
+        eprintln!("Skipped {} malformed lines:", errors.len());
+        for error in errors {
+            eprintln!("  {}", error);
+        }
     }
-    Some(lines.unwrap())
 }
 
-fn get_memory_usage() -> u64 {
-    if let Some(usage) = memory_stats() {
-        usage.physical_mem as u64
-    } else {
-        eprintln!("Couldn't get memory usage.");
-        0
+/// Reads the lines of the file in the format of its extension, malformed rows skipped in the lenient mode are printed.
+fn get_lines<T: Scalar>(file: &str, dat: &DatOptions) -> Option<LineSegments2D<T>> {
+    let path = Path::new(file);
+
+    match LineSegments2D::read(path, dat) {
+        Ok(report) => {
+            print_skipped(&report.errors);
+            Some(report.segments)
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            None
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,143 @@
+use lab03::geometry::snap_rounding::SnapRounding;
+use lab03::{
+    AlgorithmError, AlgorithmOptions, BruteForceHandler, GeoHandler, Intersection,
+    IntersectionAlgorithm, IntersectionResult, Line2D, LineSegments2D, Point2D, Registry,
+    SweepLineHandler, SweepLineOptions,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use ordered_float::OrderedFloat;
+use std::collections::HashSet;
+use std::path::Path;
+
+fn read(file: &str) -> Vec<Line2D> {
+    LineSegments2D::from_dat(Path::new(file)).unwrap().lines
+}
+
+#[test]
+fn handlers_should_agree_on_data_file() {
+    let lines = read("data/s_1000_10.dat");
+
+    let brute_force = BruteForceHandler::new(lines.clone()).run();
+    let sweep_line =
+        SweepLineHandler::new(lines.clone(), SweepLineOptions::degenerate_handling()).run();
+    let geo = GeoHandler::new(lines).run();
+
+    assert_eq!(brute_force.len(), 796);
+    assert_eq!(sweep_line, brute_force);
+    assert_eq!(geo.len(), brute_force.len());
+}
+
+#[test]
+fn crossing_should_be_reported_with_its_point() {
+    let line1 = Line2D::new(0.0, 0.0, 2.0, 2.0);
+    let line2 = Line2D::new(0.0, 2.0, 2.0, 0.0);
+
+    let intersections = BruteForceHandler::new(vec![line1, line2]).run();
+
+    assert_eq!(
+        intersections,
+        HashSet::from([Intersection::Crossing {
+            line1,
+            line2,
+            point: Point2D::new(1.0, 1.0),
+        }])
+    );
+}
+
+#[test]
+fn integer_coordinates_should_give_exact_rational_points() {
+    let line1 = Line2D {
+        start: Point2D { x: 0i64, y: 0 },
+        end: Point2D { x: 3, y: 1 },
+    };
+    let line2 = Line2D {
+        start: Point2D { x: 0i64, y: 1 },
+        end: Point2D { x: 1, y: 0 },
+    };
+
+    let intersections =
+        SweepLineHandler::new(vec![line1, line2], SweepLineOptions::degenerate_handling()).run();
+
+    let quarter = |n: i64| BigRational::new(BigInt::from(n), BigInt::from(4));
+    assert_eq!(
+        intersections.into_iter().collect::<Vec<_>>(),
+        vec![Intersection::Crossing {
+            line1,
+            line2,
+            point: Point2D {
+                x: quarter(3),
+                y: quarter(1),
+            },
+        }]
+    );
+}
+
+/// Algorithm of another crate, which only checks the first line against the others.
+struct FirstLineOnly {
+    lines: Vec<Line2D>,
+}
+
+impl IntersectionAlgorithm<OrderedFloat<f64>> for FirstLineOnly {
+    fn create(lines: Vec<Line2D>, options: &AlgorithmOptions) -> Result<Self, AlgorithmError> {
+        match options.red_count {
+            Some(_) => Err(AlgorithmError::Unsupported("bichromatic mode".to_string())),
+            None => Ok(FirstLineOnly { lines }),
+        }
+    }
+
+    fn intersect(&mut self) -> IntersectionResult<OrderedFloat<f64>> {
+        IntersectionResult::measure(self.lines.len(), || {
+            let first = self.lines[0];
+            self.lines[1..]
+                .iter()
+                .filter_map(|line| first.find_intersection(*line))
+                .collect()
+        })
+    }
+}
+
+#[test]
+fn registry_should_accept_algorithms_of_other_crates() {
+    let mut registry = Registry::default();
+    registry.register::<FirstLineOnly>("first-line", "First Line Only", |_| {});
+    let lines = vec![
+        Line2D::new(0.0, 0.0, 4.0, 0.0),
+        Line2D::new(1.0, -1.0, 1.0, 1.0),
+        Line2D::new(2.0, -1.0, 3.0, 1.0),
+        Line2D::new(0.0, 5.0, 4.0, 5.0),
+    ];
+
+    let algorithm = registry.get("first-line").unwrap();
+    let result = algorithm
+        .create(lines.clone(), &AlgorithmOptions::default())
+        .unwrap()
+        .intersect();
+    let bichromatic = AlgorithmOptions {
+        red_count: Some(1),
+        ..AlgorithmOptions::default()
+    };
+
+    assert_eq!(result.statistics.intersections, 2);
+    assert!(algorithm.create(lines, &bichromatic).is_err());
+}
+
+#[test]
+fn snap_rounded_lines_should_be_written_and_read_again() {
+    let segments = LineSegments2D {
+        lines: read("data/s_1000_1.dat"),
+    };
+    let intersections = SweepLineHandler::new(
+        segments.lines.clone(),
+        SweepLineOptions::degenerate_handling(),
+    )
+    .run();
+    let rounded = SnapRounding::new(1.0).round(&segments, &intersections);
+    let path = std::env::temp_dir().join("lab03_public_api_rounded.dat");
+
+    rounded.to_dat(&path).unwrap();
+    let read_back: LineSegments2D = LineSegments2D::from_dat(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read_back.lines.len(), rounded.lines.len());
+}